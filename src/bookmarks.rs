@@ -0,0 +1,43 @@
+// Viewpoint bookmarks: up to ten saved orientations per solid or hypersolid
+//
+// Slots are numbered like the keys that use them, 1 to 9 and then 0.
+
+use std::collections::HashMap;
+
+use crate::orientation::Orientation;
+
+pub const BOOKMARK_SLOTS: usize = 10;
+
+#[derive(Clone, Debug, Default)]
+pub struct Bookmarks {
+    slots: HashMap<String, [Option<Orientation>; BOOKMARK_SLOTS]>, // By shape name
+}
+
+impl Bookmarks {
+    pub fn save(&mut self, shape: &str, slot: usize, orientation: Orientation) {
+        self.slots.entry(shape.to_owned()).or_default()[slot] = Some(orientation);
+    }
+
+    pub fn get(&self, shape: &str, slot: usize) -> Option<Orientation> {
+        self.slots.get(shape).and_then(|slots| slots[slot])
+    }
+
+    // Key labels of the filled slots for a shape, e.g. "1 4 0"
+    pub fn labels(&self, shape: &str) -> String {
+        let Some(slots) = self.slots.get(shape) else {
+            return String::new();
+        };
+        slots
+            .iter()
+            .enumerate()
+            .filter(|(_, orientation)| orientation.is_some())
+            .map(|(slot, _)| slot_label(slot).to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// Key that selects a slot: slots 0-8 are keys 1-9, slot 9 is key 0
+fn slot_label(slot: usize) -> usize {
+    (slot + 1) % BOOKMARK_SLOTS
+}
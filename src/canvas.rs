@@ -0,0 +1,411 @@
+// Drawing targets for the stereo views
+//
+// The wireframe code only ever clears the background and draws lines (plain or with a
+// color gradient along them), dots, circle outlines and short labels, so it talks to this small trait instead of calling macroquad directly.
+// That lets the same code draw to the window, to an off-screen render target or
+// into a CPU pixel buffer for headless rendering.
+//
+// On screen, lines are not drawn one call at a time: they are gathered into one mesh
+// of quads, each just large enough for the line, its round caps and a pixel of soft
+// edge, and the mesh goes to the GPU in a single draw call when the eye view is done.
+// A small shader works out every pixel's distance to its line and turns it into
+// coverage, the same analytic anti-aliasing the CPU rasterizer uses.
+//
+// The ball-and-stick style hands over triangles in the rotated 3D space of the view
+// instead, together with the view's projection, and they are drawn with a depth test.
+//
+// Drawing can be kept to some of the color channels, which is how the two anaglyph
+// views share one picture: each only writes the channels its eye's filter lets through.
+
+use macroquad::miniquad::{BlendFactor, BlendState, BlendValue, Equation, PassAction};
+use macroquad::prelude::*;
+
+use crate::tubes::SolidMesh;
+
+// Most lines in one batch, so its vertices stay within 16-bit indices; the window's
+// draw call buffers are made large enough to take a full batch at once
+const BATCH_LINES: usize = 16383;
+pub const BATCH_VERTICES: usize = (BATCH_LINES + 1) * 4;
+pub const BATCH_INDICES: usize = (BATCH_LINES + 1) * 6;
+
+// Depth range kept by the projections: from just in front of a perspective view's eye to
+// far behind it, and half a side of a cube this size around an orthographic view's center
+const NEAR_DEPTH: f32 = 0.05;
+const FAR_DEPTH: f32 = 100.0;
+const ORTHOGRAPHIC_DEPTH: f32 = 100.0;
+
+// A view's projection takes a rotated 3D point (x, y, z, 1) to window pixels and depth in
+// the form (pixel x·w, pixel y·w, depth·w, w). The two rows give the pixel coordinates
+// times w, where w is the distance from the eye, z + `distance`.
+pub fn perspective_projection(x: Vec4, y: Vec4, distance: f32) -> Mat4 {
+    let depth_scale = (FAR_DEPTH + NEAR_DEPTH) / (FAR_DEPTH - NEAR_DEPTH);
+    let depth_offset = -2.0 * FAR_DEPTH * NEAR_DEPTH / (FAR_DEPTH - NEAR_DEPTH);
+    let depth = Vec4::new(0.0, 0.0, depth_scale, depth_scale * distance + depth_offset);
+    Mat4::from_cols(x, y, depth, Vec4::new(0.0, 0.0, 1.0, distance)).transpose()
+}
+
+// The same for an orthographic view, where w is always 1 and the rows give the pixels
+pub fn orthographic_projection(x: Vec4, y: Vec4) -> Mat4 {
+    let depth = Vec4::new(0.0, 0.0, 1.0 / ORTHOGRAPHIC_DEPTH, 0.0);
+    Mat4::from_cols(x, y, depth, Vec4::W).transpose()
+}
+
+// Window position of a rotated point
+pub fn project(projection: &Mat4, point: Vec3) -> Vec2 {
+    let projected = *projection * point.extend(1.0);
+    projected.xy() / projected.w
+}
+
+// Color channels drawn into
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channels {
+    All = 0,
+    Red = 1,  // What the red filter (the left eye's) lets through
+    Cyan = 2, // Green and blue, what the cyan filter (the right eye's) lets through
+}
+
+impl Channels {
+    pub const ALL: [Channels; 3] = [Channels::All, Channels::Red, Channels::Cyan];
+
+    // Red, green, blue and alpha, whether each is written
+    pub fn mask(self) -> (bool, bool, bool, bool) {
+        match self {
+            Channels::All => (true, true, true, true),
+            Channels::Red => (true, false, false, false),
+            Channels::Cyan => (false, true, true, false),
+        }
+    }
+}
+
+pub trait LineCanvas {
+    fn clear(&mut self, color: Color);
+    fn line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color);
+    // A line whose color blends from `start_color` at its start to `end_color` at its end
+    fn gradient_line(&mut self, start: Vec2, end: Vec2, thickness: f32, start_color: Color, end_color: Color);
+    fn circle_outline(&mut self, center: Vec2, radius: f32, thickness: f32, color: Color);
+    // Text with its baseline starting at `position`
+    fn text(&mut self, text: &str, position: Vec2, size: f32, color: Color);
+    // A filled disc, drawn as a line of no length with round caps
+    fn dot(&mut self, center: Vec2, radius: f32, color: Color) {
+        self.line(center, center, radius * 2.0, color);
+    }
+    // Lit triangles in rotated 3D space, hiding each other by depth but drawn over
+    // everything before them. `projection` is the view's, as made by the functions above.
+    fn solid(&mut self, mesh: &SolidMesh, projection: &Mat4);
+    // Finish drawing everything queued so far (the end of an eye view)
+    fn flush(&mut self);
+    // Draw only into these channels from now on, leaving the others as they are
+    fn set_channels(&mut self, channels: Channels);
+}
+
+// Each vertex carries its position relative to the line in pixels (along it from the
+// start, and across it) as uv, and the line's length and half thickness in `normal`
+const LINE_VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+attribute vec4 normal;
+
+varying lowp vec4 color;
+varying vec2 offset;
+varying vec2 line;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    offset = texcoord;
+    line = normal.xy;
+}"#;
+
+// Distance to the segment (so round past its ends), turned into coverage over one pixel
+const LINE_FRAGMENT_SHADER: &str = r#"#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+varying lowp vec4 color;
+varying vec2 offset;
+varying vec2 line;
+
+void main() {
+    float beyond = offset.x - clamp(offset.x, 0.0, line.x);
+    float away = length(vec2(beyond, offset.y));
+    float coverage = clamp(line.y + 0.5 - away, 0.0, 1.0);
+    gl_FragColor = vec4(color.rgb, color.a * coverage);
+}"#;
+
+// Solid triangles take their window position from the view's projection, passed in as
+// `Eye`, and only then go through the camera's matrices. Those are affine, so they can
+// be applied before dividing by w. The depth comes straight from the view's projection.
+const SOLID_VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec4 color0;
+
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+uniform mat4 Eye;
+
+void main() {
+    vec4 eye = Eye * vec4(position, 1);
+    vec4 window = Projection * Model * vec4(eye.xy, 0, eye.w);
+    gl_Position = vec4(window.xy, eye.z, window.w);
+    color = color0 / 255.0;
+}"#;
+
+const SOLID_FRAGMENT_SHADER: &str = r#"#version 100
+varying lowp vec4 color;
+
+void main() {
+    gl_FragColor = color;
+}"#;
+
+// Macroquad's own textured shader, for text and circles kept to some channels
+const PLAIN_VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}"#;
+
+const PLAIN_FRAGMENT_SHADER: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = color * texture2D(Texture, uv);
+}"#;
+
+// The materials drawing into one set of channels
+struct ChannelMaterials {
+    line: Material,
+    solid: Material,
+    plain: Option<Material>, // None for all channels, where macroquad's default does the job
+}
+
+impl ChannelMaterials {
+    fn new(channels: Channels) -> Self {
+        let alpha_blend = Some(BlendState::new(
+            Equation::Add,
+            BlendFactor::Value(BlendValue::SourceAlpha),
+            BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+        ));
+        let line = load_material(
+            ShaderSource::Glsl {
+                vertex: LINE_VERTEX_SHADER,
+                fragment: LINE_FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                pipeline_params: PipelineParams {
+                    color_blend: alpha_blend,
+                    color_write: channels.mask(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .expect("line shader failed to compile");
+        let solid = load_material(
+            ShaderSource::Glsl {
+                vertex: SOLID_VERTEX_SHADER,
+                fragment: SOLID_FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                pipeline_params: PipelineParams {
+                    depth_test: Comparison::LessOrEqual,
+                    depth_write: true,
+                    color_write: channels.mask(),
+                    ..Default::default()
+                },
+                uniforms: vec![UniformDesc::new("Eye", UniformType::Mat4)],
+                ..Default::default()
+            },
+        )
+        .expect("solid shader failed to compile");
+        let plain = (channels != Channels::All).then(|| {
+            load_material(
+                ShaderSource::Glsl {
+                    vertex: PLAIN_VERTEX_SHADER,
+                    fragment: PLAIN_FRAGMENT_SHADER,
+                },
+                MaterialParams {
+                    pipeline_params: PipelineParams {
+                        color_blend: alpha_blend,
+                        color_write: channels.mask(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .expect("plain shader failed to compile")
+        });
+        Self { line, solid, plain }
+    }
+}
+
+// Macroquad drawing into the current camera (window or render target). The line mesh
+// is kept between frames, so drawing allocates nothing once it has grown.
+pub struct ScreenCanvas {
+    lines: Mesh,
+    solid_chunk: Mesh, // One chunk of a solid mesh at a time
+    materials: Vec<ChannelMaterials>, // By `Channels`
+    channels: Channels,
+}
+
+impl ScreenCanvas {
+    pub fn new() -> Self {
+        let empty_mesh = || Mesh {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            texture: None,
+        };
+        Self {
+            lines: empty_mesh(),
+            solid_chunk: empty_mesh(),
+            materials: Channels::ALL.iter().map(|&channels| ChannelMaterials::new(channels)).collect(),
+            channels: Channels::All,
+        }
+    }
+
+    fn materials(&self) -> &ChannelMaterials {
+        &self.materials[self.channels as usize]
+    }
+
+    // Draw with macroquad's shapes and text, kept to the channels drawn into
+    fn draw_plain(&mut self, draw: impl FnOnce()) {
+        // Keep the drawing order: queued lines go first
+        self.flush();
+        if let Some(plain) = &self.materials().plain {
+            gl_use_material(plain);
+        }
+        draw();
+        gl_use_default_material();
+    }
+
+    // Queue a quad covering the line and one pixel around it. Colors are set at the
+    // corners so that the blend between them is exact along the line itself.
+    fn push_line(&mut self, start: Vec2, end: Vec2, thickness: f32, start_color: Color, end_color: Color) {
+        if !(start.is_finite() && end.is_finite()) || thickness <= 0.0 {
+            return;
+        }
+        if self.lines.vertices.len() >= BATCH_LINES * 4 {
+            self.flush();
+        }
+        let length = start.distance(end);
+        let direction = (end - start).try_normalize().unwrap_or(Vec2::X);
+        let reach = thickness / 2.0 + 1.0;
+        let (along, across) = (direction * reach, direction.perp() * reach);
+        let color_at = |distance: f32| {
+            let t = if length > 0.0 { distance / length } else { 0.0 };
+            Color::from_vec(start_color.to_vec().lerp(end_color.to_vec(), t).clamp(Vec4::ZERO, Vec4::ONE))
+        };
+        let line = vec4(length, thickness / 2.0, 0.0, 0.0);
+        let corner = |point: Vec2, offset: Vec2, color: Color| Vertex {
+            position: point.extend(0.0),
+            uv: offset,
+            color: color.into(),
+            normal: line,
+        };
+        let (before, after) = (color_at(-reach), color_at(length + reach));
+        let first = self.lines.vertices.len() as u16;
+        self.lines.vertices.extend([
+            corner(start - along + across, vec2(-reach, reach), before),
+            corner(end + along + across, vec2(length + reach, reach), after),
+            corner(end + along - across, vec2(length + reach, -reach), after),
+            corner(start - along - across, vec2(-reach, -reach), before),
+        ]);
+        self.lines.indices.extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+    }
+}
+
+impl LineCanvas for ScreenCanvas {
+    fn clear(&mut self, color: Color) {
+        self.lines.vertices.clear();
+        self.lines.indices.clear();
+        clear_background(color);
+    }
+
+    fn line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        self.push_line(start, end, thickness, color, color);
+    }
+
+    fn gradient_line(&mut self, start: Vec2, end: Vec2, thickness: f32, start_color: Color, end_color: Color) {
+        self.push_line(start, end, thickness, start_color, end_color);
+    }
+
+    fn circle_outline(&mut self, center: Vec2, radius: f32, thickness: f32, color: Color) {
+        self.draw_plain(|| draw_circle_lines(center.x, center.y, radius, thickness, color));
+    }
+
+    fn text(&mut self, text: &str, position: Vec2, size: f32, color: Color) {
+        self.draw_plain(|| {
+            draw_text(text, position.x, position.y, size, color);
+        });
+    }
+
+    fn solid(&mut self, mesh: &SolidMesh, projection: &Mat4) {
+        self.flush();
+        // Draw what is queued, then clear the depth left by the other eye view
+        unsafe {
+            let mut gl = get_internal_gl();
+            gl.flush();
+            let clear = PassAction::Clear {
+                color: None,
+                depth: Some(1.0),
+                stencil: None,
+            };
+            match gl.quad_gl.get_active_render_pass() {
+                Some(pass) => gl.quad_context.begin_pass(Some(pass), clear),
+                None => gl.quad_context.begin_default_pass(clear),
+            }
+            gl.quad_context.end_render_pass();
+        }
+        let material = &self.materials[self.channels as usize].solid;
+        gl_use_material(material);
+        material.set_uniform("Eye", *projection);
+        for (vertices, indices) in mesh.chunks() {
+            self.solid_chunk.vertices.clear();
+            self.solid_chunk.vertices.extend_from_slice(vertices);
+            self.solid_chunk.indices.clear();
+            self.solid_chunk.indices.extend_from_slice(indices);
+            draw_mesh(&self.solid_chunk);
+        }
+        gl_use_default_material();
+    }
+
+    fn flush(&mut self) {
+        if self.lines.vertices.is_empty() {
+            return;
+        }
+        gl_use_material(&self.materials().line);
+        draw_mesh(&self.lines);
+        gl_use_default_material();
+        self.lines.vertices.clear();
+        self.lines.indices.clear();
+    }
+
+    fn set_channels(&mut self, channels: Channels) {
+        if channels != self.channels {
+            self.flush();
+            self.channels = channels;
+        }
+    }
+}
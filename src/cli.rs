@@ -0,0 +1,377 @@
+// Command-line interface (native only)
+//
+// Without a subcommand the options set the initial state of the interactive viewer.
+// `stero3d render` draws one stereogram into a PNG with the CPU rasterizer, so it
+// works on headless machines. Every option maps directly onto a `StereogramViewer`
+// field, so both paths share the same state.
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::canvas::LineCanvas;
+use crate::colormap::{DepthRange, Palette};
+use crate::depth_cue::Fog;
+use crate::tubes::RenderStyle;
+use crate::raster::SoftwareCanvas;
+use crate::scene::Scene;
+use crate::wythoff::WythoffShape;
+use crate::{Hypersolid, PlatonicSolid, StereoLayout, StereogramViewer, VertexMarks};
+
+#[derive(Parser)]
+#[command(name = "stero3d", about = "3D/4D stereogram viewer for Platonic solids and hypersolids")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub view: ViewArgs,
+
+    /// Window size in pixels, as WIDTHxHEIGHT
+    #[arg(long, value_parser = parse_size)]
+    pub window_size: Option<(u32, u32)>,
+
+    /// Seed for the random initial rotation speeds (same seed, same spin)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Keyframe timeline to load (press P to play it)
+    #[arg(long)]
+    pub timeline: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Render a single stereogram image without opening a window
+    Render(RenderArgs),
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    pub view: ViewArgs,
+
+    /// Output PNG file
+    #[arg(long)]
+    pub out: PathBuf,
+
+    /// Image size in pixels, as WIDTHxHEIGHT
+    #[arg(long, value_parser = parse_size, default_value = "600x400")]
+    pub size: (u32, u32),
+}
+
+// Options describing what to show and how, shared by every entry point
+#[derive(Args)]
+pub struct ViewArgs {
+    /// Scene file to load first (the other options override it)
+    #[arg(long)]
+    pub scene: Option<PathBuf>,
+
+    /// Platonic solid to show: tetrahedron, cube, octahedron, dodecahedron, icosahedron
+    #[arg(long, value_parser = parse_solid)]
+    pub solid: Option<PlatonicSolid>,
+
+    /// Hypersolid to show (implies 4D): tesseract, simplex, orthoplex, duoprism-P-Q
+    /// (3 to 16 sides), prism-SOLID, clifford-torus
+    #[arg(long, value_parser = parse_hypersolid)]
+    pub hypersolid: Option<Hypersolid>,
+
+    /// Dimension of the shape shown in 4D mode, 4 to 8 (implies 4D); above 4 the
+    /// hypersolid picks the n-cube, n-simplex or n-orthoplex
+    #[arg(long, value_parser = clap::value_parser!(u8).range(4..=8))]
+    pub dim: Option<u8>,
+
+    /// Uniform polytope from the Wythoff construction, as GROUP-RINGS with one digit per
+    /// node: a3, b3, h3 (e.g. b3-101) or a4, b4, d4, f4, h4 (e.g. h4-1000)
+    #[arg(long, value_parser = parse_uniform)]
+    pub uniform: Option<WythoffShape>,
+
+    /// Highlight cell N of the hypersolid, counting from 1 (4D mode)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub cell: Option<u32>,
+
+    /// Show only the highlighted cell and its neighbors
+    #[arg(long)]
+    pub isolate: bool,
+
+    /// Start in 3D or 4D mode: 3d, 4d
+    #[arg(long, value_parser = ["3d", "4d"])]
+    pub mode: Option<String>,
+
+    /// 3D rotation angles in degrees, as X,Y,Z
+    #[arg(long, value_parser = parse_list::<3>, allow_hyphen_values = true)]
+    pub rot: Option<[f32; 3]>,
+
+    /// 4D rotation angles in degrees, as XY,XZ,YZ,XW,YW,ZW
+    #[arg(long, value_parser = parse_list::<6>, allow_hyphen_values = true)]
+    pub rot4: Option<[f32; 6]>,
+
+    /// 3D rotation speeds in degrees per frame, as X,Y,Z
+    #[arg(long, value_parser = parse_list::<3>, allow_hyphen_values = true)]
+    pub vel: Option<[f32; 3]>,
+
+    /// 4D rotation speeds in degrees per frame, as XY,XZ,YZ,XW,YW,ZW
+    #[arg(long, value_parser = parse_list::<6>, allow_hyphen_values = true)]
+    pub vel4: Option<[f32; 6]>,
+
+    /// Stereo layout: parallel, cross, anaglyph, wiggle
+    #[arg(long, value_parser = parse_layout)]
+    pub layout: Option<StereoLayout>,
+
+    /// Eye separation (0.05 to 0.3)
+    #[arg(long)]
+    pub sep: Option<f32>,
+
+    /// Perspective distance (2 to 20)
+    #[arg(long)]
+    pub distance: Option<f32>,
+
+    /// Use orthographic instead of perspective projection
+    #[arg(long)]
+    pub ortho: bool,
+
+    /// Draw on a black background
+    #[arg(long)]
+    pub dark: bool,
+
+    /// Depth coloring: none, z, w
+    #[arg(long, value_parser = ["none", "z", "w"])]
+    pub coloring: Option<String>,
+
+    /// Colormap of the depth coloring: grayscale, viridis, magma, cividis, turbo
+    #[arg(long, value_parser = parse_palette)]
+    pub palette: Option<Palette>,
+
+    /// What the depth coloring stretches over: frame (the depths on screen) or fixed
+    /// (everything the shape can reach, so colors stay put as it turns)
+    #[arg(long, value_parser = parse_depth_range)]
+    pub depth_range: Option<DepthRange>,
+
+    /// Draw near edges wider and far edges thinner
+    #[arg(long)]
+    pub depth_width: bool,
+
+    /// Fog fading far edges into the background: off, light, medium, dense
+    #[arg(long, value_parser = parse_fog)]
+    pub fog: Option<Fog>,
+
+    /// Marks at the vertices: off, dots (sized by depth), indices or coordinates (dots with labels)
+    #[arg(long, value_parser = parse_vertex_marks)]
+    pub vertex_marks: Option<VertexMarks>,
+
+    /// How edges and vertices are drawn: wireframe (flat lines) or tubes (lit tubes and balls)
+    #[arg(long, value_parser = parse_style)]
+    pub style: Option<RenderStyle>,
+
+    /// Hide the red fusion guide circles
+    #[arg(long)]
+    pub no_guides: bool,
+
+    /// Morph towards the dual shape: 0 is the shape itself, 0.5 rectified, 1 the dual
+    #[arg(long)]
+    pub morph: Option<f32>,
+}
+
+impl ViewArgs {
+    // Copy every option that was given onto the viewer
+    pub fn apply(&self, viewer: &mut StereogramViewer) -> Result<(), String> {
+        if let Some(path) = &self.scene {
+            Scene::load(path)?.apply(viewer)?;
+        }
+        if let Some(solid) = self.solid {
+            viewer.current_solid = solid;
+            viewer.is_4d_mode = false;
+        }
+        if let Some(hypersolid) = self.hypersolid {
+            viewer.current_hypersolid = hypersolid;
+            viewer.is_4d_mode = true;
+        }
+        if let Some(dimension) = self.dim {
+            viewer.set_dimension(dimension as usize);
+            viewer.is_4d_mode = true;
+        }
+        if let Some(shape) = self.uniform {
+            viewer.select_uniform(shape);
+            viewer.is_4d_mode = shape.group.rank() == 4;
+        }
+        if let Some(mode) = self.mode.as_deref() {
+            viewer.is_4d_mode = mode == "4d";
+        }
+        if let Some([x, y, z]) = self.rot {
+            viewer.rotation_x = x.to_radians();
+            viewer.rotation_y = y.to_radians();
+            viewer.rotation_z = z.to_radians();
+        }
+        if let Some([xy, xz, yz, xw, yw, zw]) = self.rot4 {
+            viewer.rotation_xy = xy.to_radians();
+            viewer.rotation_xz = xz.to_radians();
+            viewer.rotation_yz = yz.to_radians();
+            viewer.rotation_xw = xw.to_radians();
+            viewer.rotation_yw = yw.to_radians();
+            viewer.rotation_zw = zw.to_radians();
+        }
+        if let Some([x, y, z]) = self.vel {
+            viewer.rotation_velocity_x = x.to_radians();
+            viewer.rotation_velocity_y = y.to_radians();
+            viewer.rotation_velocity_z = z.to_radians();
+        }
+        if let Some([xy, xz, yz, xw, yw, zw]) = self.vel4 {
+            viewer.rotation_velocity_xy = xy.to_radians();
+            viewer.rotation_velocity_xz = xz.to_radians();
+            viewer.rotation_velocity_yz = yz.to_radians();
+            viewer.rotation_velocity_xw = xw.to_radians();
+            viewer.rotation_velocity_yw = yw.to_radians();
+            viewer.rotation_velocity_zw = zw.to_radians();
+        }
+        if let Some(layout) = self.layout {
+            viewer.stereo_layout = layout;
+        }
+        if let Some(sep) = self.sep {
+            viewer.eye_separation = sep.clamp(0.05, 0.3);
+        }
+        if let Some(distance) = self.distance {
+            viewer.perspective_distance = distance.clamp(2.0, 20.0);
+        }
+        if self.ortho {
+            viewer.orthographic = true;
+        }
+        if self.dark {
+            viewer.dark_background = true;
+        }
+        match self.coloring.as_deref() {
+            Some("none") => {
+                viewer.depth_coloring = false;
+                viewer.w_depth_coloring = false;
+            }
+            Some("z") => {
+                viewer.depth_coloring = true;
+                viewer.w_depth_coloring = false;
+            }
+            Some("w") => {
+                viewer.depth_coloring = false;
+                viewer.w_depth_coloring = true;
+            }
+            _ => {}
+        }
+        if let Some(palette) = self.palette {
+            viewer.palette = palette;
+        }
+        if let Some(range) = self.depth_range {
+            viewer.depth_range = range;
+        }
+        if self.depth_width {
+            viewer.depth_width = true;
+        }
+        if let Some(fog) = self.fog {
+            viewer.fog = fog;
+        }
+        if let Some(marks) = self.vertex_marks {
+            viewer.vertex_marks = marks;
+        }
+        if let Some(style) = self.style {
+            viewer.render_style = style;
+        }
+        if self.no_guides {
+            viewer.show_guides = false;
+        }
+        if let Some(cell) = self.cell {
+            viewer.selected_cell = Some(cell as usize - 1);
+        }
+        if self.isolate {
+            viewer.isolate_cell = true;
+        }
+        if let Some(morph) = self.morph {
+            viewer.morph = morph.clamp(0.0, 1.0);
+        }
+
+        // With the shape settled, check the highlighted cell (from --cell or the scene) is one of its cells
+        if let Some(cell) = viewer.selected_cell {
+            viewer.refresh_cells();
+            let count = viewer.cells.len();
+            if count == 0 {
+                return Err(format!("cell {} can't be highlighted: the {} is not a 4D shape made of cells", cell + 1, viewer.shape_name()));
+            } else if cell >= count {
+                return Err(format!("cell {} is out of range: the {} has {} cells", cell + 1, viewer.shape_name(), count));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Render one stereogram with the CPU rasterizer and save it as a PNG
+pub fn render(args: &RenderArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut viewer = StereogramViewer::new();
+    args.view.apply(&mut viewer)?;
+    if viewer.stereo_layout == StereoLayout::Wiggle {
+        return Err("the wiggle layout is an animation, record it from the viewer instead".into());
+    }
+
+    let (width, height) = args.size;
+    let mut canvas = SoftwareCanvas::new(width as usize, height as usize);
+    canvas.clear(viewer.background_color());
+    viewer.draw_stereo_views(&mut canvas, width as f32, height as f32);
+    canvas.save_png(&args.out)?;
+
+    println!("Saved {} ({}x{}) to {}", viewer.shape_name(), width, height, args.out.display());
+    Ok(())
+}
+
+fn parse_solid(value: &str) -> Result<PlatonicSolid, String> {
+    PlatonicSolid::from_key(value).ok_or_else(|| format!("unknown solid '{}'", value))
+}
+
+fn parse_hypersolid(value: &str) -> Result<Hypersolid, String> {
+    Hypersolid::from_key(value).ok_or_else(|| format!("unknown hypersolid '{}'", value))
+}
+
+fn parse_uniform(value: &str) -> Result<WythoffShape, String> {
+    WythoffShape::from_key(value).ok_or_else(|| format!("unknown uniform polytope '{}' (expected e.g. b4-1001)", value))
+}
+
+fn parse_palette(value: &str) -> Result<Palette, String> {
+    Palette::from_key(value).ok_or_else(|| format!("unknown palette '{}'", value))
+}
+
+fn parse_depth_range(value: &str) -> Result<DepthRange, String> {
+    DepthRange::from_key(value).ok_or_else(|| format!("unknown depth range '{}' (expected frame or fixed)", value))
+}
+
+fn parse_fog(value: &str) -> Result<Fog, String> {
+    Fog::from_key(value).ok_or_else(|| format!("unknown fog '{}' (expected off, light, medium or dense)", value))
+}
+
+fn parse_vertex_marks(value: &str) -> Result<VertexMarks, String> {
+    VertexMarks::from_key(value).ok_or_else(|| format!("unknown vertex marks '{}' (expected off, dots, indices or coordinates)", value))
+}
+
+fn parse_style(value: &str) -> Result<RenderStyle, String> {
+    RenderStyle::from_key(value).ok_or_else(|| format!("unknown style '{}' (expected wireframe or tubes)", value))
+}
+
+fn parse_layout(value: &str) -> Result<StereoLayout, String> {
+    StereoLayout::from_key(value).ok_or_else(|| format!("unknown layout '{}'", value))
+}
+
+// Parse exactly N comma-separated numbers
+fn parse_list<const N: usize>(value: &str) -> Result<[f32; N], String> {
+    let numbers = value
+        .split(',')
+        .map(|part| part.trim().parse::<f32>().map_err(|err| format!("'{}': {}", part, err)))
+        .collect::<Result<Vec<_>, _>>()?;
+    numbers
+        .try_into()
+        .map_err(|numbers: Vec<f32>| format!("expected {} comma-separated values, got {}", N, numbers.len()))
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got '{}'", value))?;
+    let width = width.trim().parse::<u32>().map_err(|err| err.to_string())?;
+    let height = height.trim().parse::<u32>().map_err(|err| err.to_string())?;
+    if width == 0 || height == 0 {
+        return Err("width and height must be positive".into());
+    }
+    Ok((width, height))
+}
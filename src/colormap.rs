@@ -0,0 +1,223 @@
+// Depth coloring: continuous colormaps for the Z or W depth along each edge
+//
+// A depth is first normalized to 0..1 over a range, either the depths actually on
+// screen this frame (full contrast, but the colors shift as the shape turns) or the
+// widest range the shape can ever reach (each depth always has the same color). The
+// normalized value then picks a color from a palette by linear interpolation between
+// evenly spaced samples. Grayscale matches the classic look; the others are the
+// perceptually uniform matplotlib maps, with turbo as an improved rainbow.
+//
+// Part of every map is left out depending on the background, so the far end never
+// disappears into it: on white the darkest end marks the nearest edges (or highest W),
+// on black the brightest end does.
+
+use macroquad::prelude::*;
+
+use crate::StereogramViewer;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Palette {
+    #[default]
+    Grayscale,
+    Viridis,
+    Magma,
+    Cividis,
+    Turbo,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DepthRange {
+    #[default]
+    PerFrame, // Stretched over the depths on screen
+    Fixed, // The shape's whole possible range, independent of its orientation
+}
+
+// Nine evenly spaced samples of each map, from 0 to 1
+const VIRIDIS: [u32; 9] = [0x440154, 0x472d7b, 0x3b528b, 0x2c728e, 0x21908c, 0x27ad81, 0x5dc863, 0xaadc32, 0xfde725];
+const MAGMA: [u32; 9] = [0x000004, 0x1d1147, 0x51127c, 0x822681, 0xb63679, 0xe65164, 0xfb8861, 0xfec287, 0xfcfdbf];
+const CIVIDIS: [u32; 9] = [0x00204d, 0x00336f, 0x39486b, 0x575c6d, 0x707173, 0x8a8779, 0xa69d75, 0xc4b56c, 0xffea46];
+const TURBO: [u32; 9] = [0x30123b, 0x4662d7, 0x36aaf9, 0x1ae4b6, 0x72fe5e, 0xc7ef34, 0xfaba39, 0xf66b19, 0x7a0403];
+
+// Part of each map used on a dark and on a light background
+const DARK_BACKGROUND_SPAN: (f32, f32) = (0.3, 1.0);
+const LIGHT_BACKGROUND_SPAN: (f32, f32) = (0.85, 0.0);
+
+impl Palette {
+    pub const ALL: [Palette; 5] = [
+        Palette::Grayscale,
+        Palette::Viridis,
+        Palette::Magma,
+        Palette::Cividis,
+        Palette::Turbo,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&palette| palette == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Grayscale => "Grayscale",
+            Palette::Viridis => "Viridis",
+            Palette::Magma => "Magma",
+            Palette::Cividis => "Cividis",
+            Palette::Turbo => "Turbo",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Palette::Grayscale => "grayscale",
+            Palette::Viridis => "viridis",
+            Palette::Magma => "magma",
+            Palette::Cividis => "cividis",
+            Palette::Turbo => "turbo",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|palette| palette.key() == key)
+    }
+
+    // Color at `position` (0 to 1) along the whole map
+    fn sample(self, position: f32) -> Color {
+        let samples = match self {
+            Palette::Grayscale => return Color::new(position, position, position, 1.0),
+            Palette::Viridis => &VIRIDIS,
+            Palette::Magma => &MAGMA,
+            Palette::Cividis => &CIVIDIS,
+            Palette::Turbo => &TURBO,
+        };
+        let scaled = position.clamp(0.0, 1.0) * (samples.len() - 1) as f32;
+        let index = (scaled as usize).min(samples.len() - 2);
+        let (low, high) = (Color::from_hex(samples[index]), Color::from_hex(samples[index + 1]));
+        Color::from_vec(low.to_vec().lerp(high.to_vec(), scaled - index as f32))
+    }
+
+    // Color of a normalized depth, where 1 is the end to emphasize (nearest, or highest W)
+    pub fn color(self, emphasis: f32, dark_background: bool) -> Color {
+        let (far, near) = if dark_background { DARK_BACKGROUND_SPAN } else { LIGHT_BACKGROUND_SPAN };
+        self.sample(far + (near - far) * emphasis.clamp(0.0, 1.0))
+    }
+}
+
+impl DepthRange {
+    pub fn next(self) -> Self {
+        match self {
+            DepthRange::PerFrame => DepthRange::Fixed,
+            DepthRange::Fixed => DepthRange::PerFrame,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DepthRange::PerFrame => "Per frame",
+            DepthRange::Fixed => "Fixed",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            DepthRange::PerFrame => "frame",
+            DepthRange::Fixed => "fixed",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        [DepthRange::PerFrame, DepthRange::Fixed].into_iter().find(|range| range.key() == key)
+    }
+}
+
+// Where `value` lies between `low` and `high`, as 0 to 1 (the middle for an empty range)
+pub fn normalize(value: f32, (low, high): (f32, f32)) -> f32 {
+    if high - low > 0.001 {
+        ((value - low) / (high - low)).clamp(0.0, 1.0)
+    } else {
+        0.5
+    }
+}
+
+// Smallest range holding every value
+pub fn range_of(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    values.fold((f32::MAX, f32::MIN), |(low, high), value| (low.min(value), high.max(value)))
+}
+
+// Name of the viewer's depth coloring mode: "none", "z" or "w"
+pub fn coloring_key(viewer: &StereogramViewer) -> &'static str {
+    if viewer.w_depth_coloring {
+        "w"
+    } else if viewer.depth_coloring {
+        "z"
+    } else {
+        "none"
+    }
+}
+
+// (depth_coloring, w_depth_coloring) for a coloring mode name
+pub fn parse_coloring(coloring: &str) -> Result<(bool, bool), String> {
+    match coloring {
+        "none" => Ok((false, false)),
+        "z" => Ok((true, false)),
+        "w" => Ok((false, true)),
+        other => Err(format!("unknown coloring '{}' (expected none, z or w)", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_round_trip() {
+        for palette in Palette::ALL {
+            assert_eq!(Palette::from_key(palette.key()), Some(palette));
+        }
+        for range in [DepthRange::PerFrame, DepthRange::Fixed] {
+            assert_eq!(DepthRange::from_key(range.key()), Some(range));
+        }
+        assert_eq!(Palette::from_key("jet"), None);
+        for coloring in ["none", "z", "w"] {
+            assert!(parse_coloring(coloring).is_ok());
+        }
+        assert!(parse_coloring("x").is_err());
+    }
+
+    #[test]
+    fn next_visits_every_palette() {
+        let mut palette = Palette::default();
+        for expected in Palette::ALL.iter().cycle().skip(1).take(Palette::ALL.len()) {
+            palette = palette.next();
+            assert_eq!(palette, *expected);
+        }
+    }
+
+    #[test]
+    fn maps_run_through_their_samples() {
+        assert_eq!(Palette::Viridis.sample(0.0), Color::from_hex(VIRIDIS[0]));
+        assert_eq!(Palette::Viridis.sample(1.0), Color::from_hex(VIRIDIS[8]));
+        assert_eq!(Palette::Magma.sample(0.5), Color::from_hex(MAGMA[4]));
+        assert_eq!(Palette::Turbo.sample(2.0), Palette::Turbo.sample(1.0));
+        let halfway = Palette::Cividis.sample(1.0 / 16.0).to_vec();
+        let expected = Color::from_hex(CIVIDIS[0]).to_vec().lerp(Color::from_hex(CIVIDIS[1]).to_vec(), 0.5);
+        assert!(halfway.distance(expected) < 1e-6);
+    }
+
+    #[test]
+    fn emphasized_depths_stand_out_from_the_background() {
+        // Black on white, white on black
+        assert_eq!(Palette::Grayscale.color(1.0, false), Color::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(Palette::Grayscale.color(1.0, true), Color::new(1.0, 1.0, 1.0, 1.0));
+        assert_eq!(Palette::Grayscale.color(0.0, true), Palette::Grayscale.sample(DARK_BACKGROUND_SPAN.0));
+        assert_eq!(Palette::Grayscale.color(-1.0, false), Palette::Grayscale.color(0.0, false));
+    }
+
+    #[test]
+    fn normalizing_over_a_range() {
+        assert_eq!(range_of([0.5, -2.0, 3.0].into_iter()), (-2.0, 3.0));
+        assert_eq!(normalize(-2.0, (-2.0, 3.0)), 0.0);
+        assert_eq!(normalize(0.5, (-2.0, 3.0)), 0.5);
+        assert_eq!(normalize(5.0, (-2.0, 3.0)), 1.0);
+        assert_eq!(normalize(7.0, (1.0, 1.0)), 0.5);
+    }
+}
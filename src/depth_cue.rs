@@ -0,0 +1,80 @@
+// Depth cues besides color: line width and fog
+//
+// Both go by how far back a point lies within the Z range of the view (0 at the nearest
+// depth, 1 at the farthest), so they follow the per-frame or fixed range setting just like
+// the coloring, and combine with any coloring mode. With the width cue, near lines are
+// drawn wider and far ones thinner. Fog mixes the background into a color by
+// 1 - e^(-density · farness), the classic exponential fog: it sets in quickly just
+// behind the front of the shape and then more slowly.
+
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Fog {
+    #[default]
+    Off,
+    Light,
+    Medium,
+    Dense,
+}
+
+// Width of a line without the width cue, and at the near and far ends with it
+pub const PLAIN_WIDTH: f32 = 2.0;
+const NEAR_WIDTH: f32 = 4.0;
+const FAR_WIDTH: f32 = 0.75;
+
+impl Fog {
+    pub const ALL: [Fog; 4] = [Fog::Off, Fog::Light, Fog::Medium, Fog::Dense];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&fog| fog == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fog::Off => "Off",
+            Fog::Light => "Light",
+            Fog::Medium => "Medium",
+            Fog::Dense => "Dense",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Fog::Off => "off",
+            Fog::Light => "light",
+            Fog::Medium => "medium",
+            Fog::Dense => "dense",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|fog| fog.key() == key)
+    }
+
+    // How quickly the fog thickens; at the far end it hides 63%, 86% or 98% of the line
+    fn density(self) -> f32 {
+        match self {
+            Fog::Off => 0.0,
+            Fog::Light => 1.0,
+            Fog::Medium => 2.0,
+            Fog::Dense => 4.0,
+        }
+    }
+
+    // `color` seen through the fog at `farness`
+    pub fn apply(self, color: Color, background: Color, farness: f32) -> Color {
+        let amount = 1.0 - (-self.density() * farness.clamp(0.0, 1.0)).exp();
+        Color::from_vec(color.to_vec().lerp(background.to_vec(), amount))
+    }
+}
+
+// Width of a line at `farness`, with or without the width cue
+pub fn width(farness: f32, depth_width: bool) -> f32 {
+    if depth_width {
+        NEAR_WIDTH + (FAR_WIDTH - NEAR_WIDTH) * farness.clamp(0.0, 1.0)
+    } else {
+        PLAIN_WIDTH
+    }
+}
@@ -0,0 +1,443 @@
+// Parametric wireframe geometry shared by the 3D and 4D renderers
+//
+// A mesh is a list of points and the edges between them. The fixed solids come from the
+// const tables in main.rs; everything derived from them (such as the dual morphs and the
+// prisms over the solids) and the parametric 4D families are built here at runtime.
+//
+// Dual morphs walk a polytope through its truncations to the rectified form and on
+// through the truncations of its dual to the dual itself. Truncating to depth `d` cuts
+// every vertex off at a fraction d/2 along its edges: each edge keeps its middle part,
+// and the new points around every old vertex are joined along its vertex figure.
+
+use std::collections::HashSet;
+
+use macroquad::prelude::*;
+
+use crate::Vec4D;
+
+#[derive(Clone, Debug, Default)]
+pub struct Mesh<P> {
+    pub vertices: Vec<P>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl<P: Point> Mesh<P> {
+    pub fn new(vertices: &[P], edges: &[(usize, usize)]) -> Self {
+        Self {
+            vertices: vertices.to_vec(),
+            edges: edges.to_vec(),
+        }
+    }
+
+    // Cut every vertex off at a fraction `depth / 2` along its edges (0 < depth <= 1)
+    pub fn truncated(&self, depth: f32) -> Self {
+        let mut vertices = Vec::with_capacity(self.edges.len() * 2);
+        let mut edges = Vec::new();
+        // New points next to each old vertex, with the neighbor their edge leads to
+        let mut around: Vec<Vec<(usize, usize)>> = vec![Vec::new(); self.vertices.len()];
+
+        for &(start, end) in &self.edges {
+            let (a, b) = (self.vertices[start], self.vertices[end]);
+            let near_start = vertices.len();
+            if depth < 1.0 {
+                vertices.push(a.lerp_to(b, depth * 0.5));
+                vertices.push(b.lerp_to(a, depth * 0.5));
+                around[start].push((near_start, end));
+                around[end].push((near_start + 1, start));
+                // What remains of the original edge
+                edges.push((near_start, near_start + 1));
+            } else {
+                // Rectified: both cuts meet in one point at the middle of the edge
+                vertices.push(a.lerp_to(b, 0.5));
+                around[start].push((near_start, end));
+                around[end].push((near_start, start));
+            }
+        }
+
+        // Two edges of a vertex lie on a common face exactly when their far ends are the
+        // closest pair of neighbors; this holds for every regular polytope
+        for (vertex, ends) in around.iter().enumerate() {
+            let pairs = || {
+                ends.iter()
+                    .enumerate()
+                    .flat_map(move |(i, &first)| ends[i + 1..].iter().map(move |&second| (first, second)))
+            };
+            let neighbor_distance = |(_, a): (usize, usize), (_, b): (usize, usize)| self.vertices[a].distance_to(self.vertices[b]);
+            let closest = pairs().map(|(a, b)| neighbor_distance(a, b)).fold(f32::MAX, f32::min);
+            let tolerance = 1e-3 * self.vertices[vertex].distance_to(self.vertices[ends[0].1]).max(1e-6);
+            for (a, b) in pairs() {
+                if neighbor_distance(a, b) <= closest + tolerance {
+                    edges.push((a.0, b.0));
+                }
+            }
+        }
+
+        Self { vertices, edges }
+    }
+
+    // Distance from the center to the middle of the first edge
+    fn midradius(&self) -> f32 {
+        self.edges.first().map_or(1.0, |&(start, end)| {
+            self.vertices[start].lerp_to(self.vertices[end], 0.5).distance_to(P::ORIGIN)
+        })
+    }
+
+    fn scaled(&self, factor: f32) -> Self {
+        Self {
+            vertices: self.vertices.iter().map(|vertex| vertex.scaled(factor)).collect(),
+            edges: self.edges.clone(),
+        }
+    }
+}
+
+// The shape `t` of the way from `polytope` (t = 0) to `dual` (t = 1): truncations of
+// the polytope, the rectified form at t = 0.5, then truncations of the dual. The dual
+// must be oriented with its vertices above the polytope's facets; it is rescaled so
+// that both meet in the same rectified form halfway.
+pub fn dual_morph<P: Point>(polytope: &Mesh<P>, dual: &Mesh<P>, t: f32) -> Mesh<P> {
+    if t <= 0.0 {
+        return polytope.clone();
+    }
+    let dual = dual.scaled(polytope.midradius() / dual.midradius());
+    if t >= 1.0 {
+        dual
+    } else if t <= 0.5 {
+        polytope.truncated(2.0 * t)
+    } else {
+        dual.truncated(2.0 * (1.0 - t))
+    }
+}
+
+// The p,q-duoprism: every vertex of a p-gon in the XY plane paired with every vertex of
+// a q-gon in the ZW plane. Both polygons have edges of length 2, like the tesseract,
+// which is the 4,4-duoprism.
+pub fn duoprism(p: usize, q: usize) -> Mesh<Vec4D> {
+    let polygon_radius = |sides: usize| 1.0 / (std::f32::consts::PI / sides as f32).sin();
+    torus_grid(p, q, polygon_radius(p), polygon_radius(q))
+}
+
+// The Clifford torus: the product of two circles of equal radius, which lies on the
+// 3-sphere of radius 2 and splits it into two equal solid tori
+pub fn clifford_torus(segments: usize) -> Mesh<Vec4D> {
+    let radius = std::f32::consts::SQRT_2;
+    torus_grid(segments, segments, radius, radius)
+}
+
+// Points (i, j) on a circle in the XY plane times a circle in the ZW plane, joined to
+// their neighbors around either circle
+fn torus_grid(p: usize, q: usize, p_radius: f32, q_radius: f32) -> Mesh<Vec4D> {
+    let angle = |index: usize, sides: usize| std::f32::consts::TAU * index as f32 / sides as f32;
+    let mut vertices = Vec::with_capacity(p * q);
+    let mut edges = Vec::with_capacity(2 * p * q);
+    for i in 0..p {
+        let (sin_i, cos_i) = angle(i, p).sin_cos();
+        for j in 0..q {
+            let (sin_j, cos_j) = angle(j, q).sin_cos();
+            vertices.push(Vec4D::new(p_radius * cos_i, p_radius * sin_i, q_radius * cos_j, q_radius * sin_j));
+            edges.push((i * q + j, (i + 1) % p * q + j));
+            edges.push((i * q + j, i * q + (j + 1) % q));
+        }
+    }
+    Mesh { vertices, edges }
+}
+
+// The uniform prism over a solid: two copies at w = ±1 joined vertex by vertex, with
+// the solid scaled to edges of length 2 so that every edge has the same length
+pub fn prism(base: &Mesh<Vec3>) -> Mesh<Vec4D> {
+    let edge_length = base.edges.first().map_or(2.0, |&(start, end)| base.vertices[start].distance(base.vertices[end]));
+    let scale = 2.0 / edge_length;
+    let count = base.vertices.len();
+    let vertices = [-1.0, 1.0]
+        .iter()
+        .flat_map(|&w| base.vertices.iter().map(move |vertex| Vec4D::new(vertex.x * scale, vertex.y * scale, vertex.z * scale, w)))
+        .collect();
+    let edges = base
+        .edges
+        .iter()
+        .flat_map(|&(start, end)| [(start, end), (start + count, end + count)])
+        .chain((0..count).map(|vertex| (vertex, vertex + count)))
+        .collect();
+    Mesh { vertices, edges }
+}
+
+// Cells of a convex 4-polytope, as the vertex sets of its supporting hyperplanes. Every
+// cell has a corner where three of its edges span it, so trying each vertex with each
+// three of its edges finds them all.
+pub fn cells(mesh: &Mesh<Vec4D>) -> Vec<Vec<usize>> {
+    let points: Vec<[f32; 4]> = mesh.vertices.iter().map(|v| [v.x, v.y, v.z, v.w]).collect();
+    let tolerance = 1e-4 * points.iter().map(|&p| dot_4d(p, p).sqrt()).fold(0.0, f32::max);
+    let mut neighbors = vec![Vec::new(); points.len()];
+    for &(start, end) in &mesh.edges {
+        neighbors[start].push(end);
+        neighbors[end].push(start);
+    }
+
+    let mut seen = HashSet::new();
+    let mut cells = Vec::new();
+    for (corner, around) in neighbors.iter().enumerate() {
+        let origin = points[corner];
+        let direction = |index: usize| std::array::from_fn(|axis| points[index][axis] - origin[axis]);
+        for (i, &a) in around.iter().enumerate() {
+            for (j, &b) in around.iter().enumerate().skip(i + 1) {
+                for &c in &around[j + 1..] {
+                    let normal = normal_4d(direction(a), direction(b), direction(c));
+                    let length = dot_4d(normal, normal).sqrt();
+                    if length < 1e-6 {
+                        continue; // The three edges lie in one plane
+                    }
+                    let heights: Vec<f32> = (0..points.len()).map(|index| dot_4d(normal, direction(index)) / length).collect();
+                    let below = heights.iter().any(|&height| height < -tolerance);
+                    let above = heights.iter().any(|&height| height > tolerance);
+                    if below && above {
+                        continue; // Cuts through the polytope
+                    }
+                    let cell: Vec<usize> = (0..points.len()).filter(|&index| heights[index].abs() <= tolerance).collect();
+                    if seen.insert(cell.clone()) {
+                        cells.push(cell);
+                    }
+                }
+            }
+        }
+    }
+    cells
+}
+
+// Faces of dimension `rank` of a convex polytope of any dimension, as vertex sets: the
+// facets of its facets, and so on down to that rank (2 for the polygons). A
+// k-dimensional face's facets lie on hyperplanes within it that leave all of its vertices
+// on one side, and like the cells above they are found from each corner with each k - 1
+// of its edges.
+pub fn faces(points: &[Vec<f32>], edges: &[(usize, usize)], rank: usize) -> Vec<Vec<usize>> {
+    face_levels(points, edges, rank).pop().unwrap_or_default()
+}
+
+// The faces of every dimension from the facets down to `rank`, facets first, as `faces`
+// finds them on its way down
+pub fn face_levels(points: &[Vec<f32>], edges: &[(usize, usize)], rank: usize) -> Vec<Vec<Vec<usize>>> {
+    let dimension = points.first().map_or(0, Vec::len);
+    let tolerance = 1e-4 * points.iter().map(|point| dot(point, point).sqrt()).fold(0.0, f32::max);
+    let mut neighbors = vec![Vec::new(); points.len()];
+    for &(start, end) in edges {
+        neighbors[start].push(end);
+        neighbors[end].push(start);
+    }
+
+    let mut levels: Vec<Vec<Vec<usize>>> = Vec::new();
+    let mut level = vec![(0..points.len()).collect::<Vec<usize>>()];
+    for face_rank in (rank + 1..=dimension).rev() {
+        let mut seen = HashSet::new();
+        level = level
+            .iter()
+            .flat_map(|face| facets(points, &neighbors, face, face_rank, tolerance))
+            .filter(|facet| seen.insert(facet.clone()))
+            .collect();
+        levels.push(level.clone());
+    }
+    levels
+}
+
+// Distance from `point` to the flat space through the points of `set`
+pub fn distance_to_span(points: &[Vec<f32>], set: &[usize], point: &[f32]) -> f32 {
+    let origin = &points[set[0]];
+    let offset = |other: &[f32]| -> Vec<f32> { other.iter().zip(origin).map(|(x, o)| x - o).collect() };
+    let mut basis: Vec<Vec<f32>> = Vec::new();
+    for &index in &set[1..] {
+        if let Some(axis) = orthonormal_residual(&basis, offset(&points[index]), 1e-5) {
+            basis.push(axis);
+        }
+    }
+    let mut rest = offset(point);
+    for axis in &basis {
+        let along = dot(axis, &rest);
+        rest.iter_mut().zip(axis).for_each(|(value, a)| *value -= along * a);
+    }
+    dot(&rest, &rest).sqrt()
+}
+
+// Facets of a face of dimension `rank`, given by its sorted vertex indices
+fn facets(points: &[Vec<f32>], neighbors: &[Vec<usize>], face: &[usize], rank: usize, tolerance: f32) -> Vec<Vec<usize>> {
+    // Coordinates of the face's vertices within its own affine span
+    let origin = &points[face[0]];
+    let offset = |index: usize| -> Vec<f32> { points[index].iter().zip(origin).map(|(x, o)| x - o).collect() };
+    let mut basis: Vec<Vec<f32>> = Vec::new();
+    for &index in &face[1..] {
+        if let Some(axis) = orthonormal_residual(&basis, offset(index), tolerance) {
+            basis.push(axis);
+        }
+        if basis.len() == rank {
+            break;
+        }
+    }
+    let local: Vec<Vec<f32>> = face.iter().map(|&index| basis.iter().map(|axis| dot(axis, &offset(index))).collect()).collect();
+    let position = |index: usize| face.binary_search(&index).ok();
+
+    let mut found: Vec<Vec<usize>> = Vec::new();
+    for (corner, &vertex) in face.iter().enumerate() {
+        let around: Vec<usize> = neighbors[vertex].iter().filter_map(|&other| position(other)).collect();
+        let directions: Vec<Vec<f32>> = local.iter().map(|point| point.iter().zip(&local[corner]).map(|(x, c)| x - c).collect()).collect();
+        for chosen in combinations(around.len(), rank - 1) {
+            // Edges of a facet found already lead to that facet again
+            let in_facet = |facet: &Vec<usize>| chosen.iter().all(|&pick| facet.binary_search(&face[around[pick]]).is_ok());
+            if found.iter().any(|facet| facet.binary_search(&vertex).is_ok() && in_facet(facet)) {
+                continue;
+            }
+            
+            // Normal within the face: what is left of the axis least in the span of the edges
+            let mut span = Vec::new();
+            for &pick in &chosen {
+                if let Some(axis) = orthonormal_residual(&span, directions[around[pick]].clone(), tolerance) {
+                    span.push(axis);
+                }
+            }
+            if span.len() < rank - 1 {
+                continue; // The edges lie in a smaller space
+            }
+            let outside = |axis: usize| 1.0 - span.iter().map(|spanned| spanned[axis] * spanned[axis]).sum::<f32>();
+            let axis = (0..rank).max_by(|&a, &b| outside(a).total_cmp(&outside(b))).unwrap_or(0);
+            let unit = (0..rank).map(|j| if j == axis { 1.0 } else { 0.0 }).collect();
+            let Some(normal) = orthonormal_residual(&span, unit, 1e-3) else {
+                continue;
+            };
+            let height = |other: usize| dot(&normal, &directions[other]);
+            let (mut below, mut above) = (false, false);
+            let cuts = (0..face.len()).any(|other| {
+                below |= height(other) < -tolerance;
+                above |= height(other) > tolerance;
+                below && above
+            });
+            if cuts {
+                continue; // Cuts through the face
+            }
+            found.push((0..face.len()).filter(|&other| height(other).abs() <= tolerance).map(|other| face[other]).collect());
+        }
+    }
+    found
+}
+
+// Unit part of `vector` at right angles to the orthonormal `basis`, or None if it has
+// next to nothing left
+fn orthonormal_residual(basis: &[Vec<f32>], mut vector: Vec<f32>, tolerance: f32) -> Option<Vec<f32>> {
+    for axis in basis {
+        let along = dot(axis, &vector);
+        vector.iter_mut().zip(axis).for_each(|(value, a)| *value -= along * a);
+    }
+    let length = dot(&vector, &vector).sqrt();
+    (length > tolerance).then(|| vector.iter().map(|value| value / length).collect())
+}
+
+// Every way to choose `count` of the indices 0..n, in increasing order
+fn combinations(n: usize, count: usize) -> Vec<Vec<usize>> {
+    if count > n {
+        return Vec::new();
+    }
+    let mut all = Vec::new();
+    let mut chosen: Vec<usize> = (0..count).collect();
+    loop {
+        all.push(chosen.clone());
+        let Some(slot) = (0..count).rev().find(|&slot| chosen[slot] < n - count + slot) else {
+            return all;
+        };
+        chosen[slot] += 1;
+        for next in slot + 1..count {
+            chosen[next] = chosen[next - 1] + 1;
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn dot_4d(a: [f32; 4], b: [f32; 4]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// Vector perpendicular to three others: the cofactors of the last row of the 4x4
+// matrix whose first rows are the three vectors
+fn normal_4d(a: [f32; 4], b: [f32; 4], c: [f32; 4]) -> [f32; 4] {
+    let minor = |skip: usize| {
+        let columns: Vec<usize> = (0..4).filter(|&column| column != skip).collect();
+        let [p, q, r] = [columns[0], columns[1], columns[2]];
+        a[p] * (b[q] * c[r] - b[r] * c[q]) - a[q] * (b[p] * c[r] - b[r] * c[p]) + a[r] * (b[p] * c[q] - b[q] * c[p])
+    };
+    [-minor(0), minor(1), -minor(2), minor(3)]
+}
+
+// What the geometry code needs from a point type
+pub trait Point: Copy {
+    const ORIGIN: Self;
+
+    fn lerp_to(self, other: Self, t: f32) -> Self;
+    fn distance_to(self, other: Self) -> f32;
+    fn scaled(self, factor: f32) -> Self;
+}
+
+impl Point for Vec3 {
+    const ORIGIN: Self = Vec3::ZERO;
+
+    fn lerp_to(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance_to(self, other: Self) -> f32 {
+        self.distance(other)
+    }
+
+    fn scaled(self, factor: f32) -> Self {
+        self * factor
+    }
+}
+
+impl Point for Vec4D {
+    const ORIGIN: Self = Vec4D::new(0.0, 0.0, 0.0, 0.0);
+
+    fn lerp_to(self, other: Self, t: f32) -> Self {
+        Vec4D::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+            self.z + (other.z - self.z) * t,
+            self.w + (other.w - self.w) * t,
+        )
+    }
+
+    fn distance_to(self, other: Self) -> f32 {
+        let (dx, dy, dz, dw) = (other.x - self.x, other.y - self.y, other.z - self.z, other.w - self.w);
+        (dx * dx + dy * dy + dz * dz + dw * dw).sqrt()
+    }
+
+    fn scaled(self, factor: f32) -> Self {
+        Vec4D::new(self.x * factor, self.y * factor, self.z * factor, self.w * factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wythoff::WythoffShape;
+    use crate::Hypersolid;
+
+    // Mesh of a uniform polychoron from the Wythoff construction, e.g. "f4-1000"
+    fn uniform_mesh(key: &str) -> Mesh<Vec4D> {
+        let polytope = WythoffShape::from_key(key).unwrap().build();
+        let vertices: Vec<Vec4D> = polytope.vertices.iter().map(|p| Vec4D::new(p[0], p[1], p[2], p[3])).collect();
+        Mesh::new(&vertices, &polytope.edges)
+    }
+
+    #[test]
+    fn cells_of_the_regular_polychora() {
+        assert_eq!(cells(&Hypersolid::Tesseract.mesh()).len(), 8);
+        assert_eq!(cells(&Hypersolid::FourSimplex.mesh()).len(), 5);
+        assert_eq!(cells(&Hypersolid::FourOrthoplex.mesh()).len(), 16);
+        assert_eq!(cells(&uniform_mesh("f4-1000")).len(), 24);
+        assert_eq!(cells(&uniform_mesh("h4-1000")).len(), 120);
+        assert_eq!(cells(&uniform_mesh("h4-0001")).len(), 600);
+    }
+
+    #[test]
+    fn cells_have_the_vertices_of_their_polyhedra() {
+        // Cubes, octahedra and dodecahedra
+        assert!(cells(&Hypersolid::Tesseract.mesh()).iter().all(|cell| cell.len() == 8));
+        assert!(cells(&uniform_mesh("f4-1000")).iter().all(|cell| cell.len() == 6));
+        assert!(cells(&uniform_mesh("h4-1000")).iter().all(|cell| cell.len() == 20));
+    }
+}
@@ -0,0 +1,246 @@
+// Vertex inspector: every vertex at each stage of the projection, in the side panel
+//
+// For each vertex the panel lists its coordinates after the rotations in 4D (or up to
+// 8D), where the 3D rotation put it, the factor perspective scales it by and where each
+// eye view drew it, along with the frame's Z and W ranges and the projection scale. A
+// selected vertex is ringed in the stereo views. The list can be saved as CSV: to a new
+// numbered file on native builds, and as a download on the web.
+
+use macroquad::prelude::*;
+
+use crate::{polytope_nd, StereogramViewer};
+
+// Text lines are this far apart; the header above the vertex list takes this much room
+const LINE_HEIGHT: f32 = 15.0;
+const HEADER_HEIGHT: f32 = 125.0;
+
+// Coordinates per line of the list
+const VALUES_PER_LINE: usize = 4;
+
+// One vertex at every stage
+struct VertexRow {
+    rotated: Vec<f32>, // After the rotations above 3D (empty for 3D solids)
+    view: Vec3,        // After the 3D rotation
+    perspective: f32,  // Factor its distance scales it by, 1 in orthographic views
+    screen: Vec<Vec2>, // Where each eye view drew it
+}
+
+// What the last frame computed for every vertex
+pub struct Snapshot {
+    shape: String,
+    dimension: usize,
+    eyes: Vec<&'static str>, // Eye of each view, by the side its camera moved to
+    vertices: Vec<VertexRow>,
+    z_range: (f32, f32),
+    w_range: Option<(f32, f32)>, // Range of the W coordinate colored, above 3D
+    scale: f32,                  // Pixels per unit of the rotated shape before perspective
+    distance: Option<f32>,       // Perspective distance, None in orthographic views
+}
+
+impl Snapshot {
+    // Read the views drawn last frame; empty before anything has been drawn
+    pub fn capture(viewer: &StereogramViewer) -> Self {
+        let views = &viewer.eye_buffers[..viewer.eyes_drawn];
+        let distance = (!viewer.orthographic).then_some(viewer.perspective_distance);
+        let rotated = if viewer.is_4d_mode { viewer.rotated_coordinates() } else { Vec::new() };
+        let view_vertices = views.first().map_or(&[][..], |buffers| &buffers.rotated_vertices[..]);
+        let vertices: Vec<VertexRow> = view_vertices
+            .iter()
+            .enumerate()
+            .map(|(index, &view)| VertexRow {
+                rotated: rotated.get(index).cloned().unwrap_or_default(),
+                view,
+                perspective: distance.map_or(1.0, |distance| 1.0 / (distance + view.z)),
+                screen: views.iter().map(|buffers| buffers.screen_vertices[index]).collect(),
+            })
+            .collect();
+
+        // W as the coloring uses it: the fourth coordinate once the higher ones are projected away
+        let w_of = |point: &Vec<f32>| {
+            if point.len() > 4 {
+                polytope_nd::project_to_3d(point).1
+            } else {
+                point[3]
+            }
+        };
+        let w_values: Vec<f32> = vertices.iter().filter(|row| row.rotated.len() >= 4).map(|row| w_of(&row.rotated)).collect();
+        Self {
+            shape: viewer.shape_name(),
+            dimension: if viewer.is_4d_mode { viewer.dimension } else { 3 },
+            eyes: views
+                .iter()
+                .map(|buffers| match buffers.camera_offset {
+                    offset if offset < 0.0 => "L",
+                    offset if offset > 0.0 => "R",
+                    _ => "C",
+                })
+                .collect(),
+            z_range: range_of(vertices.iter().map(|row| row.view.z)),
+            w_range: (!w_values.is_empty()).then(|| range_of(w_values.iter().copied())),
+            vertices,
+            scale: views.first().map_or(0.0, |buffers| buffers.scale),
+            distance,
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    // One row per vertex after a few commented lines with the frame's ranges and scale
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("# {} ({}D)\n", self.shape, self.dimension);
+        csv.push_str(&format!("# z_range,{},{}\n", self.z_range.0, self.z_range.1));
+        if let Some((low, high)) = self.w_range {
+            csv.push_str(&format!("# w_range,{},{}\n", low, high));
+        }
+        csv.push_str(&format!("# scale,{}\n", self.scale));
+        match self.distance {
+            Some(distance) => csv.push_str(&format!("# perspective_distance,{}\n", distance)),
+            None => csv.push_str("# orthographic\n"),
+        }
+
+        let mut header = vec!["vertex".to_owned()];
+        if self.dimension > 3 {
+            header.extend(polytope_nd::AXIS_NAMES[..self.dimension].iter().map(|axis| axis.to_string()));
+        }
+        header.extend(["view_x", "view_y", "view_z", "perspective"].map(str::to_owned));
+        for eye in &self.eyes {
+            header.extend([format!("{}_x", eye), format!("{}_y", eye)]);
+        }
+        csv.push_str(&header.join(","));
+        csv.push('\n');
+
+        for (index, row) in self.vertices.iter().enumerate() {
+            let mut fields = vec![index.to_string()];
+            fields.extend(row.rotated.iter().map(f32::to_string));
+            fields.extend([row.view.x, row.view.y, row.view.z, row.perspective].map(|value| value.to_string()));
+            for position in &row.screen {
+                fields.extend([position.x.to_string(), position.y.to_string()]);
+            }
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    // Text lines of one vertex's entry in the panel
+    fn vertex_lines(&self, index: usize) -> Vec<String> {
+        let row = &self.vertices[index];
+        let signed = |values: &[f32]| values.iter().map(|value| format!("{:+.2}", value)).collect::<Vec<_>>().join(" ");
+        let mut lines = Vec::new();
+        for (chunk, values) in row.rotated.chunks(VALUES_PER_LINE).enumerate() {
+            let label = if chunk == 0 { format!("{}D", row.rotated.len()) } else { String::new() };
+            lines.push(format!("{:<5}{:<4}{}", if chunk == 0 { format!("#{}", index) } else { String::new() }, label, signed(values)));
+        }
+        let view_label = if lines.is_empty() { format!("#{}", index) } else { String::new() };
+        lines.push(format!("{:<5}{:<4}{}", view_label, "3D", signed(&row.view.to_array())));
+        let screens: Vec<String> = self
+            .eyes
+            .iter()
+            .zip(&row.screen)
+            .map(|(eye, position)| format!("{} {:.0},{:.0}", eye, position.x, position.y))
+            .collect();
+        lines.push(format!("{:<5}x{:.3}  {}", "", row.perspective, screens.join("  ")));
+        lines
+    }
+
+    // Height of one vertex's entry, with a gap below it
+    fn entry_height(&self) -> f32 {
+        let rotated_lines = if self.dimension > 3 { self.dimension.div_ceil(VALUES_PER_LINE) } else { 0 };
+        (rotated_lines + 2) as f32 * LINE_HEIGHT + 6.0
+    }
+
+    // Entries that fit below the header
+    pub fn visible_entries(&self, panel: Rect) -> usize {
+        ((panel.h - HEADER_HEIGHT) / self.entry_height()).floor().max(1.0) as usize
+    }
+
+    // Vertex whose entry is at `point`, with the list scrolled to `scroll`
+    pub fn vertex_at(&self, panel: Rect, scroll: usize, point: Vec2) -> Option<usize> {
+        if !panel.contains(point) || point.y < panel.y + HEADER_HEIGHT - LINE_HEIGHT {
+            return None;
+        }
+        let entry = ((point.y - (panel.y + HEADER_HEIGHT - LINE_HEIGHT)) / self.entry_height()) as usize;
+        let index = scroll + entry;
+        (entry < self.visible_entries(panel) && index < self.vertices.len()).then_some(index)
+    }
+
+    pub fn draw_panel(&self, panel: Rect, selected: Option<usize>, scroll: usize, dark_background: bool) {
+        let title_color = if dark_background { WHITE } else { BLACK };
+        let info_color = if dark_background { YELLOW } else { Color::new(0.5, 0.3, 0.0, 1.0) };
+        let text_color = if dark_background { LIGHTGRAY } else { DARKGRAY };
+        // Opaque, as the help panel may be drawn underneath
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, if dark_background { BLACK } else { WHITE });
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 2.0, title_color);
+        draw_text("INSPECTOR", panel.x + 10.0, panel.y + 25.0, 20.0, title_color);
+
+        let mut info = vec![format!("Z range: {:+.3} to {:+.3}", self.z_range.0, self.z_range.1)];
+        if let Some((low, high)) = self.w_range {
+            info.push(format!("W range: {:+.3} to {:+.3}", low, high));
+        }
+        info.push(match self.distance {
+            Some(distance) => format!("Scale: {:.1} px/unit, distance {:.1}", self.scale, distance),
+            None => format!("Scale: {:.1} px/unit, orthographic", self.scale),
+        });
+        info.push("Click selects, wheel scrolls".to_owned());
+        #[cfg(not(target_arch = "wasm32"))]
+        info.push("F7 - Save as CSV".to_owned());
+        #[cfg(target_arch = "wasm32")]
+        info.push("F7 - Download as CSV".to_owned());
+        let mut line_y = panel.y + 45.0;
+        for line in info {
+            draw_text(&line, panel.x + 10.0, line_y, 14.0, info_color);
+            line_y += 16.0;
+        }
+
+        let entry_height = self.entry_height();
+        let mut entry_y = panel.y + HEADER_HEIGHT;
+        let last = (scroll + self.visible_entries(panel)).min(self.vertices.len());
+        for index in scroll.min(last)..last {
+            let is_selected = selected == Some(index);
+            if is_selected {
+                let highlight = if dark_background { Color::new(0.4, 0.25, 0.0, 0.9) } else { Color::new(1.0, 0.85, 0.6, 0.9) };
+                draw_rectangle(panel.x + 4.0, entry_y - LINE_HEIGHT + 2.0, panel.w - 8.0, entry_height - 4.0, highlight);
+            }
+            for (line, text) in self.vertex_lines(index).iter().enumerate() {
+                draw_text(text, panel.x + 10.0, entry_y + line as f32 * LINE_HEIGHT, 13.0, if is_selected { title_color } else { text_color });
+            }
+            entry_y += entry_height;
+        }
+        if self.vertices.len() > last - scroll.min(last) {
+            let status = format!("{}-{} of {}", scroll.min(last) + 1, last, self.vertices.len());
+            draw_text(&status, panel.x + 10.0, panel.y + panel.h - 8.0, 13.0, info_color);
+        }
+    }
+}
+
+fn range_of(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    values.fold((f32::MAX, f32::MIN), |(low, high), value| (low.min(value), high.max(value)))
+}
+
+// Save the snapshot as a new numbered CSV file, returning its name
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export_csv(snapshot: &Snapshot) -> Result<String, String> {
+    let path = crate::scene::next_numbered_file("inspector", "csv");
+    std::fs::write(&path, snapshot.to_csv()).map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(path.display().to_string())
+}
+
+// Hand the snapshot to the browser as a CSV download (JavaScript plugin in index.html)
+#[cfg(target_arch = "wasm32")]
+pub fn export_csv(snapshot: &Snapshot) -> Result<String, String> {
+    extern "C" {
+        fn stero3d_download_text(name: *const u8, name_length: u32, text: *const u8, text_length: u32);
+    }
+    let (name, text) = ("inspector.csv", snapshot.to_csv());
+    unsafe { stero3d_download_text(name.as_ptr(), name.len() as u32, text.as_ptr(), text.len() as u32) };
+    Ok(name.to_owned())
+}
+
+// Lets the JavaScript plugin check that it matches this build
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub extern "C" fn stero3d_download_crate_version() -> u32 {
+    1
+}
@@ -1,5 +1,46 @@
 use macroquad::prelude::*;
 
+mod bookmarks;
+mod canvas;
+mod colormap;
+mod depth_cue;
+mod geometry;
+mod inspector;
+mod measure;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+mod raster;
+#[cfg(not(target_arch = "wasm32"))]
+mod record;
+mod rotation_preset;
+#[cfg(not(target_arch = "wasm32"))]
+mod scene;
+mod timeline;
+mod tubes;
+mod orientation;
+mod picking;
+mod polytope_nd;
+mod settings;
+mod stats;
+#[cfg(target_arch = "wasm32")]
+mod url_state;
+mod wythoff;
+
+use canvas::{Channels, LineCanvas, ScreenCanvas};
+use colormap::{DepthRange, Palette};
+use depth_cue::Fog;
+use bookmarks::{Bookmarks, BOOKMARK_SLOTS};
+use geometry::Mesh;
+use measure::{Measurement, Radii};
+use orientation::{Orientation, QuaternionPair};
+use picking::Picked;
+use rotation_preset::{PresetKind, RotationPreset};
+use settings::{Settings, SettingsStore};
+use stats::Stats;
+use tubes::{RenderStyle, SolidMesh};
+use wythoff::{CoxeterGroup, WythoffShape};
+
 // 4D vector type for hypersolids
 #[derive(Clone, Copy, Debug)]
 struct Vec4D {
@@ -30,12 +71,23 @@ enum PlatonicSolid {
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum Hypersolid {
-    Tesseract = 0,
-    FourSimplex = 1,
-    FourOrthoplex = 2,
+    Tesseract,
+    FourSimplex,
+    FourOrthoplex,
+    Duoprism(usize, usize), // Product of a p-gon and a q-gon
+    Prism(PlatonicSolid), // A solid extruded into the fourth dimension
+    CliffordTorus, // Wire mesh of the flat torus on the 3-sphere
 }
 
 impl PlatonicSolid {
+    const ALL: [PlatonicSolid; 5] = [
+        PlatonicSolid::Tetrahedron,
+        PlatonicSolid::Cube,
+        PlatonicSolid::Octahedron,
+        PlatonicSolid::Dodecahedron,
+        PlatonicSolid::Icosahedron,
+    ];
+
     fn next(self) -> Self {
         match self {
             PlatonicSolid::Tetrahedron => PlatonicSolid::Cube,
@@ -55,26 +107,390 @@ impl PlatonicSolid {
             PlatonicSolid::Icosahedron => "Icosahedron",
         }
     }
+
+    // Used in the names of the prisms over the solid
+    fn adjective(self) -> &'static str {
+        match self {
+            PlatonicSolid::Tetrahedron => "Tetrahedral",
+            PlatonicSolid::Cube => "Cubic",
+            PlatonicSolid::Octahedron => "Octahedral",
+            PlatonicSolid::Dodecahedron => "Dodecahedral",
+            PlatonicSolid::Icosahedron => "Icosahedral",
+        }
+    }
+
+    // Short lowercase identifier used on the command line
+    fn key(self) -> &'static str {
+        match self {
+            PlatonicSolid::Tetrahedron => "tetrahedron",
+            PlatonicSolid::Cube => "cube",
+            PlatonicSolid::Octahedron => "octahedron",
+            PlatonicSolid::Dodecahedron => "dodecahedron",
+            PlatonicSolid::Icosahedron => "icosahedron",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|solid| solid.key().eq_ignore_ascii_case(key))
+    }
+
+    fn mesh(self) -> Mesh<Vec3> {
+        match self {
+            PlatonicSolid::Tetrahedron => Mesh::new(&TETRAHEDRON_VERTICES, &TETRAHEDRON_EDGES),
+            PlatonicSolid::Cube => Mesh::new(&CUBE_VERTICES, &CUBE_EDGES),
+            PlatonicSolid::Octahedron => Mesh::new(&OCTAHEDRON_VERTICES, &OCTAHEDRON_EDGES),
+            PlatonicSolid::Dodecahedron => Mesh::new(&DODECAHEDRON_VERTICES, &DODECAHEDRON_EDGES),
+            PlatonicSolid::Icosahedron => Mesh::new(&ICOSAHEDRON_VERTICES, &ICOSAHEDRON_EDGES),
+        }
+    }
+
+    fn schlafli(self) -> &'static str {
+        match self {
+            PlatonicSolid::Tetrahedron => "{3,3}",
+            PlatonicSolid::Cube => "{4,3}",
+            PlatonicSolid::Octahedron => "{3,4}",
+            PlatonicSolid::Dodecahedron => "{5,3}",
+            PlatonicSolid::Icosahedron => "{3,5}",
+        }
+    }
+
+    // Reflection group of the solid's symmetries, shared with its dual
+    fn symmetry_group(self) -> CoxeterGroup {
+        match self {
+            PlatonicSolid::Tetrahedron => CoxeterGroup::A3,
+            PlatonicSolid::Cube | PlatonicSolid::Octahedron => CoxeterGroup::B3,
+            PlatonicSolid::Dodecahedron | PlatonicSolid::Icosahedron => CoxeterGroup::H3,
+        }
+    }
+
+    // Solid whose vertices sit above this one's faces
+    fn dual(self) -> Self {
+        match self {
+            PlatonicSolid::Tetrahedron => PlatonicSolid::Tetrahedron,
+            PlatonicSolid::Cube => PlatonicSolid::Octahedron,
+            PlatonicSolid::Octahedron => PlatonicSolid::Cube,
+            PlatonicSolid::Dodecahedron => PlatonicSolid::Icosahedron,
+            PlatonicSolid::Icosahedron => PlatonicSolid::Dodecahedron,
+        }
+    }
 }
 
 impl Hypersolid {
+    // The parametric shapes appear with their default parameters
+    const ALL: [Hypersolid; 6] = [
+        Hypersolid::Tesseract,
+        Hypersolid::FourSimplex,
+        Hypersolid::FourOrthoplex,
+        Hypersolid::Duoprism(DEFAULT_DUOPRISM.0, DEFAULT_DUOPRISM.1),
+        Hypersolid::Prism(PlatonicSolid::Dodecahedron),
+        Hypersolid::CliffordTorus,
+    ];
+
     fn next(self) -> Self {
         match self {
             Hypersolid::Tesseract => Hypersolid::FourSimplex,
             Hypersolid::FourSimplex => Hypersolid::FourOrthoplex,
-            Hypersolid::FourOrthoplex => Hypersolid::Tesseract,
+            Hypersolid::FourOrthoplex => Hypersolid::ALL[3],
+            Hypersolid::Duoprism(..) => Hypersolid::ALL[4],
+            Hypersolid::Prism(_) => Hypersolid::CliffordTorus,
+            Hypersolid::CliffordTorus => Hypersolid::Tesseract,
+        }
+    }
+    
+    // Next of the three regular families, which exist in every dimension
+    fn next_regular(self) -> Self {
+        match self {
+            Hypersolid::Tesseract => Hypersolid::FourSimplex,
+            Hypersolid::FourSimplex => Hypersolid::FourOrthoplex,
+            _ => Hypersolid::Tesseract,
+        }
+    }
+    
+    fn is_regular(self) -> bool {
+        matches!(self, Hypersolid::Tesseract | Hypersolid::FourSimplex | Hypersolid::FourOrthoplex)
+    }
+    
+    fn name(self) -> String {
+        match self {
+            Hypersolid::Tesseract => "Tesseract".to_owned(),
+            Hypersolid::FourSimplex => "4-Simplex".to_owned(),
+            Hypersolid::FourOrthoplex => "4-Orthoplex".to_owned(),
+            Hypersolid::Duoprism(p, q) => format!("{},{}-Duoprism", p, q),
+            Hypersolid::Prism(base) => format!("{} Prism", base.adjective()),
+            Hypersolid::CliffordTorus => "Clifford Torus".to_owned(),
+        }
+    }
+
+    // Short lowercase identifier used on the command line, parameters included
+    fn key(self) -> String {
+        match self {
+            Hypersolid::Tesseract => "tesseract".to_owned(),
+            Hypersolid::FourSimplex => "simplex".to_owned(),
+            Hypersolid::FourOrthoplex => "orthoplex".to_owned(),
+            Hypersolid::Duoprism(p, q) => format!("duoprism-{}-{}", p, q),
+            Hypersolid::Prism(base) => format!("prism-{}", base.key()),
+            Hypersolid::CliffordTorus => "clifford-torus".to_owned(),
+        }
+    }
+
+    // Accepts "duoprism" and "prism" alone for the default parameters
+    fn from_key(key: &str) -> Option<Self> {
+        let key = key.to_ascii_lowercase();
+        if let Some(sides) = key.strip_prefix("duoprism-") {
+            let (p, q) = sides.split_once('-')?;
+            let (p, q) = (p.parse().ok()?, q.parse().ok()?);
+            let valid = |sides: usize| (3..=MAX_POLYGON_SIDES).contains(&sides);
+            return (valid(p) && valid(q)).then_some(Hypersolid::Duoprism(p, q));
+        }
+        if let Some(base) = key.strip_prefix("prism-") {
+            return PlatonicSolid::from_key(base).map(Hypersolid::Prism);
+        }
+        Self::ALL.into_iter().find(|hypersolid| {
+            let full_key = hypersolid.key();
+            full_key == key || full_key.split('-').next() == Some(key.as_str())
+        })
+    }
+
+    fn mesh(self) -> Mesh<Vec4D> {
+        match self {
+            Hypersolid::Tesseract => Mesh::new(&TESSERACT_VERTICES, &TESSERACT_EDGES),
+            Hypersolid::FourSimplex => Mesh::new(&FOUR_SIMPLEX_VERTICES, &FOUR_SIMPLEX_EDGES),
+            Hypersolid::FourOrthoplex => Mesh::new(&FOUR_ORTHOPLEX_VERTICES, &FOUR_ORTHOPLEX_EDGES),
+            Hypersolid::Duoprism(p, q) => geometry::duoprism(p, q),
+            Hypersolid::Prism(base) => geometry::prism(&base.mesh()),
+            Hypersolid::CliffordTorus => geometry::clifford_torus(CLIFFORD_TORUS_SEGMENTS),
+        }
+    }
+
+    // Hypersolid whose vertices sit above this one's cells (the duals of the parametric
+    // shapes are not built)
+    fn dual(self) -> Option<Self> {
+        match self {
+            Hypersolid::Tesseract => Some(Hypersolid::FourOrthoplex),
+            Hypersolid::FourSimplex => Some(Hypersolid::FourSimplex),
+            Hypersolid::FourOrthoplex => Some(Hypersolid::Tesseract),
+            _ => None,
         }
     }
     
+    // Schläfli symbol, None for the Clifford torus, which is not a polytope
+    fn schlafli(self) -> Option<String> {
+        match self {
+            Hypersolid::Tesseract => Some("{4,3,3}".to_owned()),
+            Hypersolid::FourSimplex => Some("{3,3,3}".to_owned()),
+            Hypersolid::FourOrthoplex => Some("{3,3,4}".to_owned()),
+            Hypersolid::Duoprism(p, q) => Some(format!("{{{}}}×{{{}}}", p, q)),
+            Hypersolid::Prism(base) => Some(format!("{}×{{ }}", base.schlafli())),
+            Hypersolid::CliffordTorus => None,
+        }
+    }
+
+    // Symmetry group of the vertex table, by name and order. The torus mesh has those of
+    // its two polygons and the swap between them.
+    fn symmetry(self) -> (String, usize) {
+        let group = |group: CoxeterGroup| (format!("{} {}", group.name(), group.bracket()), group.order());
+        match self {
+            Hypersolid::Tesseract | Hypersolid::FourOrthoplex => group(CoxeterGroup::B4),
+            Hypersolid::FourSimplex => group(CoxeterGroup::A4),
+            Hypersolid::Duoprism(p, q) if p == q => (format!("[[{},2,{}]]", p, q), 8 * p * q),
+            Hypersolid::Duoprism(p, q) => (format!("[{}]×[{}]", p, q), 4 * p * q),
+            Hypersolid::Prism(base) => {
+                let (name, order) = group(base.symmetry_group());
+                (format!("{}×[ ]", name), 2 * order)
+            }
+            Hypersolid::CliffordTorus => {
+                let segments = CLIFFORD_TORUS_SEGMENTS;
+                (format!("[[{},2,{}]]", segments, segments), 8 * segments * segments)
+            }
+        }
+    }
+    
+    // Change the first (or, with `second`, the second) parameter of a parametric shape
+    fn next_parameter(self, second: bool) -> Self {
+        let more_sides = |sides: usize| if sides >= MAX_POLYGON_SIDES { 3 } else { sides + 1 };
+        match self {
+            Hypersolid::Duoprism(p, q) if second => Hypersolid::Duoprism(p, more_sides(q)),
+            Hypersolid::Duoprism(p, q) => Hypersolid::Duoprism(more_sides(p), q),
+            Hypersolid::Prism(base) => Hypersolid::Prism(base.next()),
+            other => other,
+        }
+    }
+}
+
+// Polygon sides of the duoprism first shown, and the most sides either polygon can have
+const DEFAULT_DUOPRISM: (usize, usize) = (5, 8);
+const MAX_POLYGON_SIDES: usize = 16;
+
+// Segments around each of the two circles of the Clifford torus
+const CLIFFORD_TORUS_SEGMENTS: usize = 24;
+
+// How the left and right eye views are arranged on screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StereoLayout {
+    Parallel = 0,  // Left eye view on the left (focus through the screen)
+    CrossEyed = 1, // Left eye view on the right (cross your eyes)
+    Anaglyph = 2,  // Both views overlaid in red/cyan (for red-cyan glasses)
+    Wiggle = 3,    // Left and right views alternating in place (no viewing technique needed)
+}
+
+impl StereoLayout {
+    const ALL: [StereoLayout; 4] = [
+        StereoLayout::Parallel,
+        StereoLayout::CrossEyed,
+        StereoLayout::Anaglyph,
+        StereoLayout::Wiggle,
+    ];
+
+    fn next(self) -> Self {
+        match self {
+            StereoLayout::Parallel => StereoLayout::CrossEyed,
+            StereoLayout::CrossEyed => StereoLayout::Anaglyph,
+            StereoLayout::Anaglyph => StereoLayout::Wiggle,
+            StereoLayout::Wiggle => StereoLayout::Parallel,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            StereoLayout::Parallel => "Parallel",
+            StereoLayout::CrossEyed => "Cross-eyed",
+            StereoLayout::Anaglyph => "Anaglyph",
+            StereoLayout::Wiggle => "Wiggle",
+        }
+    }
+
+    // Short lowercase identifier used on the command line
+    fn key(self) -> &'static str {
+        match self {
+            StereoLayout::Parallel => "parallel",
+            StereoLayout::CrossEyed => "cross",
+            StereoLayout::Anaglyph => "anaglyph",
+            StereoLayout::Wiggle => "wiggle",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.key().eq_ignore_ascii_case(key))
+    }
+}
+
+// Placement of one eye's view within the stereogram area
+#[derive(Clone, Copy, Debug)]
+struct EyeView {
+    camera_offset: f32,   // Horizontal camera shift for this eye
+    screen_offset_x: f32, // Left edge of the view in the stereogram area
+    viewport_width: f32,  // Width of the whole stereogram area
+    viewport_height: f32,
+    tint: Option<Color>,  // Fixed edge color overriding depth coloring (anaglyph)
+}
+
+// How an edge is drawn while a cell of a hypersolid is highlighted
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EdgeEmphasis {
+    Plain,
+    Highlighted, // Edge of the highlighted cell
+    Dimmed,
+    Hidden, // Away from the highlighted cell while isolating it
+}
+
+impl EdgeEmphasis {
+    // Rank of the emphasis when several edges meet at a vertex, the most visible highest
+    fn prominence(self) -> u8 {
+        match self {
+            EdgeEmphasis::Hidden => 0,
+            EdgeEmphasis::Dimmed => 1,
+            EdgeEmphasis::Plain => 2,
+            EdgeEmphasis::Highlighted => 3,
+        }
+    }
+}
+
+// What is drawn at the vertices, on top of the edges
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum VertexMarks {
+    #[default]
+    Off,
+    Dots,        // A dot at each vertex, larger the nearer it is
+    Indices,     // Dots labeled with the vertex numbers
+    Coordinates, // Dots labeled with the coordinates of the unrotated shape
+}
+
+impl VertexMarks {
+    const ALL: [VertexMarks; 4] = [
+        VertexMarks::Off,
+        VertexMarks::Dots,
+        VertexMarks::Indices,
+        VertexMarks::Coordinates,
+    ];
+
+    fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&marks| marks == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
     fn name(self) -> &'static str {
         match self {
-            Hypersolid::Tesseract => "Tesseract",
-            Hypersolid::FourSimplex => "4-Simplex",
-            Hypersolid::FourOrthoplex => "4-Orthoplex",
+            VertexMarks::Off => "Off",
+            VertexMarks::Dots => "Dots",
+            VertexMarks::Indices => "Indices",
+            VertexMarks::Coordinates => "Coordinates",
         }
     }
+
+    // Short lowercase identifier used on the command line
+    fn key(self) -> &'static str {
+        match self {
+            VertexMarks::Off => "off",
+            VertexMarks::Dots => "dots",
+            VertexMarks::Indices => "indices",
+            VertexMarks::Coordinates => "coordinates",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|marks| marks.key() == key)
+    }
 }
 
+// Radius of a vertex dot at the nearest and the farthest depth
+const DOT_NEAR_RADIUS: f32 = 4.5;
+const DOT_FAR_RADIUS: f32 = 2.0;
+
+// Text size of the vertex labels
+const VERTEX_LABEL_SIZE: f32 = 13.0;
+
+// Color of the highlighted cell's edges (the anaglyph views keep their eye colors)
+const CELL_HIGHLIGHT_COLOR: Color = Color::new(1.0, 0.5, 0.0, 1.0);
+
+// Color of the ring around a picked vertex and the line over a picked edge
+const PICK_COLOR: Color = Color::new(1.0, 0.2, 0.8, 1.0);
+
+// Color of the measuring marks and the values beside them
+const MEASURE_COLOR: Color = Color::new(0.0, 0.75, 1.0, 1.0);
+
+// The highlighted cell's edges are this much wider than the others
+const HIGHLIGHT_WIDTH_SCALE: f32 = 1.5;
+
+// Gradient pieces for an edge spanning the whole colormap, one per stretch between its samples
+const GRADIENT_PIECES_PER_MAP: f32 = 8.0;
+
+// Share of the background mixed into dimmed edges
+const DIMMED_EDGE_FADE: f32 = 0.8;
+
+// How far one unit of W shifts a hypersolid's vertices in 3D
+const W_SPREAD: Vec3 = Vec3::new(0.2, 0.2, 0.5);
+
+// Morph cycle length and speed in radians per frame: there and back in eight seconds at 60fps
+const MORPH_CYCLE_FRAMES: usize = 480;
+const MORPH_CYCLE_SPEED: f32 = std::f32::consts::TAU / MORPH_CYCLE_FRAMES as f32;
+
+// Anaglyph eye colors. Each eye's view only goes into the channels its filter lets
+// through (red for the left eye, cyan for the right), where lines have to be bright on
+// a dark background and dark on a light one: so the left eye draws in red and the right
+// in cyan on black, and the other way around on white.
+const ANAGLYPH_RED: Color = Color::new(1.0, 0.0, 0.0, 1.0);
+const ANAGLYPH_CYAN: Color = Color::new(0.0, 1.0, 1.0, 1.0);
+
 // Tetrahedron vertices (4 vertices, 6 edges)
 const TETRAHEDRON_VERTICES: [Vec3; 4] = [
     Vec3::new(1.0, 1.0, 1.0),
@@ -150,10 +566,10 @@ const DODECAHEDRON_EDGES: [(usize, usize); 30] = [
 
 // Icosahedron vertices (12 vertices, 30 edges) - using golden ratio
 const ICOSAHEDRON_VERTICES: [Vec3; 12] = [
-    // Golden ratio rectangles in different planes
-    Vec3::new(0.0, INV_PHI, PHI), Vec3::new(0.0, INV_PHI, -PHI), Vec3::new(0.0, -INV_PHI, PHI), Vec3::new(0.0, -INV_PHI, -PHI),
-    Vec3::new(INV_PHI, PHI, 0.0), Vec3::new(INV_PHI, -PHI, 0.0), Vec3::new(-INV_PHI, PHI, 0.0), Vec3::new(-INV_PHI, -PHI, 0.0),
-    Vec3::new(PHI, 0.0, INV_PHI), Vec3::new(-PHI, 0.0, INV_PHI), Vec3::new(PHI, 0.0, -INV_PHI), Vec3::new(-PHI, 0.0, -INV_PHI),
+    // Three golden rectangles (1 by phi) in different planes
+    Vec3::new(0.0, 1.0, PHI), Vec3::new(0.0, 1.0, -PHI), Vec3::new(0.0, -1.0, PHI), Vec3::new(0.0, -1.0, -PHI),
+    Vec3::new(1.0, PHI, 0.0), Vec3::new(1.0, -PHI, 0.0), Vec3::new(-1.0, PHI, 0.0), Vec3::new(-1.0, -PHI, 0.0),
+    Vec3::new(PHI, 0.0, 1.0), Vec3::new(-PHI, 0.0, 1.0), Vec3::new(PHI, 0.0, -1.0), Vec3::new(-PHI, 0.0, -1.0),
 ];
 
 const ICOSAHEDRON_EDGES: [(usize, usize); 30] = [
@@ -187,13 +603,14 @@ const TESSERACT_EDGES: [(usize, usize); 32] = [
     (0, 8), (1, 9), (2, 10), (3, 11), (4, 12), (5, 13), (6, 14), (7, 15),
 ];
 
-// 4-Simplex (4D tetrahedron) - 5 vertices, 10 edges
+// 4-Simplex (4D tetrahedron) - 5 vertices, 10 edges. A regular tetrahedron at W = 1/√5
+// and the apex at W = -4/√5 put every edge at 2√2 and the center at the origin.
 const FOUR_SIMPLEX_VERTICES: [Vec4D; 5] = [
-    Vec4D::new(1.0, 1.0, 1.0, 1.0),
-    Vec4D::new(-1.0, -1.0, 1.0, 1.0),
-    Vec4D::new(-1.0, 1.0, -1.0, 1.0),
-    Vec4D::new(1.0, -1.0, -1.0, 1.0),
-    Vec4D::new(0.0, 0.0, 0.0, -1.0),
+    Vec4D::new(1.0, 1.0, 1.0, 0.447_213_6),
+    Vec4D::new(-1.0, -1.0, 1.0, 0.447_213_6),
+    Vec4D::new(-1.0, 1.0, -1.0, 0.447_213_6),
+    Vec4D::new(1.0, -1.0, -1.0, 0.447_213_6),
+    Vec4D::new(0.0, 0.0, 0.0, -1.788_854_4),
 ];
 
 const FOUR_SIMPLEX_EDGES: [(usize, usize); 10] = [
@@ -220,6 +637,143 @@ const FOUR_ORTHOPLEX_EDGES: [(usize, usize); 24] = [
     (5, 6), (5, 7),
 ];
 
+// Working buffers of one eye view, kept from frame to frame so that drawing the
+// wireframe allocates nothing once they have grown to the shape's size
+#[derive(Clone, Default)]
+struct EyeBuffers {
+    rotated_vertices: Vec<Vec3>,
+    screen_vertices: Vec<Vec2>, // Where the view put each vertex, also used for picking
+    edge_order: Vec<(f32, usize)>, // (avg_z, edge index) of the edges drawn, in drawing order
+    vertex_order: Vec<(f32, usize)>, // (z, vertex index) of the vertex dots, farthest first
+    solid: SolidMesh, // Tubes and balls in the ball-and-stick style
+    camera_offset: f32, // Sideways camera shift the view was drawn with
+    scale: f32, // Pixels per unit of the rotated shape, before perspective
+    center: Vec2, // Where the view put the shape's center
+}
+
+// Identifies the mesh shown: the shape, how far it is morphed and the dimension
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ShapeKey {
+    is_4d_mode: bool,
+    dimension: usize,
+    solid: PlatonicSolid,
+    hypersolid: Hypersolid,
+    uniform: Option<WythoffShape>,
+    morph: f32,
+}
+
+// What every eye view of a frame draws, worked out once before them. The mesh is kept
+// until the shape, its morph or the dimension changes, and the rest is refilled in place
+// each frame, so that nothing is allocated once the buffers have grown to the shape.
+#[derive(Clone, Default)]
+struct FrameShape {
+    key: Option<ShapeKey>,
+    mesh: Mesh<Vec<f32>>, // The shape in its own dimension, before any rotation
+    radius: f32, // Distance of the farthest vertex from the center
+    vertices_3d: Vec<Vec3>, // After the rotations above 3D and the projection down to 3D
+    w_values: Vec<f32>, // W of each vertex after the rotations, for W-depth coloring
+    emphasis: Vec<EdgeEmphasis>, // How each edge is drawn while a cell is highlighted
+    vertex_emphasis: Vec<EdgeEmphasis>, // The emphasis of each vertex's most visible edge
+    rotated: Vec<f32>, // Working buffer for a vertex above 4D
+    in_cell: Vec<bool>, // Working buffer: the vertices of the highlighted cell
+    neighbors: Vec<usize>, // Working buffer: the cells sharing a face with it
+}
+
+impl FrameShape {
+    // Bring everything up to the viewer's shape and rotations above 3D
+    fn refresh(&mut self, viewer: &StereogramViewer) {
+        let key = ShapeKey {
+            is_4d_mode: viewer.is_4d_mode,
+            dimension: viewer.dimension,
+            solid: viewer.current_solid,
+            hypersolid: viewer.current_hypersolid,
+            uniform: viewer.uniform_shown().map(|&(shape, _)| shape),
+            morph: viewer.morph,
+        };
+        if self.key != Some(key) {
+            self.mesh = viewer.coordinate_mesh();
+            let length = |vertex: &Vec<f32>| vertex.iter().map(|value| value * value).sum::<f32>().sqrt();
+            self.radius = self.mesh.vertices.iter().map(length).fold(0.0, f32::max);
+            self.key = Some(key);
+        }
+
+        // Bring the shape down to 3D, keeping each vertex's W coordinate for coloring
+        self.vertices_3d.clear();
+        self.w_values.clear();
+        if !viewer.is_4d_mode {
+            self.vertices_3d.extend(self.mesh.vertices.iter().map(|vertex| Vec3::from_slice(vertex)));
+        } else if viewer.shows_higher_dimension() {
+            // Rotate the polytope in all of its planes and project it to 3D one dimension at a time
+            for vertex in &self.mesh.vertices {
+                polytope_nd::rotate_into(vertex, &viewer.nd_rotation, &mut self.rotated);
+                let (vertex_3d, w) = polytope_nd::project_to_3d(&self.rotated);
+                self.vertices_3d.push(vertex_3d);
+                self.w_values.push(w);
+            }
+        } else {
+            // Apply 4D rotations in all 6 planes and project to 3D space, using a smaller
+            // distance for 4D objects to prevent clustering (even smaller in perspective)
+            let w_distance = if viewer.orthographic { 1.0 } else { 1.5 };
+            for vertex in &self.mesh.vertices {
+                let rotated = viewer.apply_4d_rotations(Vec4D::new(vertex[0], vertex[1], vertex[2], vertex[3]));
+                self.vertices_3d.push(viewer.project_4d_to_3d(rotated, w_distance));
+                self.w_values.push(rotated.w);
+            }
+        }
+        self.refresh_emphasis(viewer);
+    }
+
+    // How to draw each edge when a cell is highlighted: the cell's own edges stand out,
+    // and the rest is dimmed, or hidden apart from the neighboring cells when isolating.
+    // A vertex takes the emphasis of its most visible edge, so with all of its edges
+    // hidden it is left out too.
+    fn refresh_emphasis(&mut self, viewer: &StereogramViewer) {
+        let edges = &self.mesh.edges;
+        self.emphasis.clear();
+        self.vertex_emphasis.clear();
+        let Some(cell) = viewer.selected_cell.filter(|_| viewer.cells_shown()).and_then(|cell| viewer.cells.get(cell)) else {
+            self.emphasis.resize(edges.len(), EdgeEmphasis::Plain);
+            self.vertex_emphasis.resize(self.mesh.vertices.len(), EdgeEmphasis::Plain);
+            return;
+        };
+        self.in_cell.clear();
+        self.in_cell.resize(self.mesh.vertices.len(), false);
+        cell.iter().for_each(|&vertex| self.in_cell[vertex] = true);
+        // Neighbors share a face, so at least three vertices
+        let in_cell = &self.in_cell;
+        self.neighbors.clear();
+        self.neighbors.extend(
+            viewer
+                .cells
+                .iter()
+                .enumerate()
+                .filter(|&(_, other)| other != cell && other.iter().filter(|&&vertex| in_cell[vertex]).count() >= 3)
+                .map(|(index, _)| index),
+        );
+        let neighbors = &self.neighbors;
+        self.emphasis.extend(edges.iter().map(|&(start, end)| {
+            if in_cell[start] && in_cell[end] {
+                EdgeEmphasis::Highlighted
+            } else if !viewer.isolate_cell {
+                EdgeEmphasis::Dimmed
+            } else if neighbors.iter().any(|&other| viewer.cells[other].contains(&start) && viewer.cells[other].contains(&end)) {
+                EdgeEmphasis::Plain
+            } else {
+                EdgeEmphasis::Hidden
+            }
+        }));
+        self.vertex_emphasis.resize(self.mesh.vertices.len(), EdgeEmphasis::Hidden);
+        for (&(start, end), &edge_emphasis) in edges.iter().zip(&self.emphasis) {
+            for index in [start, end] {
+                if edge_emphasis.prominence() > self.vertex_emphasis[index].prominence() {
+                    self.vertex_emphasis[index] = edge_emphasis;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
 struct StereogramViewer {
     rotation_x: f32,
     rotation_y: f32,
@@ -246,18 +800,60 @@ struct StereogramViewer {
     show_guides: bool,
     depth_coloring: bool,
     w_depth_coloring: bool,
+    palette: Palette, // Colormap of the depth coloring
+    depth_range: DepthRange, // What the depth coloring stretches over
+    depth_legend: Option<(f32, f32)>, // Depth range colored in the last frame, for the legend
+    depth_width: bool, // Near edges drawn wider, far ones thinner
+    fog: Fog, // Far edges fading into the background
+    vertex_marks: VertexMarks, // Dots and labels at the vertices
+    render_style: RenderStyle, // Flat wireframe, or lit tubes and balls
     show_ui: bool,
     dark_background: bool,
     orthographic: bool,
     current_solid: PlatonicSolid,
     current_hypersolid: Hypersolid,
     is_4d_mode: bool, // Toggle between 3D and 4D modes
+    dimension: usize, // Dimension shown in 4D mode; above 4 the hypersolid picks the polytope family
+    nd_rotation: Vec<f32>, // Plane angles above 4D, in `polytope_nd::planes` order
+    nd_rotation_velocity: Vec<f32>,
+    stereo_layout: StereoLayout,
+    wiggle_rate: f32, // Full left-right-left wiggle cycles per second
+    wiggle_in_between: usize, // Interpolated views between the two eye positions
+    wiggle_clock: f32, // Seconds of wiggle animation shown so far
+    bookmarks: Bookmarks, // Saved orientations of each shape
+    orientation_transition: Option<orientation::Transition>, // Running glide to a recalled bookmark
+    timeline: timeline::Timeline, // Keyframes of a guided animation
+    timeline_clock: Option<f32>, // Seconds into the timeline while it plays
+    morph: f32, // 0 shows the shape itself, 1 its dual, 0.5 the rectified form between them
+    morph_animating: bool, // Morph back and forth on its own
+    morph_phase: f32, // Position in the back-and-forth morph cycle, in radians
+    rotation_preset: RotationPreset, // Simple, double or isoclinic 4D rotation replacing the plane speeds
+    preset_rotation: Option<QuaternionPair>, // The 4D orientation the preset last turned, while the plane angles still show it
+    show_rotation_presets: bool,
+    uniform: Option<(WythoffShape, wythoff::Polytope)>, // Uniform polytope shown instead of the regular shape of its rank
+    show_uniform_panel: bool,
+    cells: Vec<Vec<usize>>, // Vertex sets of the cells of the hypersolid shown (none above 4D or for the torus)
+    cells_key: String, // Key of the shape `cells` belongs to, empty outside 4D
+    selected_cell: Option<usize>, // Cell highlighted in 4D mode
+    isolate_cell: bool, // Hide everything but the highlighted cell and its neighbors
+    eye_buffers: Vec<EyeBuffers>, // Working buffers of each eye view, kept between frames
+    eyes_drawn: usize, // Eye views drawn last frame, the first ones in `eye_buffers`
+    frame_shape: FrameShape, // The shape the eye views drew last frame
     show_3d_controls: bool,
-    show_4d_controls: bool,
     dragging_slider: Option<usize>, // None, Some(0) for X, Some(1) for Y, Some(2) for Z
     dragging_angle_slider: Option<usize>, // None, Some(0) for X angle, Some(1) for Y angle, Some(2) for Z angle
-    dragging_4d_slider: Option<usize>, // None, Some(0-5) for 4D rotation planes
-    debug_printed: bool, // Flag to only print debug info once per pause
+    dragging_4d_slider: Option<usize>, // None, or the index of a 4D rotation plane (up to 27 in 8D)
+    show_inspector: bool, // Vertex inspector in the side panel instead of the help
+    help_page: usize, // Page of the help panel shown, counted on past the last
+    picked: Option<Picked>, // Vertex or edge picked with the mouse or in the inspector
+    faces: Vec<Vec<usize>>, // Vertex sets of the faces of the shape shown, found when something is picked
+    faces_key: String, // Shape `faces` belongs to, see `faces_shape_key`
+    measurement: Measurement, // Vertices or faces marked for measuring with Shift+click
+    show_radii: bool, // Circumradius and inradius written by the center
+    radii: Option<(String, Radii)>, // Radii of the shape with this `faces_shape_key`, once worked out
+    show_stats: bool, // Statistics panel at the upper right
+    stats: Option<Stats>, // Statistics of the shape last shown with the panel, kept until it changes
+    inspector_scroll: usize, // First vertex listed in the inspector
 }
 
 impl StereogramViewer {
@@ -288,6 +884,13 @@ impl StereogramViewer {
             show_guides: true,
             depth_coloring: true,
             w_depth_coloring: false,
+            palette: Palette::default(), // Grayscale
+            depth_range: DepthRange::default(), // Per frame
+            depth_width: false,
+            fog: Fog::default(), // Off
+            vertex_marks: VertexMarks::default(), // Off
+            render_style: RenderStyle::default(), // Wireframe
+            depth_legend: None,
             #[cfg(target_arch = "wasm32")]
             show_ui: false, // Web users see HTML instructions, so hide UI by default
             #[cfg(not(target_arch = "wasm32"))]
@@ -297,72 +900,618 @@ impl StereogramViewer {
             current_solid: PlatonicSolid::Cube, // Default to cube
             current_hypersolid: Hypersolid::Tesseract, // Default to tesseract
             is_4d_mode: false, // Start in 3D mode
+            dimension: 4, // The hypersolids themselves
+            nd_rotation: Vec::new(),
+            nd_rotation_velocity: Vec::new(),
+            stereo_layout: StereoLayout::Parallel, // Classic side-by-side parallel view
+            wiggle_rate: 3.0, // Three full wiggles per second
+            wiggle_in_between: 0, // Plain two-view wiggle
+            wiggle_clock: 0.0,
+            bookmarks: Bookmarks::default(), // No bookmarks yet
+            orientation_transition: None, // Not gliding to a bookmark
+            timeline: timeline::Timeline::default(), // No keyframes yet
+            timeline_clock: None, // Timeline not playing
+            morph: 0.0, // Undistorted shape
+            morph_animating: false,
+            morph_phase: 0.0,
+            rotation_preset: RotationPreset::default(), // Plane speeds drive the rotation
+            preset_rotation: None,
+            show_rotation_presets: false, // Preset panel hidden
+            uniform: None, // Regular shapes
+            show_uniform_panel: false,
+            cells: Vec::new(), // Found on the first frame
+            cells_key: String::new(),
+            selected_cell: None, // No cell highlighted
+            isolate_cell: false,
+            eye_buffers: Vec::new(),
+            frame_shape: FrameShape::default(),
+            eyes_drawn: 0,
             show_3d_controls: false, // Default to off
-            show_4d_controls: false, // Default to off
             dragging_slider: None, // No slider being dragged initially
             dragging_angle_slider: None, // No angle slider being dragged initially
             dragging_4d_slider: None, // No 4D slider being dragged initially
-            debug_printed: false, // No debug output printed yet
+            show_inspector: false,
+            help_page: 0,
+            picked: None,
+            faces: Vec::new(),
+            faces_key: String::new(),
+            measurement: Measurement::default(),
+            show_radii: false,
+            radii: None,
+            show_stats: false,
+            stats: None,
+            inspector_scroll: 0,
         }
     }
 
-    fn update(&mut self) {
+    // Move the animation on by one frame that took `seconds`
+    fn update(&mut self, seconds: f32) {
         if !self.is_paused {
-            if self.is_4d_mode {
-                // Update 4D rotations
-                self.rotation_xy += self.rotation_velocity_xy;
-                self.rotation_xz += self.rotation_velocity_xz;
-                self.rotation_yz += self.rotation_velocity_yz;
-                self.rotation_xw += self.rotation_velocity_xw;
-                self.rotation_yw += self.rotation_velocity_yw;
-                self.rotation_zw += self.rotation_velocity_zw;
-            } else {
-                // Update 3D rotations
-                self.rotation_x += self.rotation_velocity_x;
-                self.rotation_y += self.rotation_velocity_y;
-                self.rotation_z += self.rotation_velocity_z;
+            self.advance(1.0);
+            self.advance_timeline(seconds);
+        }
+    }
+
+    // Advance the active rotations by a number of frame steps (independent of real time)
+    fn advance(&mut self, steps: f32) {
+        // A playing timeline replaces the constant rotation speeds and the morph cycle
+        if self.timeline_clock.is_some() {
+            return;
+        }
+        if self.morph_animating {
+            // Ease in and out of both ends of the morph
+            self.morph_phase = (self.morph_phase + steps * MORPH_CYCLE_SPEED) % std::f32::consts::TAU;
+            self.morph = (1.0 - self.morph_phase.cos()) * 0.5;
+        }
+        
+        if self.shows_higher_dimension() {
+            for (angle, velocity) in self.nd_rotation.iter_mut().zip(&self.nd_rotation_velocity) {
+                *angle += velocity * steps;
             }
+        } else if self.is_4d_mode && self.rotation_preset.is_active() {
+            // Compose the preset's constant 4D rotation onto the current orientation, kept
+            // as a quaternion pair; the plane angles only follow it
+            let (left, right) = self.rotation_preset.step_pair(steps);
+            let mut pair = QuaternionPair::resume(self.preset_rotation, self.plane_angles());
+            pair.rotate(left, right);
+            [
+                self.rotation_xy,
+                self.rotation_xz,
+                self.rotation_yz,
+                self.rotation_xw,
+                self.rotation_yw,
+                self.rotation_zw,
+            ] = pair.angles();
+            self.preset_rotation = Some(pair);
+        } else if self.is_4d_mode {
+            // Update 4D rotations
+            self.rotation_xy += self.rotation_velocity_xy * steps;
+            self.rotation_xz += self.rotation_velocity_xz * steps;
+            self.rotation_yz += self.rotation_velocity_yz * steps;
+            self.rotation_xw += self.rotation_velocity_xw * steps;
+            self.rotation_yw += self.rotation_velocity_yw * steps;
+            self.rotation_zw += self.rotation_velocity_zw * steps;
+        } else {
+            // Update 3D rotations
+            self.rotation_x += self.rotation_velocity_x * steps;
+            self.rotation_y += self.rotation_velocity_y * steps;
+            self.rotation_z += self.rotation_velocity_z * steps;
+        }
+    }
+
+    // Move a playing timeline on by `seconds`, of real time on screen or of the
+    // recording's frame rate when recording, and stop it at its end
+    fn advance_timeline(&mut self, seconds: f32) {
+        if let Some(clock) = self.timeline_clock {
+            let clock = clock + seconds;
+            let timeline = std::mem::take(&mut self.timeline);
+            timeline.apply_at(clock, self);
+            self.timeline_clock = (clock < timeline.duration()).then_some(clock);
+            self.timeline = timeline;
         }
     }
+
+    // Play the keyframe timeline from its start
+    fn play_timeline(&mut self) {
+        if self.timeline.is_empty() {
+            return;
+        }
+        let timeline = std::mem::take(&mut self.timeline);
+        timeline.apply_at(0.0, self);
+        self.timeline = timeline;
+        self.timeline_clock = Some(0.0);
+        self.orientation_transition = None;
+        self.is_paused = false;
+    }
     
-    fn get_vertices(&self) -> &[Vec3] {
-        match self.current_solid {
-            PlatonicSolid::Tetrahedron => &TETRAHEDRON_VERTICES,
-            PlatonicSolid::Cube => &CUBE_VERTICES,
-            PlatonicSolid::Octahedron => &OCTAHEDRON_VERTICES,
-            PlatonicSolid::Dodecahedron => &DODECAHEDRON_VERTICES,
-            PlatonicSolid::Icosahedron => &ICOSAHEDRON_VERTICES,
+    // Start or stop the automatic morph, continuing from the current morph amount
+    fn toggle_morph_animation(&mut self) {
+        self.morph_animating = !self.morph_animating;
+        self.morph_phase = (1.0 - 2.0 * self.morph).clamp(-1.0, 1.0).acos();
+    }
+    
+    // Remember the current orientation of the shown shape in a bookmark slot
+    fn save_bookmark(&mut self, slot: usize) {
+        // Orientations hold the 3D and 4D angles only
+        if self.shows_higher_dimension() {
+            return;
+        }
+        self.bookmarks.save(&self.shape_name(), slot, Orientation::from_viewer(self));
+    }
+
+    // Start gliding to a bookmarked orientation; the animation pauses so the view stays there
+    fn recall_bookmark(&mut self, slot: usize) {
+        if let Some(target) = self.bookmarks.get(&self.shape_name(), slot) {
+            self.orientation_transition = Some(orientation::Transition::new(Orientation::from_viewer(self), target));
+            self.is_paused = true;
+        }
+    }
+
+    // Advance a running bookmark transition by `dt` seconds
+    fn update_transition(&mut self, dt: f32) {
+        // Dragging an angle slider takes over from the transition
+        if self.dragging_angle_slider.is_some() || self.dragging_4d_slider.is_some() {
+            self.orientation_transition = None;
+        }
+        if let Some(transition) = self.orientation_transition.as_mut() {
+            let (orientation, finished) = transition.step(dt);
+            orientation.apply(self);
+            if finished {
+                self.orientation_transition = None;
+            }
+        }
+    }
+
+    // Camera offset of the wiggle view shown at the current wiggle clock.
+    // The view sweeps from the left eye to the right eye and back again,
+    // stopping at each interpolated in-between position along the way.
+    fn wiggle_camera_offset(&self) -> f32 {
+        let views = 2 + self.wiggle_in_between; // Distinct camera positions, both eyes included
+        let sweep = 2 * (views - 1); // Positions shown per full left-right-left cycle
+        let position = (self.wiggle_clock * self.wiggle_rate * sweep as f32) as usize % sweep;
+        let index = if position < views { position } else { sweep - position };
+        let t = index as f32 / (views - 1) as f32;
+        -self.eye_separation + 2.0 * self.eye_separation * t
+    }
+
+    // Velocities of the rotations that `advance` animates in the current mode
+    #[cfg(not(target_arch = "wasm32"))]
+    fn active_velocities_mut(&mut self) -> Vec<&mut f32> {
+        if self.shows_higher_dimension() {
+            self.nd_rotation_velocity.iter_mut().collect()
+        } else if self.is_4d_mode && self.rotation_preset.is_active() {
+            self.rotation_preset.speeds_mut()
+        } else if self.is_4d_mode {
+            vec![
+                &mut self.rotation_velocity_xy,
+                &mut self.rotation_velocity_xz,
+                &mut self.rotation_velocity_yz,
+                &mut self.rotation_velocity_xw,
+                &mut self.rotation_velocity_yw,
+                &mut self.rotation_velocity_zw,
+            ]
+        } else {
+            vec![
+                &mut self.rotation_velocity_x,
+                &mut self.rotation_velocity_y,
+                &mut self.rotation_velocity_z,
+            ]
+        }
+    }
+
+    // Frames after which the morph animation and the wiggle are both back where they
+    // started, at the recordings' frame rate (1 if neither runs)
+    #[cfg(not(target_arch = "wasm32"))]
+    fn loop_cycle_frames(&self) -> usize {
+        let mut cycle = if self.morph_animating { MORPH_CYCLE_FRAMES } else { 1 };
+        if self.stereo_layout == StereoLayout::Wiggle {
+            let fps = record::RECORD_FPS as usize;
+            let whole_wiggles = |frames: usize| {
+                let wiggles = frames as f32 * self.wiggle_rate / fps as f32;
+                (wiggles - wiggles.round()).abs() < 1e-4
+            };
+            // The rate goes in half wiggles per second, so two seconds always hold whole wiggles
+            let wiggle = (1..=2 * fps).find(|&frames| whole_wiggles(frames)).unwrap_or(2 * fps);
+            cycle = cycle / gcd(cycle, wiggle) * wiggle;
+        }
+        cycle
+    }
+
+    // Number of frames in a seamless loop of the current animation: the fewest whole
+    // morph and wiggle cycles, up to RECORD_MAX_LOOP_FRAMES, over which every active
+    // rotation can be rounded to a whole number of turns while changing its speed by no
+    // more than LOOP_SPEED_TOLERANCE, or else the count that changes the speeds least
+    #[cfg(not(target_arch = "wasm32"))]
+    fn loop_period_frames(&mut self) -> usize {
+        let cycle = self.loop_cycle_frames();
+        let velocities: Vec<f32> =
+            self.active_velocities_mut().into_iter().map(|velocity| *velocity).filter(|&velocity| velocity != 0.0).collect();
+        if velocities.is_empty() {
+            return cycle; // No rotation, one morph or wiggle cycle (or a single frame) is a full period
+        }
+        let speed_change = |frames: usize| {
+            velocities
+                .iter()
+                .map(|&velocity| ((loop_velocity(velocity, frames) - velocity) / velocity).abs())
+                .fold(0.0f32, f32::max)
+        };
+        let mut closest = (f32::INFINITY, RECORD_MAX_LOOP_FRAMES / cycle * cycle);
+        for frames in (cycle..=RECORD_MAX_LOOP_FRAMES).step_by(cycle) {
+            let change = speed_change(frames);
+            if change <= LOOP_SPEED_TOLERANCE {
+                return frames;
+            }
+            if change < closest.0 {
+                closest = (change, frames);
+            }
         }
+        closest.1
+    }
+
+    // Round every active velocity to a whole number of turns over `frames` steps,
+    // so that the last frame leads seamlessly back into the first
+    #[cfg(not(target_arch = "wasm32"))]
+    fn snap_velocities_to_loop(&mut self, frames: usize) {
+        for velocity in self.active_velocities_mut() {
+            *velocity = loop_velocity(*velocity, frames);
+        }
+    }
+
+    // Display name of the solid or hypersolid shown in the current mode
+    fn shape_name(&self) -> String {
+        if let Some((shape, _)) = self.uniform_shown() {
+            shape.name()
+        } else if self.shows_higher_dimension() {
+            polytope_nd::name(self.current_hypersolid, self.dimension).to_owned()
+        } else if self.is_4d_mode {
+            self.current_hypersolid.name()
+        } else {
+            self.current_solid.name().to_owned()
+        }
+    }
+
+    // Uniform polytope picked in the Wythoff panel, if it has the rank of the current mode
+    fn uniform_shown(&self) -> Option<&(WythoffShape, wythoff::Polytope)> {
+        let rank = if self.is_4d_mode { 4 } else { 3 };
+        self.uniform
+            .as_ref()
+            .filter(|(shape, _)| shape.group.rank() == rank && !self.shows_higher_dimension())
+    }
+    
+    // Build a uniform polytope and show it in place of the regular shapes
+    fn select_uniform(&mut self, shape: WythoffShape) {
+        self.uniform = Some((shape, shape.build()));
     }
     
-    fn get_edges(&self) -> &[(usize, usize)] {
-        match self.current_solid {
-            PlatonicSolid::Tetrahedron => &TETRAHEDRON_EDGES,
-            PlatonicSolid::Cube => &CUBE_EDGES,
-            PlatonicSolid::Octahedron => &OCTAHEDRON_EDGES,
-            PlatonicSolid::Dodecahedron => &DODECAHEDRON_EDGES,
-            PlatonicSolid::Icosahedron => &ICOSAHEDRON_EDGES,
+    // Find the cells again when the 4D shape changes. A highlighted cell stays selected
+    // as long as the new shape has that many cells.
+    fn refresh_cells(&mut self) {
+        let key = if !self.is_4d_mode || self.shows_higher_dimension() {
+            String::new()
+        } else if let Some((shape, _)) = self.uniform_shown() {
+            shape.key()
+        } else {
+            self.current_hypersolid.key()
+        };
+        if key == self.cells_key {
+            return;
         }
+        self.cells = if key.is_empty() || self.current_hypersolid == Hypersolid::CliffordTorus && self.uniform_shown().is_none() {
+            Vec::new() // The torus is a wire surface, not a solid made of cells
+        } else if let Some((_, polytope)) = self.uniform_shown() {
+            polytope.cells.clone()
+        } else {
+            geometry::cells(&self.current_hypersolid.mesh())
+        };
+        self.cells_key = key;
+        self.selected_cell = self.selected_cell.filter(|&cell| cell < self.cells.len());
+    }
+    
+    // Cells can be highlighted on the undistorted shape only, not part way to its dual
+    fn cells_shown(&self) -> bool {
+        let morphing = self.morph > 0.0 && self.has_dual_morph();
+        self.is_4d_mode && !self.shows_higher_dimension() && !morphing && !self.cells.is_empty()
     }
     
-    fn get_4d_vertices(&self) -> &[Vec4D] {
-        match self.current_hypersolid {
-            Hypersolid::Tesseract => &TESSERACT_VERTICES,
-            Hypersolid::FourSimplex => &FOUR_SIMPLEX_VERTICES,
-            Hypersolid::FourOrthoplex => &FOUR_ORTHOPLEX_VERTICES,
+    // Widest range Z, or W, can take for a shape whose farthest vertex is `radius` from the
+    // center, whatever its orientation. Rotations keep every vertex at its distance from
+    // the center, so that distance bounds W, and Z too once W has been spread into 3D.
+    fn fixed_depth_range(&self, radius: f32, along_w: bool) -> (f32, f32) {
+        let radius = if !self.is_4d_mode || along_w {
+            radius
+        } else if self.shows_higher_dimension() {
+            1.0 // The chain of projections keeps the unit ball inside itself
+        } else {
+            radius * (1.0 + W_SPREAD.length_squared()).sqrt()
+        };
+        (-radius, radius)
+    }
+    
+    // Step through the cells one way or the other, with nothing highlighted between the last and the first
+    fn step_cell(&mut self, forward: bool) {
+        let count = self.cells.len();
+        self.selected_cell = match (self.selected_cell, forward) {
+            (None, true) => Some(0),
+            (None, false) => count.checked_sub(1),
+            (Some(cell), true) => Some(cell + 1).filter(|&next| next < count),
+            (Some(cell), false) => cell.checked_sub(1),
+        };
+    }
+    
+    // Highlight the cell whose center is drawn nearest to a point on screen, in any eye
+    // view whose outline contains the point
+    fn pick_cell(&mut self, point: Vec2) {
+        let views: Vec<&[Vec2]> = self.eye_buffers[..self.eyes_drawn].iter().map(|buffers| &buffers.screen_vertices[..]).collect();
+        if let Some(cell) = picking::nearest_center(&views, &self.cells, point) {
+            self.selected_cell = Some(cell);
+        }
+    }
+    
+    // Identifies the shape shown, including how far it is morphed, for the faces found on it
+    fn faces_shape_key(&self) -> String {
+        format!("{} {} {}", if self.is_4d_mode { "4d" } else { "3d" }, self.shape_name(), self.morph)
+    }
+    
+    // Find the faces of the shape shown, unless they are known already. The Clifford torus
+    // is a wire surface with no faces; uniform polytopes come with theirs.
+    fn refresh_faces(&mut self) {
+        let key = self.faces_shape_key();
+        if key == self.faces_key {
+            return;
+        }
+        let torus = self.is_4d_mode && !self.shows_higher_dimension() && self.current_hypersolid == Hypersolid::CliffordTorus;
+        self.faces = if let Some((_, polytope)) = self.uniform_shown() {
+            polytope.faces.clone()
+        } else if torus {
+            Vec::new()
+        } else {
+            let mesh = self.coordinate_mesh();
+            geometry::faces(&mesh.vertices, &mesh.edges, 2)
+        };
+        self.faces_key = key;
+    }
+    
+    // Pick a vertex or an edge, or nothing
+    fn pick(&mut self, picked: Option<Picked>) {
+        if picked.is_some() {
+            self.refresh_faces();
+        }
+        self.picked = picked;
+    }
+    
+    // Pick the vertex or edge drawn nearest to a point on screen in any eye view, if one
+    // is near enough; edges hidden by the cell highlighting can't be picked
+    fn pick_element(&mut self, point: Vec2) -> bool {
+        if self.eyes_drawn == 0 {
+            return false;
+        }
+        let shape = &self.frame_shape;
+        let views: Vec<&[Vec2]> = self.eye_buffers[..self.eyes_drawn].iter().map(|buffers| &buffers.screen_vertices[..]).collect();
+        let picked = picking::hit_test(&views, &shape.mesh.edges, |edge| shape.emphasis[edge] != EdgeEmphasis::Hidden, point);
+        self.pick(picked);
+        picked.is_some()
+    }
+    
+    // Mark the vertex drawn nearest to a point on screen for measuring, or failing that the
+    // face whose center is drawn nearest; a point off the shape clears the marks
+    fn measure_at(&mut self, point: Vec2) {
+        self.refresh_faces();
+        if self.eyes_drawn == 0 {
+            return;
+        }
+        let shape = &self.frame_shape;
+        let views: Vec<&[Vec2]> = self.eye_buffers[..self.eyes_drawn].iter().map(|buffers| &buffers.screen_vertices[..]).collect();
+        match picking::hit_test(&views, &shape.mesh.edges, |edge| shape.emphasis[edge] != EdgeEmphasis::Hidden, point) {
+            Some(Picked::Vertex(vertex)) => self.measurement.mark_vertex(vertex),
+            _ => match picking::nearest_center(&views, &self.faces, point) {
+                Some(face) => self.measurement.mark_face(face),
+                None => self.measurement = Measurement::default(),
+            },
+        }
+    }
+    
+    // Title and lines of the panel about a picked vertex or edge
+    fn describe_picked(&self, picked: Picked) -> (String, Vec<String>) {
+        let mesh = &self.frame_shape.mesh;
+        let cells = self.cells_shown().then_some(&self.cells[..]);
+        picking::describe(picked, &mesh.vertices, &mesh.edges, &self.faces, cells)
+    }
+    
+    // Bottom edge of the picked vertex or edge panel, above the preset panel when it is shown
+    fn picked_panel_bottom(&self) -> f32 {
+        screen_height() - if self.show_rotation_presets { 180.0 } else { 30.0 }
+    }
+    
+    // Lines of the uniform polytope panel: every ring pattern of the group, the one shown
+    // marked
+    fn uniform_panel_lines(&self) -> Vec<(String, bool)> {
+        let selected = self.uniform_shown().map(|(shape, polytope)| (*shape, polytope));
+        let group = match selected {
+            Some((shape, _)) => shape.group,
+            None if self.is_4d_mode => CoxeterGroup::A4,
+            None => CoxeterGroup::A3,
+        };
+        
+        let mut lines: Vec<(String, bool)> = Vec::new();
+        if self.shows_higher_dimension() {
+            lines.push(("Wythoff shapes are 3D or 4D (U)".to_owned(), false));
+        } else {
+            lines.push((format!("N - Group: {}   C - Next rings", group.name()), false));
+            for shape in wythoff::catalog(group) {
+                let is_selected = selected.is_some_and(|(current, _)| current == shape);
+                lines.push((format!("{} {}  {}", if is_selected { ">" } else { " " }, shape.diagram(), shape.name()), is_selected));
+            }
+            if let Some((_, polytope)) = selected {
+                lines.push((format!("V {}  E {}  F {}", polytope.vertices.len(), polytope.edges.len(), polytope.faces.len()), false));
+                lines.push((format!("{} - Back to the regular shapes", if self.is_4d_mode { "J" } else { "S" }), false));
+            }
+        }
+        lines
+    }
+    
+    // Whether a point on screen is on one of the panels drawn over the stereogram or the
+    // inspector, so that a click there is not taken for one on the shape behind
+    fn panel_at(&self, point: Vec2) -> bool {
+        let mut panels = Vec::new();
+        if self.show_inspector {
+            panels.push(side_panel_rect());
+        }
+        if let Some(stats) = self.stats.as_ref().filter(|_| self.show_stats) {
+            panels.push(stats.panel_rect(stereogram_width()));
+        }
+        if let Some(picked) = self.picked {
+            let (_, lines) = self.describe_picked(picked);
+            panels.push(picking::panel_rect(lines.len(), self.picked_panel_bottom()));
+        }
+        if self.show_rotation_presets {
+            panels.push(preset_panel_rect());
+        }
+        if self.show_uniform_panel {
+            panels.push(uniform_panel_rect(self.uniform_panel_lines().len()));
+        }
+        panels.iter().any(|panel| panel.contains(point))
+    }
+    
+    // Work out the radii of the shape shown, unless they are known already. They need its
+    // facets: the faces of a solid and the cells of a 4D shape, found like the faces.
+    fn refresh_radii(&mut self) {
+        let key = self.faces_shape_key();
+        if self.radii.as_ref().is_some_and(|(known, _)| *known == key) {
+            return;
+        }
+        let torus = self.is_4d_mode && !self.shows_higher_dimension() && self.current_hypersolid == Hypersolid::CliffordTorus;
+        let mesh = self.coordinate_mesh();
+        let facets = if let Some((_, polytope)) = self.uniform_shown() {
+            if self.is_4d_mode { polytope.cells.clone() } else { polytope.faces.clone() }
+        } else if torus {
+            Vec::new()
+        } else {
+            let dimension = mesh.vertices.first().map_or(0, Vec::len);
+            geometry::faces(&mesh.vertices, &mesh.edges, dimension.saturating_sub(1))
+        };
+        self.radii = Some((key, measure::radii(&mesh.vertices, &facets)));
+    }
+    
+    // Wireframe of the solid shown in 3D mode, part of the way to its dual while morphing
+    fn solid_mesh(&self) -> Mesh<Vec3> {
+        if let Some((_, polytope)) = self.uniform_shown() {
+            return polytope.mesh_3d();
+        }
+        let solid = self.current_solid.mesh();
+        if self.morph <= 0.0 {
+            return solid;
+        }
+        let mut dual = self.current_solid.dual().mesh();
+        if self.current_solid.dual() == self.current_solid {
+            // A tetrahedron's dual is the tetrahedron turned inside out
+            dual.vertices.iter_mut().for_each(|vertex| *vertex = -*vertex);
+        }
+        geometry::dual_morph(&solid, &dual, self.morph)
+    }
+    
+    // Wireframe of the hypersolid shown in 4D mode, part of the way to its dual while morphing
+    fn hypersolid_mesh(&self) -> Mesh<Vec4D> {
+        if let Some((_, polytope)) = self.uniform_shown() {
+            return polytope.mesh_4d();
+        }
+        let hypersolid = self.current_hypersolid.mesh();
+        match self.current_hypersolid.dual() {
+            Some(dual) if self.morph > 0.0 => {
+                let mut dual = dual.mesh();
+                if self.current_hypersolid == Hypersolid::FourSimplex {
+                    // Like the tetrahedron, the 4-simplex's dual is itself turned inside out
+                    dual.vertices.iter_mut().for_each(|vertex| *vertex = Vec4D::new(-vertex.x, -vertex.y, -vertex.z, -vertex.w));
+                }
+                geometry::dual_morph(&hypersolid, &dual, self.morph)
+            }
+            _ => hypersolid,
+        }
+    }
+    
+    // Whether 4D mode shows a polytope of five or more dimensions
+    fn shows_higher_dimension(&self) -> bool {
+        self.is_4d_mode && self.dimension > 4
+    }
+    
+    // Show the polytopes of another dimension in 4D mode (4 is the hypersolids),
+    // starting from a slight tilt in every plane with new random plane speeds
+    fn set_dimension(&mut self, dimension: usize) {
+        self.dimension = dimension.clamp(4, polytope_nd::MAX_DIMENSION);
+        if self.dimension > 4 && !self.current_hypersolid.is_regular() {
+            // Duoprisms, prisms and the Clifford torus only exist in 4D
+            self.current_hypersolid = Hypersolid::Tesseract;
         }
+        let planes = if self.dimension > 4 { polytope_nd::planes(self.dimension).len() } else { 0 };
+        self.nd_rotation = vec![polytope_nd::REST_ANGLE; planes];
+        self.nd_rotation_velocity = (0..planes).map(|_| rand::gen_range(-0.01, 0.01)).collect();
     }
     
-    fn get_4d_edges(&self) -> &[(usize, usize)] {
-        match self.current_hypersolid {
-            Hypersolid::Tesseract => &TESSERACT_EDGES,
-            Hypersolid::FourSimplex => &FOUR_SIMPLEX_EDGES,
-            Hypersolid::FourOrthoplex => &FOUR_ORTHOPLEX_EDGES,
+    // Names, angles and speeds of the rotation planes listed in the 4D controls panel
+    fn rotation_planes(&self) -> Vec<(String, f32, f32)> {
+        if self.shows_higher_dimension() {
+            return polytope_nd::planes(self.dimension)
+                .into_iter()
+                .map(polytope_nd::plane_name)
+                .zip(self.nd_rotation.iter().zip(&self.nd_rotation_velocity))
+                .map(|(name, (&angle, &velocity))| (name, angle, velocity))
+                .collect();
+        }
+        vec![
+            ("XY".to_owned(), self.rotation_xy, self.rotation_velocity_xy),
+            ("XZ".to_owned(), self.rotation_xz, self.rotation_velocity_xz),
+            ("YZ".to_owned(), self.rotation_yz, self.rotation_velocity_yz),
+            ("XW".to_owned(), self.rotation_xw, self.rotation_velocity_xw),
+            ("YW".to_owned(), self.rotation_yw, self.rotation_velocity_yw),
+            ("ZW".to_owned(), self.rotation_zw, self.rotation_velocity_zw),
+        ]
+    }
+    
+    // Angle and speed of one plane from `rotation_planes`
+    fn plane_rotation_mut(&mut self, plane: usize) -> Option<(&mut f32, &mut f32)> {
+        if self.shows_higher_dimension() {
+            return self.nd_rotation.get_mut(plane).zip(self.nd_rotation_velocity.get_mut(plane));
+        }
+        match plane {
+            0 => Some((&mut self.rotation_xy, &mut self.rotation_velocity_xy)),
+            1 => Some((&mut self.rotation_xz, &mut self.rotation_velocity_xz)),
+            2 => Some((&mut self.rotation_yz, &mut self.rotation_velocity_yz)),
+            3 => Some((&mut self.rotation_xw, &mut self.rotation_velocity_xw)),
+            4 => Some((&mut self.rotation_yw, &mut self.rotation_velocity_yw)),
+            5 => Some((&mut self.rotation_zw, &mut self.rotation_velocity_zw)),
+            _ => None,
         }
     }
     
+    // Whether the shape shown can morph into its dual
+    fn has_dual_morph(&self) -> bool {
+        if self.uniform_shown().is_some() {
+            return false;
+        }
+        !self.is_4d_mode || (!self.shows_higher_dimension() && self.current_hypersolid.dual().is_some())
+    }
+    
+    // Name of the dual the morph leads to
+    fn dual_name(&self) -> String {
+        if self.is_4d_mode {
+            self.current_hypersolid.dual().map_or_else(String::new, Hypersolid::name)
+        } else {
+            self.current_solid.dual().name().to_owned()
+        }
+    }
+    
+    // The six 4D plane angles: XY, XZ, YZ, XW, YW, ZW
+    fn plane_angles(&self) -> [f32; 6] {
+        [self.rotation_xy, self.rotation_xz, self.rotation_yz, self.rotation_xw, self.rotation_yw, self.rotation_zw]
+    }
+    
     // Apply 4D rotations in all 6 rotation planes
     fn apply_4d_rotations(&self, vertex: Vec4D) -> Vec4D {
+        // While a preset runs its quaternion pair is the orientation, and the plane angles
+        // are only read from it
+        if let Some(pair) = self.preset_rotation.filter(|pair| self.rotation_preset.is_active() && pair.holds(&self.plane_angles())) {
+            let rotated = pair.rotate_point(Vec4::new(vertex.x, vertex.y, vertex.z, vertex.w));
+            return Vec4D::new(rotated.x, rotated.y, rotated.z, rotated.w);
+        }
         let mut result = vertex;
         
         // XY plane rotation
@@ -420,14 +1569,163 @@ impl StereogramViewer {
     fn project_4d_to_3d(&self, vertex_4d: Vec4D, _w_distance: f32) -> Vec3 {
         // Use W coordinate to create proper 3D separation
         // Spread vertices in 3D space based on their W coordinate
-        Vec3::new(
-            vertex_4d.x + vertex_4d.w * 0.2, // W affects X position
-            vertex_4d.y + vertex_4d.w * 0.2, // W affects Y position
-            vertex_4d.z + vertex_4d.w * 0.5  // W affects Z position
-        )
+        // W affects X, Y and Z positions by different amounts
+        vertex_4d.to_vec3() + W_SPREAD * vertex_4d.w
+    }
+
+    // Draw an edge with its color and the depth cues following the depth along it. `farness`
+    // is how far back each end lies, and the colormap position runs from `from` at the start
+    // to `to` at the end, picking colors through `color_at`. The edge goes in pieces short
+    // enough for the blend along each to follow the map's bends and the fog's curve.
+    fn draw_cued_edge(
+        &self,
+        canvas: &mut dyn LineCanvas,
+        (start, end): (Vec2, Vec2),
+        (start_farness, end_farness): (f32, f32),
+        (from, to): (f32, f32),
+        color_at: impl Fn(f32) -> Color,
+        width_scale: f32,
+    ) {
+        let cued = self.depth_width || self.fog != Fog::Off;
+        let change = (to - from).abs().max(if cued { (end_farness - start_farness).abs() } else { 0.0 });
+        let pieces = (change * GRADIENT_PIECES_PER_MAP).ceil().max(1.0) as usize;
+        let background = self.background_color();
+        let farness_at = |t: f32| start_farness + (end_farness - start_farness) * t;
+        let color_at = |t: f32| self.fog.apply(color_at(from + (to - from) * t), background, farness_at(t));
+        for piece in 0..pieces {
+            let (t0, t1) = (piece as f32 / pieces as f32, (piece + 1) as f32 / pieces as f32);
+            let width = depth_cue::width(farness_at((t0 + t1) / 2.0), self.depth_width) * width_scale;
+            let (color0, color1) = (color_at(t0), color_at(t1));
+            if color0 == color1 {
+                canvas.line(start.lerp(end, t0), start.lerp(end, t1), width, color0);
+            } else {
+                canvas.gradient_line(start.lerp(end, t0), start.lerp(end, t1), width, color0, color1);
+            }
+        }
     }
 
-    fn draw_solid_wireframe(&self, camera_offset: f32, screen_offset_x: f32, viewport_width: f32) {
+    // The shape shown with its vertices as coordinate lists in any dimension, before any rotation
+    fn coordinate_mesh(&self) -> Mesh<Vec<f32>> {
+        if !self.is_4d_mode {
+            let mesh = self.solid_mesh();
+            Mesh { vertices: mesh.vertices.iter().map(|vertex| vertex.to_array().to_vec()).collect(), edges: mesh.edges }
+        } else if self.shows_higher_dimension() {
+            polytope_nd::mesh(self.current_hypersolid, self.dimension)
+        } else {
+            let mesh = self.hypersolid_mesh();
+            Mesh { vertices: mesh.vertices.iter().map(|vertex| vec![vertex.x, vertex.y, vertex.z, vertex.w]).collect(), edges: mesh.edges }
+        }
+    }
+    
+    // Coordinates of each vertex after the rotations above 3D, before the projection
+    // down to 3D; empty for 3D solids
+    fn rotated_coordinates(&self) -> Vec<Vec<f32>> {
+        if !self.is_4d_mode {
+            Vec::new()
+        } else if self.shows_higher_dimension() {
+            self.frame_shape.mesh.vertices.iter().map(|vertex| polytope_nd::rotate(vertex, &self.nd_rotation)).collect()
+        } else {
+            self.frame_shape
+                .mesh
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    let rotated = self.apply_4d_rotations(Vec4D::new(vertex[0], vertex[1], vertex[2], vertex[3]));
+                    vec![rotated.x, rotated.y, rotated.z, rotated.w]
+                })
+                .collect()
+        }
+    }
+    
+    // Dots at the vertices of one eye view, sized by depth and drawn far to near, with
+    // their labels. A label sits at the same offset from its dot in both eye views, so it
+    // gets the same disparity as the vertex and is seen at the vertex's depth.
+    // `color_of` gives each vertex's color, or None to leave the vertex out, and
+    // `coordinates` are the vertices' own, written by the coordinate labels.
+    fn draw_vertex_marks(
+        &self,
+        canvas: &mut dyn LineCanvas,
+        (screen_vertices, rotated_vertices, coordinates): (&[Vec2], &[Vec3], &[Vec<f32>]),
+        vertex_order: &mut Vec<(f32, usize)>,
+        z_range: (f32, f32),
+        color_of: impl Fn(usize) -> Option<Color>,
+    ) {
+        if self.vertex_marks == VertexMarks::Off {
+            return;
+        }
+        vertex_order.clear();
+        vertex_order.extend(rotated_vertices.iter().enumerate().map(|(index, vertex)| (vertex.z, index)));
+        vertex_order.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        
+        let background = self.background_color();
+        let mut labels = Vec::new();
+        for &(z, index) in vertex_order.iter() {
+            let Some(color) = color_of(index) else {
+                continue;
+            };
+            let farness = colormap::normalize(z, z_range);
+            let color = self.fog.apply(color, background, farness);
+            let radius = DOT_NEAR_RADIUS + (DOT_FAR_RADIUS - DOT_NEAR_RADIUS) * farness;
+            let position = screen_vertices[index];
+            if self.render_style == RenderStyle::Wireframe {
+                // Balls already mark the vertices in the ball-and-stick style
+                canvas.dot(position, radius, color);
+            }
+            
+            let label = match self.vertex_marks {
+                VertexMarks::Indices => index.to_string(),
+                VertexMarks::Coordinates => match coordinates.get(index) {
+                    Some(point) => format!("({})", point.iter().map(|value| format!("{:.2}", value)).collect::<Vec<_>>().join(", ")),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            labels.push((label, position + Vec2::new(radius + 2.0, -radius - 2.0), color));
+        }
+        
+        // Labels over all of the dots
+        for (label, position, color) in labels {
+            canvas.text(&label, position, VERTEX_LABEL_SIZE, color);
+        }
+    }
+
+    // Tubes along the edges and balls at the vertices, hiding each other by depth.
+    // `edge_colors` gives an edge's color at its start and end and how much thicker than
+    // usual it is, and `ball_color` a vertex's color; None leaves either out. Sizes are set in pixels at the
+    // view's center, where one unit of the rotated shape spans `pixels_per_unit`.
+    fn draw_ball_and_stick(
+        &self,
+        canvas: &mut dyn LineCanvas,
+        solid: &mut SolidMesh,
+        (projection, pixels_per_unit, z_range): (&Mat4, f32, (f32, f32)),
+        (rotated_vertices, edges): (&[Vec3], &[(usize, usize)]),
+        edge_colors: impl Fn(usize) -> Option<(Color, Color, f32)>,
+        ball_color: impl Fn(usize) -> Option<Color>,
+    ) {
+        let background = self.background_color();
+        let fogged = |color: Color, index: usize| {
+            self.fog.apply(color, background, colormap::normalize(rotated_vertices[index].z, z_range))
+        };
+        solid.clear();
+        for (edge, &(start_idx, end_idx)) in edges.iter().enumerate() {
+            let Some((start_color, end_color, radius_scale)) = edge_colors(edge) else {
+                continue;
+            };
+            let ends = (rotated_vertices[start_idx], rotated_vertices[end_idx]);
+            let radius = tubes::TUBE_RADIUS * radius_scale / pixels_per_unit;
+            solid.tube(ends, radius, (fogged(start_color, start_idx), fogged(end_color, end_idx)));
+        }
+        for (index, &vertex) in rotated_vertices.iter().enumerate() {
+            if let Some(color) = ball_color(index) {
+                solid.ball(vertex, tubes::BALL_RADIUS / pixels_per_unit, fogged(color, index));
+            }
+        }
+        canvas.solid(solid, projection);
+    }
+
+    fn draw_solid_wireframe(&mut self, canvas: &mut dyn LineCanvas, view: EyeView, shape: &FrameShape, buffers: &mut EyeBuffers) {
+        let EyeView { camera_offset, screen_offset_x, viewport_width, viewport_height, tint } = view;
+        
         // Apply rotation to vertices first
         // Create rotation matrices for each axis
         let rot_x_matrix = Mat4::from_rotation_x(self.rotation_x);
@@ -439,7 +1737,7 @@ impl StereogramViewer {
 
         // Transform and project vertices to 2D screen space manually
         let screen_center_x = viewport_width / 4.0 + screen_offset_x; // Quarter width + offset for each view
-        let screen_center_y = screen_height() / 2.0;
+        let screen_center_y = viewport_height / 2.0;
         // Adjust scale to maintain cube size as perspective changes
         let base_scale = 180.0;
         let scale = if self.orthographic {
@@ -452,204 +1750,246 @@ impl StereogramViewer {
         };
         let perspective_distance = self.perspective_distance; // Use adjustable perspective distance
 
+        // Camera shifted sideways by the eye's offset (for stereogram effect), looking down +Z
+        // from the perspective distance in front of the solid
+        let projection = if self.orthographic {
+            // Orthographic projection - no perspective distortion
+            canvas::orthographic_projection(
+                Vec4::new(scale, 0.0, 0.0, screen_center_x - camera_offset * scale),
+                Vec4::new(0.0, -scale, 0.0, screen_center_y),
+            )
+        } else {
+            // Perspective projection - traditional 3D perspective, dividing by the distance from the eye
+            canvas::perspective_projection(
+                Vec4::new(scale, 0.0, screen_center_x, screen_center_x * perspective_distance - camera_offset * scale),
+                Vec4::new(0.0, -scale, screen_center_y, screen_center_y * perspective_distance),
+                perspective_distance,
+            )
+        };
+
         // Project each vertex to 2D screen coordinates
-        let mut projected_vertices = Vec::new();
-        let mut transformed_vertices = Vec::new();
+        (buffers.camera_offset, buffers.scale) = (camera_offset, scale);
+        let EyeBuffers { rotated_vertices: transformed_vertices, screen_vertices: projected_vertices, edge_order, vertex_order, solid, .. } = buffers;
+        transformed_vertices.clear();
+        projected_vertices.clear();
         
-        let vertices = self.get_vertices();
-        for &vertex in vertices {
+        let mesh = &shape.mesh;
+        for &vertex in &shape.vertices_3d {
             // Apply rotation
-            let vec4 = combined_rotation * Vec4::new(vertex.x, vertex.y, vertex.z, 1.0);
-            let rotated = Vec3::new(vec4.x, vec4.y, vec4.z);
+            let rotated = combined_rotation.transform_point3(vertex);
             transformed_vertices.push(rotated);
-            
-            // Apply camera offset (for stereogram effect)
-            let camera_adjusted = Vec3::new(
-                rotated.x - camera_offset,
-                rotated.y,
-                rotated.z + perspective_distance
-            );
-            
-            // Choose projection type: perspective or orthographic
-            let (projected_x, projected_y) = if self.orthographic {
-                // Orthographic projection - no perspective distortion
-                let projected_x = screen_center_x + camera_adjusted.x * scale;
-                let projected_y = screen_center_y - camera_adjusted.y * scale;
-                (projected_x, projected_y)
-            } else {
-                // Perspective projection - traditional 3D perspective
-                let projected_x = screen_center_x + (camera_adjusted.x * scale) / camera_adjusted.z;
-                let projected_y = screen_center_y - (camera_adjusted.y * scale) / camera_adjusted.z;
-                (projected_x, projected_y)
-            };
-            
-            projected_vertices.push(Vec2::new(projected_x, projected_y));
+            projected_vertices.push(canvas::project(&projection, rotated));
         }
+        buffers.center = canvas::project(&projection, Vec3::ZERO);
 
         // Draw wireframe edges using 2D lines with depth sorting
-        let edges = self.get_edges();
         
         // Collect all edges with their depth information for sorting
-        let mut edge_data: Vec<(f32, Vec2, Vec2, Color)> = Vec::new();
-        
-        for &(start_idx, end_idx) in edges {
-            let start_2d = projected_vertices[start_idx];
-            let end_2d = projected_vertices[end_idx];
-            
+        edge_order.clear();
+        for (edge, &(start_idx, end_idx)) in mesh.edges.iter().enumerate() {
             // Calculate depth for sorting (use raw Z values after transformation)
-            let start_3d = transformed_vertices[start_idx];
-            let end_3d = transformed_vertices[end_idx];
-            let avg_z = (start_3d.z + end_3d.z) / 2.0;
-            
-            let wire_color = if self.depth_coloring {
-                // Map Z distance to color intensity (closer = darker, farther = lighter)
-                // Use raw Z values for consistent depth calculation
-                let min_z = -2.0; // Approximate range for transformed vertices
-                let max_z = 2.0;
-                let intensity = ((max_z - avg_z) / (max_z - min_z)).clamp(0.2, 1.0);
-                
-                // Create color based on depth and background
-                if self.dark_background {
-                    // Dark background: use white to light gray
-                    Color::new(intensity, intensity, intensity, 1.0)
-                } else {
-                    // Light background: use black to dark gray
-                    Color::new(1.0 - intensity, 1.0 - intensity, 1.0 - intensity, 1.0)
-                }
-            } else {
-                // Use plain color based on background
-                if self.dark_background {
-                    WHITE
-                } else {
-                    BLACK
-                }
-            };
+            let avg_z = (transformed_vertices[start_idx].z + transformed_vertices[end_idx].z) / 2.0;
             
             // Store edge data with depth for sorting
-            edge_data.push((avg_z, start_2d, end_2d, wire_color));
+            edge_order.push((avg_z, edge));
+        }
+        
+        // Range the depth coloring and cues spread over: this frame's vertices, or all the solid can reach
+        let depth_range = match self.depth_range {
+            DepthRange::PerFrame => colormap::range_of(transformed_vertices.iter().map(|vertex| vertex.z)),
+            DepthRange::Fixed => self.fixed_depth_range(shape.radius, false),
+        };
+        if self.depth_coloring {
+            self.depth_legend = Some(depth_range);
         }
         
         // Sort edges by depth (nearest first, farthest last) for proper depth sorting
-        edge_data.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        edge_order.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        
+        // Vertex dots and balls colored like the edges around them
+        let plain_color = if self.dark_background { WHITE } else { BLACK };
+        let vertex_color = |index: usize| {
+            Some(match tint {
+                Some(tint) => tint,
+                None if self.depth_coloring => {
+                    self.palette.color(1.0 - colormap::normalize(transformed_vertices[index].z, depth_range), self.dark_background)
+                }
+                None => plain_color,
+            })
+        };
         
-        // Draw edges from front to back (nearest first)
-        for (_, start_2d, end_2d, wire_color) in edge_data {
-            draw_line(start_2d.x, start_2d.y, end_2d.x, end_2d.y, 2.0, wire_color);
+        if self.render_style == RenderStyle::Tubes {
+            // Each tube takes the colors of the vertices at its ends
+            let pixels_per_unit = if self.orthographic { scale } else { scale / perspective_distance };
+            let edge_colors = |edge: usize| {
+                let (start_idx, end_idx) = mesh.edges[edge];
+                Some((vertex_color(start_idx)?, vertex_color(end_idx)?, 1.0))
+            };
+            let tube_view = (&projection, pixels_per_unit, depth_range);
+            self.draw_ball_and_stick(canvas, solid, tube_view, (transformed_vertices, &mesh.edges), edge_colors, vertex_color);
+        } else {
+            // Draw edges from front to back (nearest first)
+            for &(_, edge) in edge_order.iter() {
+                let (start_idx, end_idx) = mesh.edges[edge];
+                let farness = |index: usize| colormap::normalize(transformed_vertices[index].z, depth_range);
+                let ends = (projected_vertices[start_idx], projected_vertices[end_idx]);
+                let farness = (farness(start_idx), farness(end_idx));
+                if let Some(tint) = tint {
+                    // Fixed eye color (anaglyph)
+                    self.draw_cued_edge(canvas, ends, farness, (0.0, 0.0), |_| tint, 1.0);
+                } else if self.depth_coloring {
+                    // Closer (lower Z) stands out more, all along the edge
+                    let color_at = |emphasis: f32| self.palette.color(emphasis, self.dark_background);
+                    self.draw_cued_edge(canvas, ends, farness, (1.0 - farness.0, 1.0 - farness.1), color_at, 1.0);
+                } else {
+                    // Use plain color based on background
+                    self.draw_cued_edge(canvas, ends, farness, (0.0, 0.0), |_| plain_color, 1.0);
+                }
+            }
         }
+        self.draw_vertex_marks(canvas, (projected_vertices, transformed_vertices, &mesh.vertices), vertex_order, depth_range, vertex_color);
     }
 
-    fn render_stereogram(&mut self) {
-        let raw_screen_width = screen_width();
+    fn render_stereogram(&mut self, canvas: &mut ScreenCanvas) {
         let screen_height = screen_height();
+        let stereogram_width = stereogram_width();
         
-        // For web version, treat display width like native version (constrained)
-        // Native: window is 663px (393px stereogram + 270px panel)
-        // Web: constrain to match native behavior
-        #[cfg(target_arch = "wasm32")]
-        let display_width = raw_screen_width.min(663.0); // Match native window width
-        #[cfg(not(target_arch = "wasm32"))]
-        let display_width = raw_screen_width;
-        
-        // Stereogram always uses the left portion of the screen
-        let stereogram_width = display_width.min(393.0); // Optimal width for stereogram fusion
-        let half_stereogram_width = stereogram_width / 2.0;
-        
-        // Determine help panel width (smaller on web to avoid covering view)
-        if self.show_ui {
-            // Calculate panel width (used in UI layout)
-            #[cfg(target_arch = "wasm32")]
-            let _panel_width = (display_width - stereogram_width - 20.0).clamp(120.0, 220.0);
-            #[cfg(not(target_arch = "wasm32"))]
-            let _panel_width = 270.0;
-            let _ = _panel_width; // keep variable from unused warnings
-        }
+        // Use default 2D camera for all rendering
+        set_default_camera();
         
         // Clear the screen with current background setting
+        canvas.clear(self.background_color());
+        
+        self.draw_stereo_views(canvas, stereogram_width, screen_height);
+    }
+
+    fn background_color(&self) -> Color {
         if self.dark_background {
-            clear_background(BLACK);
+            BLACK
         } else {
-            clear_background(WHITE);
+            WHITE
         }
-        
-        // Use default 2D camera for all rendering
-        set_default_camera();
-        
-        // Render left eye view (left half of stereogram area)
-        if self.is_4d_mode {
-            self.draw_4d_hypersolid_wireframe(-self.eye_separation, 0.0, stereogram_width);
-        } else {
-            self.draw_solid_wireframe(-self.eye_separation, 0.0, stereogram_width);
+    }
+
+    // Draw both eye views in the current stereo layout into a stereogram_width x viewport_height area
+    fn draw_stereo_views(&mut self, canvas: &mut dyn LineCanvas, stereogram_width: f32, viewport_height: f32) {
+        self.refresh_cells();
+        if (self.picked.is_some() || !self.measurement.is_empty()) && self.faces_key != self.faces_shape_key() {
+            // Picked or marked on another shape
+            self.picked = None;
+            self.measurement = Measurement::default();
         }
-        
-        // Render right eye view (right half of stereogram area)  
-        if self.is_4d_mode {
-            self.draw_4d_hypersolid_wireframe(self.eye_separation, half_stereogram_width, stereogram_width);
-        } else {
-            self.draw_solid_wireframe(self.eye_separation, half_stereogram_width, stereogram_width);
+        if self.show_radii {
+            self.refresh_radii();
         }
-        
-        // Draw guides only if show_guides is true
-        if self.show_guides {
+        self.eyes_drawn = 0;
+        self.depth_legend = None;
+        let mut shape = std::mem::take(&mut self.frame_shape);
+        shape.refresh(self);
+        let half_stereogram_width = stereogram_width / 2.0;
+        let eye_separation = self.eye_separation;
+        let eye_view = |camera_offset: f32, screen_offset_x: f32, tint: Option<Color>| EyeView {
+            camera_offset,
+            screen_offset_x,
+            viewport_width: stereogram_width,
+            viewport_height,
+            tint,
+        };
+
+        match self.stereo_layout {
+            StereoLayout::Parallel => {
+                // Left eye view in the left half, right eye view in the right half
+                self.draw_eye_view(canvas, eye_view(-eye_separation, 0.0, None), &shape);
+                self.draw_eye_view(canvas, eye_view(eye_separation, half_stereogram_width, None), &shape);
+            }
+            StereoLayout::CrossEyed => {
+                // Swapped halves: each eye looks across at the opposite view
+                self.draw_eye_view(canvas, eye_view(eye_separation, 0.0, None), &shape);
+                self.draw_eye_view(canvas, eye_view(-eye_separation, half_stereogram_width, None), &shape);
+            }
+            StereoLayout::Anaglyph => {
+                // Both views centered in the full stereogram area, tinted per eye, each in
+                // its own channels so that neither covers the other
+                let centered_offset = stereogram_width / 4.0;
+                let (left_tint, right_tint) =
+                    if self.dark_background { (ANAGLYPH_RED, ANAGLYPH_CYAN) } else { (ANAGLYPH_CYAN, ANAGLYPH_RED) };
+                canvas.set_channels(Channels::Red);
+                self.draw_eye_view(canvas, eye_view(-eye_separation, centered_offset, Some(left_tint)), &shape);
+                canvas.set_channels(Channels::Cyan);
+                self.draw_eye_view(canvas, eye_view(eye_separation, centered_offset, Some(right_tint)), &shape);
+                canvas.set_channels(Channels::All);
+            }
+            StereoLayout::Wiggle => {
+                // One view at a time, centered, reusing the same camera offsets as the eye views
+                let centered_offset = stereogram_width / 4.0;
+                let camera_offset = self.wiggle_camera_offset();
+                self.draw_eye_view(canvas, eye_view(camera_offset, centered_offset, None), &shape);
+            }
+        }
+        self.frame_shape = shape;
+
+        // Draw guides only if show_guides is true (overlaid views need no fusion aid)
+        let side_by_side = matches!(self.stereo_layout, StereoLayout::Parallel | StereoLayout::CrossEyed);
+        if self.show_guides && side_by_side {
             // Add fusion aids - outline circles just above the cubes
-            let fusion_aid_y = screen_height / 2.0 - 150.0; // Higher above cube center
+            let fusion_aid_y = viewport_height / 2.0 - 150.0; // Higher above cube center
             let fusion_aid_size = 6.0; // Smaller for less distraction
             let outline_thickness = 2.0; // Thin outline
             
             // Left fusion aid (center of left half of stereogram)
             let left_aid_x = half_stereogram_width / 2.0;
-            draw_circle_lines(left_aid_x, fusion_aid_y, fusion_aid_size, outline_thickness, RED);
+            canvas.circle_outline(Vec2::new(left_aid_x, fusion_aid_y), fusion_aid_size, outline_thickness, RED);
             
             // Right fusion aid (center of right half of stereogram)
             let right_aid_x = half_stereogram_width + half_stereogram_width / 2.0;
-            draw_circle_lines(right_aid_x, fusion_aid_y, fusion_aid_size, outline_thickness, RED);
+            canvas.circle_outline(Vec2::new(right_aid_x, fusion_aid_y), fusion_aid_size, outline_thickness, RED);
         }
     }
-    
-    fn draw_4d_hypersolid_wireframe(&mut self, camera_offset: f32, screen_offset_x: f32, viewport_width: f32) {
-        // Debug: Print vertex coordinates when paused (only once per pause)
-        let should_print_debug = self.is_paused && !self.debug_printed;
-        if should_print_debug {
-            println!("\n=== 4D VERTEX DEBUG (PAUSED) ===");
-            println!("Hypersolid: {:?}", self.current_hypersolid);
-            println!("Orthographic: {}", self.orthographic);
-            println!("Perspective Distance: {:.1}", self.perspective_distance);
-            println!("Camera Offset: {:.1}", camera_offset);
-            println!("Screen Offset X: {:.1}", screen_offset_x);
-            println!("Screen Size: {}x{}", screen_width(), screen_height());
-            self.debug_printed = true; // Mark as printed
+
+    // Draw a single eye view of the current 3D solid or 4D hypersolid, all of its edges at once
+    fn draw_eye_view(&mut self, canvas: &mut dyn LineCanvas, view: EyeView, shape: &FrameShape) {
+        if self.eye_buffers.len() <= self.eyes_drawn {
+            self.eye_buffers.push(EyeBuffers::default());
         }
-        
-        let vertices_4d = self.get_4d_vertices();
-        let edges = self.get_4d_edges();
-        
-        // Apply 4D rotations to vertices
-        let mut transformed_vertices_4d = Vec::new();
-        for (i, &vertex_4d) in vertices_4d.iter().enumerate() {
-            // Apply 4D rotations in all 6 planes
-            let transformed = self.apply_4d_rotations(vertex_4d);
-            
-            if should_print_debug { // Print all vertices to see what's happening
-                println!("Vertex {}: 4D original ({:.1}, {:.1}, {:.1}, {:.1}) -> 4D transformed ({:.1}, {:.1}, {:.1}, {:.1})", 
-                    i, vertex_4d.x, vertex_4d.y, vertex_4d.z, vertex_4d.w,
-                    transformed.x, transformed.y, transformed.z, transformed.w);
-            }
-            
-            transformed_vertices_4d.push(transformed);
+        let mut buffers = std::mem::take(&mut self.eye_buffers[self.eyes_drawn]);
+        if self.is_4d_mode {
+            self.draw_4d_hypersolid_wireframe(canvas, view, shape, &mut buffers);
+        } else {
+            self.draw_solid_wireframe(canvas, view, shape, &mut buffers);
         }
-        
-        // Project 4D vertices to 3D space
-        // Use a smaller distance for 4D objects to prevent clustering
-        let w_distance = if self.orthographic { 1.0 } else { 1.5 }; // Even smaller for perspective mode
-        let mut vertices_3d = Vec::new();
-        for (i, vertex_4d) in transformed_vertices_4d.iter().enumerate() {
-            let vertex_3d = self.project_4d_to_3d(*vertex_4d, w_distance);
-            if should_print_debug { // Print all vertices to see what's happening
-                println!("Vertex {}: 4D->3D projected ({:.1}, {:.1}, {:.1})", 
-                    i, vertex_3d.x, vertex_3d.y, vertex_3d.z);
+        // Ring around the picked vertex, or a line over the picked edge
+        let pick_color = view.tint.unwrap_or(PICK_COLOR);
+        match self.picked {
+            Some(Picked::Vertex(index)) if index < buffers.screen_vertices.len() => {
+                canvas.circle_outline(buffers.screen_vertices[index], 9.0, 2.0, pick_color);
             }
-            vertices_3d.push(vertex_3d);
-            
+            Some(Picked::Edge(edge)) => {
+                let screen = &buffers.screen_vertices;
+                if let Some(&(start, end)) = shape.mesh.edges.get(edge).filter(|&&(start, end)| start.max(end) < screen.len()) {
+                    canvas.line(screen[start], screen[end], 4.0, pick_color);
+                }
+            }
+            _ => {}
         }
+        // Measured values beside what they measure, and the radii by the center
+        let measure_color = view.tint.unwrap_or(MEASURE_COLOR);
+        if !self.measurement.is_empty() {
+            let mesh = &shape.mesh;
+            let label = self.measurement.label(&mesh.vertices, &self.faces);
+            self.measurement.draw(canvas, &buffers.screen_vertices, &mesh.edges, &self.faces, label.as_deref(), measure_color);
+        }
+        if let Some((_, radii)) = self.radii.as_ref().filter(|_| self.show_radii) {
+            radii.draw(canvas, buffers.center, measure_color);
+        }
+        canvas.flush();
+        self.eye_buffers[self.eyes_drawn] = buffers;
+        self.eyes_drawn += 1;
+    }
+    
+    fn draw_4d_hypersolid_wireframe(&mut self, canvas: &mut dyn LineCanvas, view: EyeView, shape: &FrameShape, buffers: &mut EyeBuffers) {
+        let EyeView { camera_offset, screen_offset_x, viewport_width, viewport_height, tint } = view;
+        let FrameShape { mesh, vertices_3d, w_values, emphasis, vertex_emphasis, .. } = shape;
+        let edges = &mesh.edges;
         
         // Apply 3D rotation to the projected vertices (no scaling yet)
         let rot_x_matrix = Mat4::from_rotation_x(self.rotation_x);
@@ -657,24 +1997,16 @@ impl StereogramViewer {
         let rot_z_matrix = Mat4::from_rotation_z(self.rotation_z);
         let combined_rotation = rot_z_matrix * rot_y_matrix * rot_x_matrix;
         
-        let mut transformed_vertices = Vec::new();
-        for (i, vertex_3d) in vertices_3d.iter().enumerate() {
-            let rotated_vertex = combined_rotation.transform_point3(*vertex_3d);
-            if should_print_debug { // Print all vertices to see what's happening
-                println!("Vertex {}: 3D rotated ({:.1}, {:.1}, {:.1})", 
-                    i, rotated_vertex.x, rotated_vertex.y, rotated_vertex.z);
-            }
-            transformed_vertices.push(rotated_vertex);
-            
-        }
+        let EyeBuffers { rotated_vertices: transformed_vertices, screen_vertices, edge_order, vertex_order, solid, .. } = buffers;
+        transformed_vertices.clear();
+        transformed_vertices.extend(vertices_3d.iter().map(|&vertex_3d| combined_rotation.transform_point3(vertex_3d)));
         
         // Calculate 2D screen positions with proper scaling
         // Scale to fit 25% of viewport width and center the object
-        let screen_height = screen_height();
         let target_width = viewport_width * 0.25; // 25% of viewport width for proper sizing
         let half_viewport_width = viewport_width * 0.5;
         let viewport_center_x = screen_offset_x + half_viewport_width * 0.5; // Center in the quarter-screen viewport
-        let viewport_center_y = screen_height * 0.5;
+        let viewport_center_y = viewport_height * 0.5;
         
         // Find the bounding box of the rotated vertices to determine scale
         let mut min_x = f32::MAX;
@@ -684,7 +2016,7 @@ impl StereogramViewer {
         
         if self.orthographic {
             // For orthographic, use direct 3D coordinates
-            for vertex in &transformed_vertices {
+            for vertex in transformed_vertices.iter() {
                 min_x = min_x.min(vertex.x);
                 max_x = max_x.max(vertex.x);
                 min_y = min_y.min(vertex.y);
@@ -692,7 +2024,7 @@ impl StereogramViewer {
             }
         } else {
             // For perspective, calculate projected coordinates to get accurate size
-            for vertex in &transformed_vertices {
+            for vertex in transformed_vertices.iter() {
                 let perspective_factor = 1.0 / (self.perspective_distance + vertex.z);
                 let proj_x = vertex.x * perspective_factor;
                 let proj_y = vertex.y * perspective_factor;
@@ -707,232 +2039,311 @@ impl StereogramViewer {
         let object_height = max_y - min_y;
         let object_size = object_width.max(object_height);
         let scale = if object_size > 0.0 { target_width / object_size } else { 1.0 };
-        
-        if should_print_debug {
-            println!("Scale: {:.3}, Object size: {:.3}, Target width: {:.1}", scale, object_size, target_width);
-            println!("Viewport center: ({:.1}, {:.1})", viewport_center_x, viewport_center_y);
-        }
-        
-        let mut edge_data: Vec<(f32, f32, Vec2, Vec2, Color)> = Vec::new(); // (avg_z, avg_w, start_2d, end_2d, color)
-        for &(start_idx, end_idx) in edges {
+        (buffers.camera_offset, buffers.scale) = (camera_offset, scale);
+        
+        // Screen position of every vertex, kept for picking cells with the mouse
+        let perspective_distance = self.perspective_distance;
+        let projection = if self.orthographic {
+            // Use Z for depth separation, shifting points up as they go back
+            canvas::orthographic_projection(
+                Vec4::new(scale, 0.0, 0.0, viewport_center_x + camera_offset),
+                Vec4::new(0.0, -scale, -scale * 0.5, viewport_center_y),
+            )
+        } else {
+            // Use perspective distance for 4D objects, inverting Y to center properly
+            let center_x = viewport_center_x + camera_offset;
+            canvas::perspective_projection(
+                Vec4::new(scale, 0.0, center_x, center_x * perspective_distance),
+                Vec4::new(0.0, -scale, viewport_center_y, viewport_center_y * perspective_distance),
+                perspective_distance,
+            )
+        };
+        screen_vertices.clear();
+        screen_vertices.extend(transformed_vertices.iter().map(|&vertex| canvas::project(&projection, vertex)));
+        buffers.center = canvas::project(&projection, Vec3::ZERO);
+        
+        edge_order.clear();
+        for (edge, (&(start_idx, end_idx), &edge_emphasis)) in edges.iter().zip(emphasis).enumerate() {
+            if edge_emphasis == EdgeEmphasis::Hidden {
+                continue;
+            }
             let start_3d = transformed_vertices[start_idx];
             let end_3d = transformed_vertices[end_idx];
             
-            
-            let start_2d = if self.orthographic {
-                Vec2::new(
-                    viewport_center_x + start_3d.x * scale + camera_offset,
-                    viewport_center_y - start_3d.y * scale - start_3d.z * scale * 0.5, // Use Z for depth separation
-                )
-            } else {
-                // Use perspective distance for 4D objects
-                let perspective_distance = self.perspective_distance;
-                let perspective_factor = 1.0 / (perspective_distance + start_3d.z);
-                Vec2::new(
-                    viewport_center_x + start_3d.x * scale * perspective_factor + camera_offset,
-                    viewport_center_y - start_3d.y * scale * perspective_factor, // Invert Y to center properly
-                )
-            };
-            
-            let end_2d = if self.orthographic {
-                Vec2::new(
-                    viewport_center_x + end_3d.x * scale + camera_offset,
-                    viewport_center_y - end_3d.y * scale - end_3d.z * scale * 0.5, // Use Z for depth separation
-                )
-            } else {
-                // Use perspective distance for 4D objects
-                let perspective_distance = self.perspective_distance;
-                let perspective_factor = 1.0 / (perspective_distance + end_3d.z);
-                Vec2::new(
-                    viewport_center_x + end_3d.x * scale * perspective_factor + camera_offset,
-                    viewport_center_y - end_3d.y * scale * perspective_factor, // Invert Y to center properly
-                )
-            };
-            
-            // Debug: Print 2D screen coordinates for first few edges when paused
-            if should_print_debug && edge_data.len() < 3 {
-                println!("Edge {}: 3D start ({:.1}, {:.1}, {:.1}) -> 2D start ({:.1}, {:.1})", 
-                    edge_data.len(), start_3d.x, start_3d.y, start_3d.z, start_2d.x, start_2d.y);
-                println!("Edge {}: 3D end ({:.1}, {:.1}, {:.1}) -> 2D end ({:.1}, {:.1})", 
-                    edge_data.len(), end_3d.x, end_3d.y, end_3d.z, end_2d.x, end_2d.y);
-                if !self.orthographic {
-                    let perspective_factor_start = 1.0 / (self.perspective_distance + start_3d.z);
-                    let perspective_factor_end = 1.0 / (self.perspective_distance + end_3d.z);
-                    println!("  Perspective factors: start={:.3}, end={:.3}", perspective_factor_start, perspective_factor_end);
-                }
-            }
-            
-            
             let avg_z = (start_3d.z + end_3d.z) / 2.0;
             
-            // Calculate average W coordinate for W-depth coloring
-            let start_w = transformed_vertices_4d[start_idx].w;
-            let end_w = transformed_vertices_4d[end_idx].w;
-            let avg_w = (start_w + end_w) / 2.0;
-            
             // Store edge data without color - color will be calculated later
-            edge_data.push((avg_z, avg_w, start_2d, end_2d, WHITE)); // Placeholder color
+            edge_order.push((avg_z, edge));
         }
         
-        // Calculate Z range once per frame for consistent depth coloring
-        let mut frame_min_z = f32::MAX;
-        let mut frame_max_z = f32::MIN;
-        for (avg_z, _, _, _, _) in &edge_data {
-            frame_min_z = frame_min_z.min(*avg_z);
-            frame_max_z = frame_max_z.max(*avg_z);
-        }
-        
-        // Debug: Print frame Z range
-        if should_print_debug {
-            println!("Frame Z range: {:.3} to {:.3}", frame_min_z, frame_max_z);
-        }
-        
-        // Sort edges by depth (front to back)
-        edge_data.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        // Sort edges by depth (front to back), drawing a highlighted cell over everything else
+        edge_order.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        edge_order.sort_by_key(|&(_, edge)| emphasis[edge] == EdgeEmphasis::Highlighted);
         
-        // Calculate W range for W-depth coloring
-        let mut frame_min_w = f32::MAX;
-        let mut frame_max_w = f32::MIN;
-        for (_, avg_w, _, _, _) in &edge_data {
-            frame_min_w = frame_min_w.min(*avg_w);
-            frame_max_w = frame_max_w.max(*avg_w);
-        }
-        
-        // Debug: Print frame W range
-        if should_print_debug {
-            println!("Frame W range: {:.3} to {:.3}", frame_min_w, frame_max_w);
+        // Ranges the depth coloring (Z or W) and the depth cues (Z) spread over: this frame's
+        // vertices, or all the shape can reach
+        let (z_range, w_range) = match self.depth_range {
+            DepthRange::PerFrame => (colormap::range_of(transformed_vertices.iter().map(|vertex| vertex.z)), colormap::range_of(w_values.iter().copied())),
+            DepthRange::Fixed => (self.fixed_depth_range(shape.radius, false), self.fixed_depth_range(shape.radius, true)),
+        };
+        let depth_range = if self.w_depth_coloring { w_range } else { z_range };
+        if self.depth_coloring || self.w_depth_coloring {
+            self.depth_legend = Some(depth_range);
         }
         
-        // Draw edges with improved depth coloring
-        for (avg_z, avg_w, start_2d, end_2d, _) in &edge_data {
-            let wire_color = if self.w_depth_coloring {
-                // Use W coordinate for 4th dimension coloring
-                let w_range = frame_max_w - frame_min_w;
-                let normalized_w = if w_range > 0.001 {
-                    (avg_w - frame_min_w) / w_range  // Higher W = higher normalized_w
-                } else {
-                    0.5 // Default to middle if no range
-                };
-                
-                // Debug: Print normalized W values for first few edges
-                if should_print_debug && edge_data.len() < 5 {
-                    println!("Edge {}: avg_w={:.3}, normalized_w={:.3}", edge_data.len(), avg_w, normalized_w);
-                }
-                
-                // Create W-based color bands (different from Z coloring)
-                if self.dark_background {
-                    // Dark background: shades of white/gray based on W (6 bands) - highest W = brightest
-                    if normalized_w > 0.83 {
-                        Color::new(1.0, 1.0, 1.0, 1.0) // White (highest W)
-                    } else if normalized_w > 0.67 {
-                        Color::new(0.9, 0.9, 0.9, 1.0) // Very light gray
-                    } else if normalized_w > 0.50 {
-                        Color::new(0.8, 0.8, 0.8, 1.0) // Light gray
-                    } else if normalized_w > 0.33 {
-                        Color::new(0.7, 0.7, 0.7, 1.0) // Medium gray
-                    } else if normalized_w > 0.17 {
-                        Color::new(0.6, 0.6, 0.6, 1.0) // Dark gray
-                    } else {
-                        Color::new(0.5, 0.5, 0.5, 1.0) // Very dark gray (lowest W)
-                    }
-                } else {
-                    // Light background: shades of black/gray based on W (6 bands) - highest W = darkest
-                    if normalized_w > 0.83 {
-                        Color::new(0.0, 0.0, 0.0, 1.0) // Black (highest W)
-                    } else if normalized_w > 0.67 {
-                        Color::new(0.2, 0.2, 0.2, 1.0) // Very dark gray
-                    } else if normalized_w > 0.50 {
-                        Color::new(0.4, 0.4, 0.4, 1.0) // Dark gray
-                    } else if normalized_w > 0.33 {
-                        Color::new(0.6, 0.6, 0.6, 1.0) // Medium gray
-                    } else if normalized_w > 0.17 {
-                        Color::new(0.8, 0.8, 0.8, 1.0) // Light gray
-                    } else {
-                        Color::new(0.9, 0.9, 0.9, 1.0) // Very light gray (lowest W)
-                    }
-                }
-            } else if self.depth_coloring {
-                // Use frame-wide Z range for consistent coloring
-                // Invert the calculation so closer edges (lower Z) get higher normalized values
-                let z_range = frame_max_z - frame_min_z;
-                let normalized_z = if z_range > 0.001 {
-                    1.0 - (avg_z - frame_min_z) / z_range  // Invert: closer = higher normalized_z
-                } else {
-                    0.5 // Default to middle if no range
-                };
-                
-                // Debug: Print normalized Z values for first few edges
-                if should_print_debug && edge_data.len() < 5 {
-                    println!("Edge {}: avg_z={:.3}, normalized_z={:.3}", edge_data.len(), avg_z, normalized_z);
-                }
-                
-                // Create more color bands for better distribution
-                if self.dark_background {
-                    // Dark background: shades of white/gray (6 bands) - closest = brightest
-                    if normalized_z > 0.83 {
-                        Color::new(1.0, 1.0, 1.0, 1.0) // White (closest)
-                    } else if normalized_z > 0.67 {
-                        Color::new(0.95, 0.95, 0.95, 1.0) // Very light gray
-                    } else if normalized_z > 0.50 {
-                        Color::new(0.9, 0.9, 0.9, 1.0) // Light gray
-                    } else if normalized_z > 0.33 {
-                        Color::new(0.85, 0.85, 0.85, 1.0) // Medium-light gray
-                    } else if normalized_z > 0.17 {
-                        Color::new(0.8, 0.8, 0.8, 1.0) // Medium gray
-                    } else {
-                        Color::new(0.75, 0.75, 0.75, 1.0) // Light gray (farthest)
-                    }
-                } else {
-                    // Light background: dark colors (6 bands) - closest = darkest
-                    if normalized_z > 0.83 {
-                        Color::new(0.1, 0.1, 0.1, 1.0) // Very dark gray (closest)
-                    } else if normalized_z > 0.67 {
-                        Color::new(0.25, 0.25, 0.25, 1.0) // Dark gray
-                    } else if normalized_z > 0.50 {
-                        Color::new(0.4, 0.4, 0.4, 1.0) // Medium-dark gray
-                    } else if normalized_z > 0.33 {
-                        Color::new(0.55, 0.55, 0.55, 1.0) // Medium gray
-                    } else if normalized_z > 0.17 {
-                        Color::new(0.7, 0.7, 0.7, 1.0) // Light gray
-                    } else {
-                        Color::new(0.85, 0.85, 0.85, 1.0) // Very light gray (farthest)
-                    }
+        // Vertex dots and balls colored like the edges, each with the emphasis of its most
+        // visible edge
+        let plain_color = if self.dark_background { Color::new(0.8, 0.8, 0.8, 1.0) } else { Color::new(0.2, 0.2, 0.2, 1.0) };
+        let background = self.background_color();
+        // Color at a vertex of something drawn with the given emphasis
+        let color_with = |emphasis: EdgeEmphasis, index: usize| {
+            let fade = if emphasis == EdgeEmphasis::Dimmed { DIMMED_EDGE_FADE } else { 0.0 };
+            let faded = |color: Color| Color::from_vec(color.to_vec().lerp(background.to_vec(), fade));
+            match (emphasis, tint) {
+                (EdgeEmphasis::Hidden, _) => None,
+                (EdgeEmphasis::Highlighted, _) => Some(tint.unwrap_or(CELL_HIGHLIGHT_COLOR)),
+                (_, Some(tint)) => Some(faded(tint)),
+                _ if self.w_depth_coloring => Some(faded(self.palette.color(colormap::normalize(w_values[index], w_range), self.dark_background))),
+                _ if self.depth_coloring => {
+                    let emphasis = 1.0 - colormap::normalize(transformed_vertices[index].z, z_range);
+                    Some(faded(self.palette.color(emphasis, self.dark_background)))
                 }
-            } else {
-                // Uniform color when depth coloring is off
-                if self.dark_background {
-                    Color::new(0.8, 0.8, 0.8, 1.0)
+                _ => Some(faded(plain_color)),
+            }
+        };
+        let vertex_color = |index: usize| color_with(vertex_emphasis[index], index);
+        
+        if self.render_style == RenderStyle::Tubes {
+            // Each tube takes the colors of the vertices at its ends, with its own emphasis
+            let pixels_per_unit = if self.orthographic { scale } else { scale / perspective_distance };
+            let edge_colors = |edge: usize| {
+                let ((start_idx, end_idx), edge_emphasis) = (edges[edge], emphasis[edge]);
+                let radius_scale = if edge_emphasis == EdgeEmphasis::Highlighted { HIGHLIGHT_WIDTH_SCALE } else { 1.0 };
+                Some((color_with(edge_emphasis, start_idx)?, color_with(edge_emphasis, end_idx)?, radius_scale))
+            };
+            let tube_view = (&projection, pixels_per_unit, z_range);
+            self.draw_ball_and_stick(canvas, solid, tube_view, (transformed_vertices, edges), edge_colors, vertex_color);
+        } else {
+            // Draw edges with depth coloring, each color following the depth along the edge
+            for &(_, edge) in edge_order.iter() {
+                let ((start_idx, end_idx), edge_emphasis) = (edges[edge], emphasis[edge]);
+                let fade = if edge_emphasis == EdgeEmphasis::Dimmed { DIMMED_EDGE_FADE } else { 0.0 };
+                let faded = |color: Color| Color::from_vec(color.to_vec().lerp(background.to_vec(), fade));
+                let ends = (screen_vertices[start_idx], screen_vertices[end_idx]);
+                let farness = |index: usize| colormap::normalize(transformed_vertices[index].z, z_range);
+                let farness = (farness(start_idx), farness(end_idx));
+                if edge_emphasis == EdgeEmphasis::Highlighted {
+                    let color = tint.unwrap_or(CELL_HIGHLIGHT_COLOR);
+                    self.draw_cued_edge(canvas, ends, farness, (0.0, 0.0), |_| color, HIGHLIGHT_WIDTH_SCALE);
+                } else if let Some(tint) = tint {
+                    // Fixed eye color (anaglyph)
+                    self.draw_cued_edge(canvas, ends, farness, (0.0, 0.0), |_| faded(tint), 1.0);
+                } else if self.depth_coloring || self.w_depth_coloring {
+                    // Higher W, or closer Z, stands out more
+                    let emphasis = |index: usize| {
+                        if self.w_depth_coloring {
+                            colormap::normalize(w_values[index], w_range)
+                        } else {
+                            1.0 - colormap::normalize(transformed_vertices[index].z, z_range)
+                        }
+                    };
+                    let color_at = |emphasis: f32| faded(self.palette.color(emphasis, self.dark_background));
+                    self.draw_cued_edge(canvas, ends, farness, (emphasis(start_idx), emphasis(end_idx)), color_at, 1.0);
                 } else {
-                    Color::new(0.2, 0.2, 0.2, 1.0)
+                    // Uniform color when depth coloring is off
+                    self.draw_cued_edge(canvas, ends, farness, (0.0, 0.0), |_| faded(plain_color), 1.0);
                 }
-            };
-            
-            draw_line(start_2d.x, start_2d.y, end_2d.x, end_2d.y, 2.0, wire_color);
+            }
         }
-        
-        
-        // Debug info moved to main UI section to avoid duplicate drawing
+        self.draw_vertex_marks(canvas, (screen_vertices, transformed_vertices, &mesh.vertices), vertex_order, z_range, vertex_color);
+    }
+}
+
+// Row spacing and slider height of the 4D controls panel, squeezed to fit many planes
+fn plane_slider_layout(planes: usize) -> (f32, f32) {
+    let spacing = (700.0 / planes.max(1) as f32).min(35.0);
+    (spacing, (spacing - 15.0).min(20.0))
+}
+
+// Width of the stereogram area, which always uses the left portion of the screen
+fn stereogram_width() -> f32 {
+    let raw_screen_width = screen_width();
+    
+    // For web version, treat display width like native version (constrained)
+    // Native: window is 663px (393px stereogram + 270px panel)
+    // Web: constrain to match native behavior
+    #[cfg(target_arch = "wasm32")]
+    let display_width = raw_screen_width.min(663.0); // Match native window width
+    #[cfg(not(target_arch = "wasm32"))]
+    let display_width = raw_screen_width;
+    
+    display_width.min(393.0) // Optimal width for stereogram fusion
+}
+
+// Area of the panel at the right of the stereogram, shared by the help and the inspector
+fn side_panel_rect() -> Rect {
+    // For web version, treat display width like native version (constrained)
+    #[cfg(target_arch = "wasm32")]
+    let display_width = screen_width().min(663.0); // Match native window width
+    #[cfg(not(target_arch = "wasm32"))]
+    let display_width = screen_width();
+    let stereogram_width = stereogram_width();
+    
+    // Determine panel width (match stereogram-side calculations)
+    let panel_width = if cfg!(target_arch = "wasm32") {
+        (display_width - stereogram_width - 20.0).clamp(120.0, 220.0)
+    } else {
+        270.0
+    };
+    let panel_x = (display_width - panel_width - 10.0).max(stereogram_width + 10.0);
+    Rect::new(panel_x, 10.0, panel_width, screen_height() - 20.0)
+}
+
+// Area of the 4D rotation preset panel, at the bottom left over the stereogram
+fn preset_panel_rect() -> Rect {
+    Rect::new(10.0, screen_height() - 170.0, 260.0, 140.0)
+}
+
+// Area of the uniform polytope panel with this many lines, at the bottom left over the
+// stereogram
+fn uniform_panel_rect(line_count: usize) -> Rect {
+    let panel_height = 40.0 + line_count as f32 * 17.0;
+    Rect::new(10.0, screen_height() - 30.0 - panel_height, 280.0, panel_height)
+}
+
+// Number of frames recorded with Shift+R
+#[cfg(not(target_arch = "wasm32"))]
+const RECORD_FIXED_FRAMES: usize = 120;
+
+// Longest seamless loop recorded with R (a minute at the recordings' 30 frames per
+// second), and how far it may change any rotation speed without looking for a longer one
+#[cfg(not(target_arch = "wasm32"))]
+const RECORD_MAX_LOOP_FRAMES: usize = 1800;
+#[cfg(not(target_arch = "wasm32"))]
+const LOOP_SPEED_TOLERANCE: f32 = 0.05;
+
+// Nearest velocity that makes a whole number of turns over `frames` steps. A rotation
+// that moves at all makes at least one, so that it does not stop in the recording.
+#[cfg(not(target_arch = "wasm32"))]
+fn loop_velocity(velocity: f32, frames: usize) -> f32 {
+    let turn_step = std::f32::consts::TAU / frames as f32;
+    let turns = (velocity / turn_step).round();
+    if turns == 0.0 && velocity != 0.0 {
+        velocity.signum() * turn_step
+    } else {
+        turns * turn_step
     }
 }
 
-fn window_conf() -> Conf {
-    Conf {
-        window_title: "iPhone Stereogram Viewer".to_owned(),
-        window_width: 663, // 393 (stereogram) + 270 (panel)
-        window_height: 852,
+#[cfg(not(target_arch = "wasm32"))]
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn window_conf() -> macroquad::conf::Conf {
+    macroquad::conf::Conf {
+        miniquad_conf: Conf {
+            window_title: "iPhone Stereogram Viewer".to_owned(),
+            window_width: 663, // 393 (stereogram) + 270 (panel)
+            window_height: 852,
+            ..Default::default()
+        },
+        // Room for a full batch of edges in a single draw call
+        draw_call_vertex_capacity: canvas::BATCH_VERTICES,
+        draw_call_index_capacity: canvas::BATCH_INDICES,
         ..Default::default()
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    let mut viewer = StereogramViewer::new();
+fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use clap::Parser;
+        let cli = cli::Cli::parse();
+        
+        // `stero3d render ...` draws a single image without ever opening a window
+        if let Some(cli::Command::Render(args)) = &cli.command {
+            if let Err(err) = cli::render(args) {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+        
+        // Seed the initial random spin: reproducible with --seed, different every launch without
+        let seed = cli.seed.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or(0)
+        });
+        rand::srand(seed);
+        
+        // Saved settings first, then anything given on the command line
+        let mut viewer = StereogramViewer::new();
+        let stored = Settings::load().unwrap_or_else(Settings::defaults);
+        stored.apply(&mut viewer);
+        if let Err(err) = cli.view.apply(&mut viewer) {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+        if let Some(path) = &cli.timeline {
+            match timeline::Timeline::load(path) {
+                Ok(loaded) => viewer.timeline = loaded,
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        
+        let mut conf = window_conf();
+        if let Some((width, height)) = cli.window_size {
+            conf.miniquad_conf.window_width = width as i32;
+            conf.miniquad_conf.window_height = height as i32;
+        }
+        macroquad::Window::from_config(conf, run_viewer(viewer, stored, cli.view.scene));
+    }
+    
+    #[cfg(target_arch = "wasm32")]
+    {
+        // Saved settings first, then the state encoded in the page URL, e.g. ?mode=4d&shape=tesseract&wcolor=1
+        let mut viewer = StereogramViewer::new();
+        let stored = Settings::load().unwrap_or_else(Settings::defaults);
+        stored.apply(&mut viewer);
+        url_state::apply_query(&mut viewer, &url_state::read_page_query());
+        macroquad::Window::from_config(window_conf(), run_viewer(viewer, stored));
+    }
+}
+
+async fn run_viewer(
+    mut viewer: StereogramViewer,
+    stored_settings: Settings,
+    #[cfg(not(target_arch = "wasm32"))] mut scene_path: Option<std::path::PathBuf>,
+) {
+    // Saves viewing preferences whenever the user changes them
+    let mut settings_store = SettingsStore::new(stored_settings, &viewer);
+    
+    // Batches each eye view's edges into one draw call, reusing its buffers every frame
+    let mut screen_canvas = ScreenCanvas::new();
+    
+    // Off-screen recording state (native only, the web build cannot write files)
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut recording: Option<record::Recording> = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut record_format = record::RecordFormat::Gif;
+    
+    // Keeps the page URL in sync with the viewer so any view can be shared as a link
+    #[cfg(target_arch = "wasm32")]
+    let mut url_sync = url_state::UrlSync::default();
     
     loop {
-        viewer.update();
+        viewer.update(get_frame_time());
+        viewer.update_transition(get_frame_time());
+        viewer.wiggle_clock += get_frame_time();
         
         // Render stereogram
-        viewer.render_stereogram();
+        viewer.render_stereogram(&mut screen_canvas);
         
         // Draw UI overlay
         set_default_camera();
@@ -1013,7 +2424,10 @@ async fn main() {
             let panel_x = 10.0;
             let panel_y = 50.0;
             let panel_width = 300.0;
-            let panel_height = 340.0;
+            // One row per rotation plane in 4D mode, from 6 planes up to 28 in 8D
+            let rotation_planes = if viewer.is_4d_mode { viewer.rotation_planes() } else { Vec::new() };
+            let (plane_spacing, plane_slider_height) = plane_slider_layout(rotation_planes.len());
+            let panel_height = (90.0 + rotation_planes.len() as f32 * plane_spacing).max(340.0);
             
             // Panel background
             draw_rectangle(
@@ -1053,18 +2467,9 @@ async fn main() {
             
             if viewer.is_4d_mode {
                 // Draw 4D rotation sliders in two columns
-                let rotation_angles = [
-                    (viewer.rotation_xy, "XY"),
-                    (viewer.rotation_xz, "XZ"),
-                    (viewer.rotation_yz, "YZ"),
-                    (viewer.rotation_xw, "XW"),
-                    (viewer.rotation_yw, "YW"),
-                    (viewer.rotation_zw, "ZW"),
-                ];
-                
                 // Left column: Angle sliders
-                for (i, (rotation_angle, label)) in rotation_angles.iter().enumerate() {
-                    let y_pos = angle_slider_y + (i as f32) * angle_slider_spacing;
+                for (i, (label, rotation_angle, _)) in rotation_planes.iter().enumerate() {
+                    let y_pos = angle_slider_y + (i as f32) * plane_spacing;
                     
                     // Label
                     draw_text(
@@ -1079,21 +2484,21 @@ async fn main() {
                     draw_text(
                         "0°",
                         angle_slider_x - 15.0,
-                        y_pos + 15.0,
+                        y_pos + plane_slider_height * 0.75,
                         12.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
                     draw_text(
                         "180°",
                         angle_slider_x + angle_slider_width / 2.0 - 15.0,
-                        y_pos + 15.0,
+                        y_pos + plane_slider_height * 0.75,
                         12.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
                     draw_text(
                         "360°",
                         angle_slider_x + angle_slider_width + 5.0,
-                        y_pos + 15.0,
+                        y_pos + plane_slider_height * 0.75,
                         12.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
@@ -1103,7 +2508,7 @@ async fn main() {
                         angle_slider_x,
                         y_pos,
                         angle_slider_width,
-                        angle_slider_height,
+                        plane_slider_height,
                         if viewer.dark_background { Color::new(0.3, 0.3, 0.3, 1.0) } else { Color::new(0.7, 0.7, 0.7, 1.0) }
                     );
                     
@@ -1115,7 +2520,7 @@ async fn main() {
                         handle_x - 5.0,
                         y_pos + 2.0,
                         10.0,
-                        angle_slider_height - 4.0,
+                        plane_slider_height - 4.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
                 }
@@ -1287,24 +2692,15 @@ async fn main() {
             
             if viewer.is_4d_mode {
                 // Right column: Velocity sliders
-                let rotation_velocities = [
-                    (viewer.rotation_velocity_xy, "dXY"),
-                    (viewer.rotation_velocity_xz, "dXZ"),
-                    (viewer.rotation_velocity_yz, "dYZ"),
-                    (viewer.rotation_velocity_xw, "dXW"),
-                    (viewer.rotation_velocity_yw, "dYW"),
-                    (viewer.rotation_velocity_zw, "dZW"),
-                ];
-                
                 // Position velocity sliders in right column
                 let velocity_slider_x = panel_x + 150.0; // Right column position, closer to center
                 
-                for (i, (rotation_velocity, label)) in rotation_velocities.iter().enumerate() {
-                    let y_pos = angle_slider_y + (i as f32) * angle_slider_spacing; // Same Y as angle sliders
+                for (i, (label, _, rotation_velocity)) in rotation_planes.iter().enumerate() {
+                    let y_pos = angle_slider_y + (i as f32) * plane_spacing; // Same Y as angle sliders
                     
                     // Label
                     draw_text(
-                        label,
+                        &format!("d{}", label),
                         velocity_slider_x,
                         y_pos - 5.0,
                         14.0,
@@ -1315,21 +2711,21 @@ async fn main() {
                     draw_text(
                         "-",
                         velocity_slider_x - 10.0,
-                        y_pos + 15.0,
+                        y_pos + plane_slider_height * 0.75,
                         12.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
                     draw_text(
                         "0",
                         velocity_slider_x + slider_width / 2.0 - 3.0,
-                        y_pos + 15.0,
+                        y_pos + plane_slider_height * 0.75,
                         12.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
                     draw_text(
                         "+",
                         velocity_slider_x + slider_width + 5.0,
-                        y_pos + 15.0,
+                        y_pos + plane_slider_height * 0.75,
                         12.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
@@ -1339,7 +2735,7 @@ async fn main() {
                         velocity_slider_x,
                         y_pos,
                         slider_width,
-                        slider_height,
+                        plane_slider_height,
                         if viewer.dark_background { Color::new(0.3, 0.3, 0.3, 1.0) } else { Color::new(0.7, 0.7, 0.7, 1.0) }
                     );
                     
@@ -1348,7 +2744,7 @@ async fn main() {
                         velocity_slider_x + slider_width / 2.0,
                         y_pos,
                         velocity_slider_x + slider_width / 2.0,
-                        y_pos + slider_height,
+                        y_pos + plane_slider_height,
                         1.0,
                         if viewer.dark_background { Color::new(0.5, 0.5, 0.5, 1.0) } else { Color::new(0.3, 0.3, 0.3, 1.0) }
                     );
@@ -1359,7 +2755,7 @@ async fn main() {
                         handle_x - 5.0,
                         y_pos + 2.0,
                         10.0,
-                        slider_height - 4.0,
+                        plane_slider_height - 4.0,
                         if viewer.dark_background { WHITE } else { BLACK }
                     );
                 }
@@ -1550,7 +2946,7 @@ async fn main() {
             draw_text(
                 "Click and drag sliders to adjust rotation speed",
                 panel_x + 10.0,
-                panel_y + 310.0,
+                panel_y + panel_height - 30.0,
                 12.0,
                 if viewer.dark_background { Color::new(0.7, 0.7, 0.7, 1.0) } else { Color::new(0.4, 0.4, 0.4, 1.0) }
             );
@@ -1558,9 +2954,9 @@ async fn main() {
         
         // Mode and shape label below buttons - always visible (moved to safe position)
         let mode_text = if viewer.is_4d_mode {
-            format!("4D {}", viewer.current_hypersolid.name())
+            format!("4D {}", viewer.shape_name())
         } else {
-            format!("3D {}", viewer.current_solid.name())
+            format!("3D {}", viewer.shape_name())
         };
         draw_text(
             &mode_text,
@@ -1571,29 +2967,8 @@ async fn main() {
         );
         
         if viewer.show_ui {
-            let raw_screen_width = screen_width();
-            let screen_height = screen_height();
-            
-            // For web version, treat display width like native version (constrained)
-            #[cfg(target_arch = "wasm32")]
-            let display_width = raw_screen_width.min(663.0); // Match native window width
-            #[cfg(not(target_arch = "wasm32"))]
-            let display_width = raw_screen_width;
-            
-            let stereogram_width = display_width.min(393.0);
-            
-            // Determine help panel width (match stereogram-side calculations)
-            let panel_width = if cfg!(target_arch = "wasm32") {
-                (display_width - stereogram_width - 20.0).clamp(120.0, 220.0)
-            } else {
-                270.0
-            };
-            
             // Create right-side help panel with semi-transparent background
-            let min_panel_x = stereogram_width + 10.0;
-            let panel_x = (display_width - panel_width - 10.0).max(min_panel_x);
-            let panel_y = 10.0;
-            let panel_height = screen_height - 20.0;
+            let Rect { x: panel_x, y: panel_y, w: panel_width, h: panel_height } = side_panel_rect();
             
             // Draw semi-transparent panel background
             draw_rectangle(
@@ -1668,86 +3043,305 @@ async fn main() {
             let hint_color = if viewer.dark_background { YELLOW } else { Color::new(0.8, 0.6, 0.0, 1.0) };
             let info_color = if viewer.dark_background { ORANGE } else { Color::new(0.8, 0.4, 0.0, 1.0) };
             
-            // Fusion hint
-            draw_text(
-                "Focus THROUGH screen to see 3D!",
-                panel_x + 10.0,
-                help_y,
-                16.0,
-                hint_color
-            );
-            help_y += 25.0;
-            
-            // Basic controls
-            draw_text("Basic Controls:", panel_x + 10.0, help_y, 16.0, status_color);
-            help_y += 22.0;
-            
-            // Basic controls in right panel
-            draw_text("SPACE - Pause/Resume", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("G - Toggle guides", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("T - Toggle UI", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("B - Background color", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 30.0;
-            
-            // Viewing controls
-            draw_text("Viewing:", panel_x + 10.0, help_y, 16.0, status_color);
-            help_y += 22.0;
-            draw_text("Z - Z-depth coloring", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("W - W-depth coloring", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("O - Projection mode", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("←/→ - Eye separation", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("↑/↓ - Perspective", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 30.0;
-            
-            // Mode controls
-            draw_text("Mode:", panel_x + 10.0, help_y, 16.0, status_color);
-            help_y += 22.0;
-            draw_text("H - Toggle 3D/4D", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("S - 3D solids", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 20.0;
-            draw_text("J - 4D hypersolids", panel_x + 10.0, help_y, 14.0, text_color);
-            help_y += 30.0;
+            // Help and status sections, each a title and its lines, laid out on as many
+            // pages as the panel's height needs
+            let keys = |lines: &[&str]| -> Vec<String> { lines.iter().map(|&line| line.to_owned()).collect() };
+            let mut sections: Vec<(&str, Vec<String>, Color)> = vec![
+                (
+                    "Basic Controls:",
+                    keys(&[
+                        "SPACE - Pause/Resume",
+                        "G - Toggle guides",
+                        "T - Toggle UI",
+                        "B - Background color",
+                        "Ctrl+D - Reset settings",
+                        "Shift+D - Vertex inspector",
+                    ]),
+                    text_color,
+                ),
+                (
+                    "Viewing:",
+                    keys(&[
+                        "Z - Z-depth coloring",
+                        "W - W-depth coloring",
+                        "Shift+Z - Depth colormap",
+                        "Shift+W - Depth range: frame/fixed",
+                        "Shift+G - Line width by depth",
+                        "Shift+B - Fog: off/light/medium/dense",
+                        "O - Projection mode",
+                        "←/→ - Eye separation",
+                        "↑/↓ - Perspective",
+                        "L - Stereo layout",
+                        "[/] - Wiggle rate",
+                        "I - Wiggle in-between views",
+                        "Shift+I - Vertex dots, numbers, coordinates",
+                        "Shift+T - Wireframe or ball and stick",
+                    ]),
+                    text_color,
+                ),
+            ];
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let mut recording = keys(&["R - Record full loop"]);
+                recording.push(format!("Shift+R - Record {} frames", RECORD_FIXED_FRAMES));
+                recording.push("F - Recording format".to_owned());
+                sections.push(("Recording:", recording, text_color));
+                sections.push(("Scenes:", keys(&["F5 - Save scene file", "F9 - Reload scene file"]), text_color));
+            }
+            let timeline = [
+                "K - Add keyframe",
+                "Shift+K - Remove last keyframe",
+                "E - Easing into last keyframe",
+                "P - Play/stop timeline",
+                #[cfg(not(target_arch = "wasm32"))]
+                "Ctrl+R - Record timeline",
+                #[cfg(not(target_arch = "wasm32"))]
+                "F6 - Save timeline file",
+            ];
+            sections.push(("Timeline:", keys(&timeline), text_color));
+            sections.push((
+                "Mode:",
+                keys(&[
+                    "H - Toggle 3D/4D",
+                    "S - 3D solids",
+                    "J - 4D hypersolids",
+                    "U - Dimension 4-8 (4D mode)",
+                    "A/Shift+A - Duoprism sides, prism base",
+                    "M - 4D rotation presets",
+                    "Q - Uniform polytopes (Wythoff)",
+                    "V/Shift+V, click - Highlight a cell (4D)",
+                    "Y - Only the cell and its neighbors",
+                    "X - Morph to dual and back",
+                    "-/= - Morph step",
+                ]),
+                text_color,
+            ));
+            sections.push((
+                "Picking and Measuring:",
+                keys(&[
+                    "Click - Pick a vertex or edge",
+                    "Shift+click - Measure vertices or faces",
+                    "Shift+M - Circumradius and inradius",
+                    "Shift+S - Shape statistics",
+                ]),
+                text_color,
+            ));
+            sections.push(("Bookmarks:", keys(&["Ctrl+1..0 - Save view", "1..0 - Glide to saved view"]), text_color));
             
             // Status info in right panel
-            draw_text("Status:", panel_x + 10.0, help_y, 16.0, status_color);
-            help_y += 22.0;
-            draw_text(&format!("Eye Sep: {:.3}", viewer.eye_separation), panel_x + 10.0, help_y, 14.0, info_color);
-            help_y += 20.0;
-            draw_text(&format!("Distance: {:.1}", viewer.perspective_distance), panel_x + 10.0, help_y, 14.0, info_color);
-            help_y += 20.0;
-            draw_text(&format!("Projection: {}", if viewer.orthographic { "Ortho" } else { "Persp" }), panel_x + 10.0, help_y, 14.0, info_color);
-            help_y += 20.0;
+            let mut status = vec![
+                format!("Eye Sep: {:.3}", viewer.eye_separation),
+                format!("Distance: {:.1}", viewer.perspective_distance),
+                format!("Projection: {}", if viewer.orthographic { "Ortho" } else { "Persp" }),
+                format!("Layout: {}", viewer.stereo_layout.name()),
+                format!("Colormap: {}, {}", viewer.palette.name(), viewer.depth_range.name().to_lowercase()),
+            ];
+            if viewer.render_style != RenderStyle::Wireframe {
+                status.push(format!("Style: {}", viewer.render_style.name()));
+            }
+            if viewer.vertex_marks != VertexMarks::Off {
+                status.push(format!("Vertices: {}", viewer.vertex_marks.name().to_lowercase()));
+            }
+            if viewer.depth_width || viewer.fog != Fog::Off {
+                let width = if viewer.depth_width { "width by depth" } else { "fixed width" };
+                status.push(format!("Depth cues: {}, {} fog", width, viewer.fog.name().to_lowercase()));
+            }
+            if viewer.stereo_layout == StereoLayout::Wiggle {
+                status.push(format!("Wiggle: {:.1}/s, {} between", viewer.wiggle_rate, viewer.wiggle_in_between));
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            status.push(format!("Record as: {}", record_format.name()));
+            if let Some(clock) = viewer.timeline_clock {
+                status.push(format!("Timeline: {:.1}/{:.1} s", clock, viewer.timeline.duration()));
+            } else if let Some(easing) = viewer.timeline.last_easing() {
+                status.push(format!("Timeline: {} keys, {:.1} s", viewer.timeline.len(), viewer.timeline.duration()));
+                status.push(format!("Last easing: {}", easing.name()));
+            }
+            if viewer.morph > 0.0 && viewer.has_dual_morph() {
+                status.push(format!("Morph: {:.0}% to {}", viewer.morph * 100.0, viewer.dual_name()));
+            }
+            if let Some(cell) = viewer.selected_cell.filter(|_| viewer.cells_shown()) {
+                let vertices = viewer.cells[cell].len();
+                status.push(format!("Cell: {} of {} ({} vertices)", cell + 1, viewer.cells.len(), vertices));
+            }
+            let saved_views = viewer.bookmarks.labels(&viewer.shape_name());
+            if !saved_views.is_empty() {
+                status.push(format!("Saved views: {}", saved_views));
+            }
             if !viewer.is_4d_mode {
-                draw_text(&format!("Solid: {}", viewer.current_solid.name()), panel_x + 10.0, help_y, 14.0, info_color);
+                status.push(format!("Solid: {}", viewer.current_solid.name()));
+            }
+            sections.push(("Status:", status, info_color));
+            
+            // Pages of whole sections, below the fusion hint on the first and above the page line
+            let section_height = |lines: usize| 22.0 + 20.0 * lines as f32 + 10.0;
+            let page_bottom = panel_y + panel_height - 30.0;
+            let mut pages: Vec<Vec<usize>> = Vec::new();
+            let mut page: Vec<usize> = Vec::new();
+            let mut page_y = help_y + 25.0;
+            for (index, (_, lines, _)) in sections.iter().enumerate() {
+                let height = section_height(lines.len());
+                if page_y + height > page_bottom && !page.is_empty() {
+                    pages.push(std::mem::take(&mut page));
+                    page_y = help_y;
+                }
+                page.push(index);
+                page_y += height;
             }
+            pages.push(page);
+            let page = viewer.help_page % pages.len();
             
-
+            // Fusion hint
+            if page == 0 {
+                draw_text("Focus THROUGH screen to see 3D!", panel_x + 10.0, help_y, 16.0, hint_color);
+                help_y += 25.0;
+            }
+            for &index in &pages[page] {
+                let (title, lines, color) = &sections[index];
+                draw_text(title, panel_x + 10.0, help_y, 16.0, status_color);
+                help_y += 22.0;
+                for line in lines {
+                    draw_text(line, panel_x + 10.0, help_y, 14.0, *color);
+                    help_y += 20.0;
+                }
+                help_y += 10.0;
+            }
+            if pages.len() > 1 {
+                let footer = format!("Tab - More help (page {} of {})", page + 1, pages.len());
+                draw_text(&footer, panel_x + 10.0, panel_y + panel_height - 12.0, 14.0, hint_color);
+            }
+        }
+        
+        // Vertex inspector, in place of the help panel
+        if viewer.show_inspector {
+            let selected = match viewer.picked {
+                Some(Picked::Vertex(index)) => Some(index),
+                _ => None,
+            };
+            inspector::Snapshot::capture(&viewer).draw_panel(side_panel_rect(), selected, viewer.inspector_scroll, viewer.dark_background);
+        }
+        
+        // Counts, Euler characteristic and symmetry of the shape (upper right of the stereogram)
+        if viewer.show_stats {
+            if !viewer.stats.as_ref().is_some_and(|stats| stats.is_for(&viewer.faces_shape_key())) {
+                viewer.stats = Some(Stats::compute(&viewer));
+            }
+            if let Some(stats) = &viewer.stats {
+                stats.draw_panel(stereogram_width(), viewer.dark_background);
+            }
+        }
+        
+        // What the picked vertex or edge is part of (bottom left, above the preset panel)
+        if let Some(picked) = viewer.picked {
+            let (title, lines) = viewer.describe_picked(picked);
+            picking::draw_panel(&title, &lines, viewer.picked_panel_bottom(), viewer.dark_background);
+        }
+        
+        // 4D rotation preset panel (bottom left, over the stereogram)
+        if viewer.show_rotation_presets {
+            let Rect { x: panel_x, y: panel_y, w: panel_width, h: panel_height } = preset_panel_rect();
+            draw_rectangle(
+                panel_x,
+                panel_y,
+                panel_width,
+                panel_height,
+                if viewer.dark_background { Color::new(0.1, 0.1, 0.1, 0.9) } else { Color::new(0.95, 0.95, 0.95, 0.9) }
+            );
+            let title_color = if viewer.dark_background { WHITE } else { BLACK };
+            let line_color = if viewer.dark_background { LIME } else { Color::new(0.0, 0.4, 0.0, 1.0) };
+            draw_text("4D ROTATION PRESET", panel_x + 10.0, panel_y + 22.0, 16.0, title_color);
+            
+            let preset = &viewer.rotation_preset;
+            let mut line_y = panel_y + 45.0;
+            let mut lines = vec![format!("N - Preset: {}", preset.kind.name())];
+            if preset.is_active() {
+                lines.push(format!("C - Planes: {}", preset.planes_name()));
+                lines.push(format!(",/. - Speed: {:.2}°/frame", preset.speed(0).to_degrees()));
+                if preset.kind == PresetKind::Double {
+                    lines.push(format!(";/' - Second speed: {:.2}°/frame", preset.speed(1).to_degrees()));
+                } else if preset.kind != PresetKind::Simple {
+                    lines.push("Both planes turn at this speed".to_owned());
+                }
+            }
+            if !viewer.is_4d_mode {
+                lines.push("Presets apply in 4D mode (H)".to_owned());
+            }
+            for line in lines {
+                draw_text(&line, panel_x + 10.0, line_y, 14.0, line_color);
+                line_y += 20.0;
+            }
+        }
+        
+        // Depth coloring legend (right edge of the stereogram area): the colormap from the
+        // emphasized end at the top to the faded end at the bottom, with the range it covers
+        if let Some((low, high)) = viewer.depth_legend.filter(|_| viewer.show_ui && viewer.stereo_layout != StereoLayout::Anaglyph) {
+            let text_color = if viewer.dark_background { WHITE } else { BLACK };
+            let bar_x = stereogram_width() - 30.0;
+            let bar_height = 150.0;
+            let bar_y = screen_height() - 60.0 - bar_height;
+            const LEGEND_STEPS: usize = 30;
+            for step in 0..LEGEND_STEPS {
+                let emphasis = 1.0 - step as f32 / (LEGEND_STEPS - 1) as f32;
+                let step_height = bar_height / LEGEND_STEPS as f32;
+                let color = viewer.palette.color(emphasis, viewer.dark_background);
+                draw_rectangle(bar_x, bar_y + step as f32 * step_height, 14.0, step_height + 0.5, color);
+            }
+            draw_rectangle_lines(bar_x, bar_y, 14.0, bar_height, 1.0, text_color);
+            
+            // Nearest Z at the top, highest W at the top
+            let (title, top, bottom) = if viewer.w_depth_coloring {
+                ("W", format!("{:+.2}", high), format!("{:+.2}", low))
+            } else {
+                ("Z", format!("near {:+.1}", low), format!("far {:+.1}", high))
+            };
+            let label_width = |text: &str| measure_text(text, None, 13, 1.0).width;
+            draw_text(title, bar_x + 7.0 - label_width(title) / 2.0, bar_y - 22.0, 14.0, text_color);
+            draw_text(&top, bar_x - 4.0 - label_width(&top), bar_y + 10.0, 13.0, text_color);
+            draw_text(&bottom, bar_x - 4.0 - label_width(&bottom), bar_y + bar_height, 13.0, text_color);
+            let mode = viewer.depth_range.name().to_lowercase();
+            draw_text(&mode, bar_x + 14.0 - label_width(&mode), bar_y + bar_height + 18.0, 13.0, text_color);
+        }
+        
+        // Uniform polytope panel (bottom left, over the stereogram, in place of the preset panel)
+        if viewer.show_uniform_panel {
+            let title_color = if viewer.dark_background { WHITE } else { BLACK };
+            let line_color = if viewer.dark_background { LIME } else { Color::new(0.0, 0.4, 0.0, 1.0) };
+            let lines = viewer.uniform_panel_lines();
+            let Rect { x: panel_x, y: panel_y, w: panel_width, h: panel_height } = uniform_panel_rect(lines.len());
+            draw_rectangle(
+                panel_x,
+                panel_y,
+                panel_width,
+                panel_height,
+                if viewer.dark_background { Color::new(0.1, 0.1, 0.1, 0.9) } else { Color::new(0.95, 0.95, 0.95, 0.9) }
+            );
+            draw_text("UNIFORM POLYTOPES", panel_x + 10.0, panel_y + 22.0, 16.0, title_color);
+            let mut line_y = panel_y + 43.0;
+            for (line, is_selected) in lines {
+                draw_text(&line, panel_x + 10.0, line_y, 13.0, if is_selected { title_color } else { line_color });
+                line_y += 17.0;
+            }
         }
         
         // Handle input
         if is_key_pressed(KeyCode::Space) {
             // Toggle pause state
             viewer.is_paused = !viewer.is_paused;
-            // Reset debug flag when unpausing so we can print again on next pause
-            if !viewer.is_paused {
-                viewer.debug_printed = false;
-            }
         }
         
-        if is_key_pressed(KeyCode::G) {
+        let shift_held = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        if is_key_pressed(KeyCode::G) && shift_held {
+            // Draw near edges wider and far ones thinner
+            viewer.depth_width = !viewer.depth_width;
+        } else if is_key_pressed(KeyCode::G) {
             // Toggle guides visibility
             viewer.show_guides = !viewer.show_guides;
         }
         
-        if is_key_pressed(KeyCode::Z) {
+        if is_key_pressed(KeyCode::Z) && shift_held {
+            // Next colormap for the depth coloring
+            viewer.palette = viewer.palette.next();
+        } else if is_key_pressed(KeyCode::Z) {
             // Toggle Z-depth coloring (disable W-depth if enabled)
             viewer.depth_coloring = !viewer.depth_coloring;
             if viewer.depth_coloring {
@@ -1755,7 +3349,10 @@ async fn main() {
             }
         }
         
-        if is_key_pressed(KeyCode::W) {
+        if is_key_pressed(KeyCode::W) && shift_held {
+            // Stretch the depth coloring over this frame's depths or the shape's fixed range
+            viewer.depth_range = viewer.depth_range.next();
+        } else if is_key_pressed(KeyCode::W) {
             // Toggle W-depth coloring (disable Z-depth if enabled)
             viewer.w_depth_coloring = !viewer.w_depth_coloring;
             if viewer.w_depth_coloring {
@@ -1763,24 +3360,72 @@ async fn main() {
             }
         }
         
-        if is_key_pressed(KeyCode::T) {
+        if is_key_pressed(KeyCode::T) && shift_held {
+            // Switch between flat lines and lit tubes and balls
+            viewer.render_style = viewer.render_style.next();
+        } else if is_key_pressed(KeyCode::T) {
             // Toggle UI visibility
             viewer.show_ui = !viewer.show_ui;
         }
         
-        if is_key_pressed(KeyCode::B) {
+        if is_key_pressed(KeyCode::Tab) {
+            // Next page of the help panel
+            viewer.help_page += 1;
+        }
+        
+        if is_key_pressed(KeyCode::B) && shift_held {
+            // Thicker fog toward the background, then none again
+            viewer.fog = viewer.fog.next();
+        } else if is_key_pressed(KeyCode::B) {
             // Toggle background color
             viewer.dark_background = !viewer.dark_background;
         }
         
+        if is_key_pressed(KeyCode::D) && shift_held {
+            // Show the vertex inspector instead of the help panel
+            viewer.show_inspector = !viewer.show_inspector;
+        } else if is_key_pressed(KeyCode::D) && (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)) {
+            // Reset viewing settings to the built-in defaults, also in the saved settings
+            settings_store.reset(&mut viewer);
+        }
+        
         if is_key_pressed(KeyCode::O) {
             // Toggle orthographic/perspective projection
             viewer.orthographic = !viewer.orthographic;
         }
         
-        if is_key_pressed(KeyCode::S) {
-            // Cycle through Platonic solids
-            viewer.current_solid = viewer.current_solid.next();
+        if is_key_pressed(KeyCode::S) && shift_held {
+            // Show or hide the statistics of the shape
+            viewer.show_stats = !viewer.show_stats;
+        } else if is_key_pressed(KeyCode::S) {
+            // Cycle through Platonic solids, coming back to them from a uniform polyhedron first
+            if !viewer.is_4d_mode && viewer.uniform_shown().is_some() {
+                viewer.uniform = None;
+            } else {
+                viewer.current_solid = viewer.current_solid.next();
+            }
+        }
+        
+        if is_key_pressed(KeyCode::L) {
+            // Cycle through stereo layouts
+            viewer.stereo_layout = viewer.stereo_layout.next();
+        }
+        
+        if is_key_pressed(KeyCode::LeftBracket) {
+            // Slow down the wiggle
+            viewer.wiggle_rate = (viewer.wiggle_rate - 0.5).max(0.5);
+        }
+        if is_key_pressed(KeyCode::RightBracket) {
+            // Speed up the wiggle
+            viewer.wiggle_rate = (viewer.wiggle_rate + 0.5).min(10.0);
+        }
+        
+        if is_key_pressed(KeyCode::I) && shift_held {
+            // Dots at the vertices, then labeled with numbers or coordinates, then none
+            viewer.vertex_marks = viewer.vertex_marks.next();
+        } else if is_key_pressed(KeyCode::I) {
+            // Cycle through 0-3 interpolated wiggle views
+            viewer.wiggle_in_between = (viewer.wiggle_in_between + 1) % 4;
         }
         
         if is_key_pressed(KeyCode::H) {
@@ -1789,12 +3434,125 @@ async fn main() {
         }
         
         if is_key_pressed(KeyCode::J) {
-            // Cycle through hypersolids (only in 4D mode)
-            if viewer.is_4d_mode {
+            // Cycle through hypersolids (only in 4D mode), coming back to them from a uniform polychoron first
+            if viewer.is_4d_mode && viewer.uniform_shown().is_some() {
+                viewer.uniform = None;
+            } else if viewer.shows_higher_dimension() {
+                viewer.current_hypersolid = viewer.current_hypersolid.next_regular();
+            } else if viewer.is_4d_mode {
                 viewer.current_hypersolid = viewer.current_hypersolid.next();
             }
         }
         
+        if is_key_pressed(KeyCode::A) && viewer.is_4d_mode && !viewer.shows_higher_dimension() {
+            // Duoprism sides (Shift for the second polygon) or prism base solid
+            let shift_held = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            viewer.current_hypersolid = viewer.current_hypersolid.next_parameter(shift_held);
+        }
+        
+        if is_key_pressed(KeyCode::U) {
+            // Cycle through dimensions 4 to 8 (only in 4D mode)
+            if viewer.is_4d_mode {
+                let next = if viewer.dimension >= polytope_nd::MAX_DIMENSION { 4 } else { viewer.dimension + 1 };
+                viewer.set_dimension(next);
+            }
+        }
+        
+        if is_key_pressed(KeyCode::X) {
+            // Morph back and forth between the shape and its dual
+            viewer.toggle_morph_animation();
+        }
+        if is_key_pressed(KeyCode::Minus) {
+            // Step the morph back towards the shape itself
+            viewer.morph_animating = false;
+            viewer.morph = ((viewer.morph - 0.05) * 20.0).round() / 20.0;
+            viewer.morph = viewer.morph.max(0.0);
+        }
+        if is_key_pressed(KeyCode::Equal) {
+            // Step the morph on towards the dual
+            viewer.morph_animating = false;
+            viewer.morph = ((viewer.morph + 0.05) * 20.0).round() / 20.0;
+            viewer.morph = viewer.morph.min(1.0);
+        }
+        
+        if is_key_pressed(KeyCode::M) && shift_held {
+            // Write the circumradius and inradius by the shape's center
+            viewer.show_radii = !viewer.show_radii;
+        } else if is_key_pressed(KeyCode::M) {
+            // Show or hide the 4D rotation preset panel
+            viewer.show_rotation_presets = !viewer.show_rotation_presets;
+            viewer.show_uniform_panel = false;
+        }
+        
+        if is_key_pressed(KeyCode::Q) {
+            // Show or hide the uniform polytope panel (it shares its keys with the preset panel)
+            viewer.show_uniform_panel = !viewer.show_uniform_panel;
+            viewer.show_rotation_presets = false;
+        }
+        
+        // Highlight the cells of the hypersolid one by one, alone with their neighbors on request
+        if viewer.cells_shown() {
+            if is_key_pressed(KeyCode::V) {
+                let forward = !(is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift));
+                viewer.step_cell(forward);
+            }
+            if is_key_pressed(KeyCode::Y) {
+                viewer.isolate_cell = !viewer.isolate_cell;
+            }
+        }
+        
+        // Uniform polytope keys only work while the panel is open, in 3D and 4D
+        if viewer.show_uniform_panel && !viewer.shows_higher_dimension() {
+            let current = viewer.uniform_shown().map(|(shape, _)| *shape);
+            let first_group = if viewer.is_4d_mode { CoxeterGroup::A4 } else { CoxeterGroup::A3 };
+            if is_key_pressed(KeyCode::N) {
+                let shape = current.map_or(WythoffShape::new(first_group, 1), |shape| WythoffShape::new(shape.group.next(), shape.rings));
+                viewer.select_uniform(shape);
+            }
+            if is_key_pressed(KeyCode::C) {
+                let shape = current.map_or(WythoffShape::new(first_group, 1), WythoffShape::next_rings);
+                viewer.select_uniform(shape);
+            }
+        }
+        
+        // Preset keys only work while the panel is open
+        if viewer.show_rotation_presets {
+            let preset = &mut viewer.rotation_preset;
+            if is_key_pressed(KeyCode::N) {
+                preset.kind = preset.kind.next();
+            }
+            if is_key_pressed(KeyCode::C) {
+                preset.next_planes();
+            }
+            if is_key_pressed(KeyCode::Comma) {
+                preset.adjust_speed(0, -1.0);
+            }
+            if is_key_pressed(KeyCode::Period) {
+                preset.adjust_speed(0, 1.0);
+            }
+            if is_key_pressed(KeyCode::Semicolon) {
+                preset.adjust_speed(1, -1.0);
+            }
+            if is_key_pressed(KeyCode::Apostrophe) {
+                preset.adjust_speed(1, 1.0);
+            }
+        }
+        
+        // Ctrl+1..0 saves the current orientation as a bookmark, 1..0 glides back to it
+        const BOOKMARK_KEYS: [KeyCode; BOOKMARK_SLOTS] = [
+            KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+            KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9, KeyCode::Key0,
+        ];
+        for (slot, &key) in BOOKMARK_KEYS.iter().enumerate() {
+            if is_key_pressed(key) {
+                if is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl) {
+                    viewer.save_bookmark(slot);
+                } else {
+                    viewer.recall_bookmark(slot);
+                }
+            }
+        }
+        
         // Handle button clicks
         if is_mouse_button_pressed(MouseButton::Left) {
             let mouse_pos = mouse_position();
@@ -1818,6 +3576,39 @@ async fn main() {
                 viewer.show_3d_controls = !viewer.show_3d_controls;
             }
             
+            // A click on an inspector entry selects its vertex, or clears the selection
+            let on_inspector = viewer.show_inspector && side_panel_rect().contains(Vec2::new(mouse_pos.0, mouse_pos.1));
+            if on_inspector {
+                let snapshot = inspector::Snapshot::capture(&viewer);
+                if let Some(index) = snapshot.vertex_at(side_panel_rect(), viewer.inspector_scroll, Vec2::new(mouse_pos.0, mouse_pos.1)) {
+                    let picked = Some(Picked::Vertex(index));
+                    viewer.pick(picked.filter(|&picked| viewer.picked != Some(picked)));
+                }
+            }
+            
+            // A click near a vertex or an edge picks it; anywhere else on the hypersolid picks
+            // a cell, unless a control or a panel is in the way. Shift+click marks for measuring.
+            let on_buttons = mouse_pos.1 <= help_button_y + button_size;
+            let on_controls = viewer.show_3d_controls && mouse_pos.0 <= 310.0;
+            let on_panel = viewer.panel_at(Vec2::new(mouse_pos.0, mouse_pos.1));
+            let shift_held = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            if !on_buttons && !on_controls && !on_panel && shift_held {
+                viewer.measure_at(Vec2::new(mouse_pos.0, mouse_pos.1));
+            } else if !on_buttons && !on_controls && !on_panel {
+                let point = Vec2::new(mouse_pos.0, mouse_pos.1);
+                if viewer.pick_element(point) {
+                    // Bring a picked vertex into the inspector's list
+                    if let Some(Picked::Vertex(index)) = viewer.picked.filter(|_| viewer.show_inspector) {
+                        let visible = inspector::Snapshot::capture(&viewer).visible_entries(side_panel_rect());
+                        if index < viewer.inspector_scroll || index >= viewer.inspector_scroll + visible {
+                            viewer.inspector_scroll = index.saturating_sub(visible / 2);
+                        }
+                    }
+                } else if viewer.cells_shown() {
+                    viewer.pick_cell(point);
+                }
+            }
+            
             // Handle slider interactions when 3D controls are visible
             if viewer.show_3d_controls {
                 let panel_x = 10.0;
@@ -1835,12 +3626,14 @@ async fn main() {
                 if viewer.is_4d_mode {
                     // 4D mode: Check left column (angle sliders) and right column (velocity sliders)
                     let velocity_slider_x = panel_x + 150.0; // Right column position
+                    let planes = viewer.rotation_planes().len();
+                    let (plane_spacing, plane_slider_height) = plane_slider_layout(planes);
                     
                     // Check left column for angle sliders
                     if mouse_pos.0 >= slider_x && mouse_pos.0 <= slider_x + slider_width {
-                        for i in 0..6 {
-                            let y_pos = angle_slider_y + (i as f32) * angle_slider_spacing;
-                            if mouse_pos.1 >= y_pos && mouse_pos.1 <= y_pos + slider_height {
+                        for i in 0..planes {
+                            let y_pos = angle_slider_y + (i as f32) * plane_spacing;
+                            if mouse_pos.1 >= y_pos && mouse_pos.1 <= y_pos + plane_slider_height {
                                 viewer.dragging_4d_slider = Some(i);
                                 break;
                             }
@@ -1849,9 +3642,9 @@ async fn main() {
                     
                     // Check right column for velocity sliders
                     if mouse_pos.0 >= velocity_slider_x && mouse_pos.0 <= velocity_slider_x + slider_width {
-                        for i in 0..6 {
-                            let y_pos = angle_slider_y + (i as f32) * angle_slider_spacing;
-                            if mouse_pos.1 >= y_pos && mouse_pos.1 <= y_pos + slider_height {
+                        for i in 0..planes {
+                            let y_pos = angle_slider_y + (i as f32) * plane_spacing;
+                            if mouse_pos.1 >= y_pos && mouse_pos.1 <= y_pos + plane_slider_height {
                                 viewer.dragging_slider = Some(i);
                                 break;
                             }
@@ -1913,14 +3706,8 @@ async fn main() {
             
             if viewer.is_4d_mode {
                 // 4D velocity sliders
-                match slider_index {
-                    0 => viewer.rotation_velocity_xy = velocity,
-                    1 => viewer.rotation_velocity_xz = velocity,
-                    2 => viewer.rotation_velocity_yz = velocity,
-                    3 => viewer.rotation_velocity_xw = velocity,
-                    4 => viewer.rotation_velocity_yw = velocity,
-                    5 => viewer.rotation_velocity_zw = velocity,
-                    _ => {}
+                if let Some((_, plane_velocity)) = viewer.plane_rotation_mut(slider_index) {
+                    *plane_velocity = velocity;
                 }
             } else {
                 // 3D velocity sliders
@@ -1963,14 +3750,8 @@ async fn main() {
             let degrees = normalized * 360.0;
             let radians = degrees.to_radians();
             
-            match angle_slider_index {
-                0 => viewer.rotation_xy = radians,
-                1 => viewer.rotation_xz = radians,
-                2 => viewer.rotation_yz = radians,
-                3 => viewer.rotation_xw = radians,
-                4 => viewer.rotation_yw = radians,
-                5 => viewer.rotation_zw = radians,
-                _ => {}
+            if let Some((plane_angle, _)) = viewer.plane_rotation_mut(angle_slider_index) {
+                *plane_angle = radians;
             }
         }
         
@@ -1990,6 +3771,137 @@ async fn main() {
             viewer.perspective_distance = (viewer.perspective_distance - 0.5).max(2.0);
         }
         
+        // Scroll the inspector's vertex list with the mouse wheel, and save it as CSV
+        if viewer.show_inspector {
+            let snapshot = inspector::Snapshot::capture(&viewer);
+            let panel = side_panel_rect();
+            let (mouse_x, mouse_y) = mouse_position();
+            let wheel = mouse_wheel().1;
+            if panel.contains(Vec2::new(mouse_x, mouse_y)) && wheel != 0.0 {
+                viewer.inspector_scroll = if wheel > 0.0 { viewer.inspector_scroll.saturating_sub(1) } else { viewer.inspector_scroll + 1 };
+            }
+            let last_scroll = snapshot.vertex_count().saturating_sub(snapshot.visible_entries(panel));
+            viewer.inspector_scroll = viewer.inspector_scroll.min(last_scroll);
+            
+            if is_key_pressed(KeyCode::F7) && snapshot.vertex_count() > 0 {
+                match inspector::export_csv(&snapshot) {
+                    Ok(name) => println!("Inspector saved to {}", name),
+                    Err(err) => eprintln!("Could not save inspector: {}", err),
+                }
+            }
+        }
+        
+        // Save and reload scene files
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if is_key_pressed(KeyCode::F5) {
+                // Always write a new file so earlier scenes are never overwritten
+                let path = scene::next_numbered_path("scene");
+                match scene::Scene::from_viewer(&viewer).save(&path) {
+                    Ok(()) => {
+                        println!("Scene saved to {}", path.display());
+                        scene_path = Some(path);
+                    }
+                    Err(err) => eprintln!("Could not save scene: {}", err),
+                }
+            }
+            
+            if is_key_pressed(KeyCode::F9) {
+                // Reload the scene given with --scene or saved last
+                if let Some(path) = &scene_path {
+                    match scene::Scene::load(path).and_then(|loaded| loaded.apply(&mut viewer)) {
+                        Ok(()) => println!("Scene loaded from {}", path.display()),
+                        Err(err) => eprintln!("Could not load scene: {}", err),
+                    }
+                }
+            }
+        }
+        
+        // Author and play keyframe timelines
+        if is_key_pressed(KeyCode::K) {
+            if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                viewer.timeline.remove_last_keyframe();
+            } else {
+                let mut timeline = std::mem::take(&mut viewer.timeline);
+                timeline.add_keyframe(&viewer);
+                viewer.timeline = timeline;
+            }
+        }
+        
+        if is_key_pressed(KeyCode::E) {
+            viewer.timeline.cycle_last_easing();
+        }
+        
+        if is_key_pressed(KeyCode::P) {
+            if viewer.timeline_clock.is_some() {
+                viewer.timeline_clock = None;
+            } else {
+                viewer.play_timeline();
+            }
+        }
+        
+        // Timeline files (native only)
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if is_key_pressed(KeyCode::F6) && !viewer.timeline.is_empty() {
+                let path = scene::next_numbered_path("timeline");
+                match viewer.timeline.save(&path) {
+                    Ok(()) => println!("Timeline saved to {}", path.display()),
+                    Err(err) => eprintln!("Could not save timeline: {}", err),
+                }
+            }
+        }
+        
+        // Start, advance and finish off-screen recordings
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if is_key_pressed(KeyCode::F) {
+                // Cycle through recording output formats
+                record_format = record_format.next();
+            }
+            
+            if is_key_pressed(KeyCode::R) && recording.is_none() {
+                // Record what the stereogram area currently shows
+                let width = stereogram_width() as u32;
+                let height = screen_height() as u32;
+                let started = if is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl) {
+                    record::Recording::timeline(&viewer, record_format, width, height)
+                } else if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                    record::Recording::fixed_steps(&viewer, record_format, width, height, RECORD_FIXED_FRAMES, 1.0)
+                } else {
+                    record::Recording::full_period(&viewer, record_format, width, height)
+                };
+                match started {
+                    Ok(started) => recording = Some(started),
+                    Err(err) => eprintln!("Could not start recording: {}", err),
+                }
+            }
+            
+            if let Some(active) = recording.as_mut() {
+                let (done, total) = active.progress();
+                draw_text(&format!("REC {}/{}", done, total), 10.0, screen_height() - 20.0, 20.0, RED);
+                
+                if let Err(err) = active.capture_frames() {
+                    eprintln!("Recording failed: {}", err);
+                    recording = None;
+                } else if active.is_finished() {
+                    match recording.take().map(record::Recording::finish) {
+                        Some(Ok(path)) => println!("Recording saved to {}", path.display()),
+                        Some(Err(err)) => eprintln!("Could not finish recording: {}", err),
+                        None => {}
+                    }
+                }
+            }
+        }
+        
+        // Timeline playback changes distance, separation and coloring every frame; save once it stops
+        if viewer.timeline_clock.is_none() {
+            settings_store.update(&viewer);
+        }
+        
+        #[cfg(target_arch = "wasm32")]
+        url_sync.update(&viewer);
+        
         next_frame().await;
     }
 }
@@ -0,0 +1,310 @@
+// Measuring the shape: distances and angles between marked vertices, dihedral angles
+// between marked faces, and the radii of the whole polytope
+//
+// Shift+click marks vertices or faces. Two vertices give their distance and three the
+// angle at the middle one; two faces give the angle between them across their shared
+// edge. Values come from the shape's own coordinates in its full dimension, so they are
+// the true lengths and angles rather than the foreshortened ones on screen. Each is
+// written beside what it measures at the same offset in both eye views, so that it
+// fuses at that depth like the vertex labels.
+
+use macroquad::prelude::*;
+
+use crate::canvas::LineCanvas;
+use crate::geometry;
+
+// Marks up to this many vertices (the angle at the middle one) or faces (a dihedral angle)
+const MARKED_VERTICES: usize = 3;
+const MARKED_FACES: usize = 2;
+
+// Text size of the annotations, and how far they sit from what they measure (pixels)
+const LABEL_SIZE: f32 = 15.0;
+const LABEL_OFFSET: Vec2 = Vec2::new(8.0, -8.0);
+
+// Vertices or faces marked for measuring, never both at once
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Measurement {
+    pub vertices: Vec<usize>,
+    pub faces: Vec<usize>,
+}
+
+// Radii of the polytope about its center
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Radii {
+    pub circumradius: f32,
+    pub inradius: Option<f32>, // None for shapes without facets, like the Clifford torus
+}
+
+impl Measurement {
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty() && self.faces.is_empty()
+    }
+
+    // Mark a vertex, or unmark it if it is marked; a vertex past the last starts over
+    pub fn mark_vertex(&mut self, vertex: usize) {
+        self.faces.clear();
+        toggle(&mut self.vertices, vertex, MARKED_VERTICES);
+    }
+
+    // Mark a face, or unmark it if it is marked; a face past the last starts over
+    pub fn mark_face(&mut self, face: usize) {
+        self.vertices.clear();
+        toggle(&mut self.faces, face, MARKED_FACES);
+    }
+
+    // The measured value as written beside it, once enough is marked
+    pub fn label(&self, points: &[Vec<f32>], faces: &[Vec<usize>]) -> Option<String> {
+        match (self.vertices.as_slice(), self.faces.as_slice()) {
+            (&[start, end], _) => Some(format!("{:.3}", length(&difference(&points[end], &points[start])))),
+            (&[first, apex, last], _) => {
+                let angle = angle_between(&difference(&points[first], &points[apex]), &difference(&points[last], &points[apex]));
+                Some(degrees(angle))
+            }
+            (_, &[first, second]) => {
+                Some(dihedral_angle(points, &faces[first], &faces[second]).map_or_else(|| "no shared edge".to_owned(), degrees))
+            }
+            _ => None,
+        }
+    }
+
+    // Draw the marks and the value into one eye view, where the shape's vertices are at
+    // `screen`. The value sits by the middle of a distance, at the apex of an angle, and
+    // by the shared edge of two faces.
+    pub fn draw(
+        &self,
+        canvas: &mut dyn LineCanvas,
+        screen: &[Vec2],
+        edges: &[(usize, usize)],
+        faces: &[Vec<usize>],
+        label: Option<&str>,
+        color: Color,
+    ) {
+        if self.vertices.iter().chain(self.faces.iter().flat_map(|&face| &faces[face])).any(|&vertex| vertex >= screen.len()) {
+            return;
+        }
+        let anchor = match *self.vertices.as_slice() {
+            [start, end] => {
+                canvas.line(screen[start], screen[end], 1.5, color);
+                Some((screen[start] + screen[end]) / 2.0)
+            }
+            [first, apex, last] => {
+                canvas.line(screen[apex], screen[first], 1.5, color);
+                canvas.line(screen[apex], screen[last], 1.5, color);
+                Some(screen[apex])
+            }
+            _ => None,
+        };
+        for &vertex in &self.vertices {
+            canvas.circle_outline(screen[vertex], 6.0, 2.0, color);
+        }
+
+        // A marked face's outline is its edges, as its vertices come in no particular order
+        for &face in &self.faces {
+            let in_face = |vertex: &usize| faces[face].contains(vertex);
+            for &(start, end) in edges.iter().filter(|(start, end)| in_face(start) && in_face(end)) {
+                canvas.line(screen[start], screen[end], 3.0, color);
+            }
+        }
+        let anchor = anchor.or_else(|| match *self.faces.as_slice() {
+            [first, second] => {
+                let shared: Vec<usize> = faces[first].iter().copied().filter(|vertex| faces[second].contains(vertex)).collect();
+                let between: Vec<Vec2> = if shared.is_empty() {
+                    vec![center(&faces[first], screen), center(&faces[second], screen)]
+                } else {
+                    shared.iter().map(|&vertex| screen[vertex]).collect()
+                };
+                Some(between.iter().sum::<Vec2>() / between.len() as f32)
+            }
+            _ => None,
+        });
+
+        if let (Some(anchor), Some(label)) = (anchor, label) {
+            canvas.text(label, anchor + LABEL_OFFSET, LABEL_SIZE, color);
+        }
+    }
+}
+
+fn toggle(marked: &mut Vec<usize>, index: usize, limit: usize) {
+    if let Some(position) = marked.iter().position(|&other| other == index) {
+        marked.remove(position);
+    } else {
+        if marked.len() == limit {
+            marked.clear();
+        }
+        marked.push(index);
+    }
+}
+
+// Circumradius and inradius of the polytope with these vertices and facets (the faces one
+// dimension down from the whole, empty if it has none), about the vertices' centroid
+pub fn radii(points: &[Vec<f32>], facets: &[Vec<usize>]) -> Radii {
+    let dimension = points.first().map_or(0, Vec::len);
+    let mut centroid = vec![0.0; dimension];
+    for point in points {
+        centroid.iter_mut().zip(point).for_each(|(sum, value)| *sum += value / points.len() as f32);
+    }
+    let circumradius = points.iter().map(|point| length(&difference(point, &centroid))).fold(0.0, f32::max);
+    let inradius = facets
+        .iter()
+        .map(|facet| geometry::distance_to_span(points, facet, &centroid))
+        .min_by(f32::total_cmp);
+    Radii { circumradius, inradius }
+}
+
+impl Radii {
+    pub fn label(&self) -> String {
+        match self.inradius {
+            Some(inradius) => format!("R {:.3}  r {:.3}", self.circumradius, inradius),
+            None => format!("R {:.3}", self.circumradius),
+        }
+    }
+
+    // Written beside the shape's center, drawn at `center` in this eye view
+    pub fn draw(&self, canvas: &mut dyn LineCanvas, center: Vec2, color: Color) {
+        canvas.circle_outline(center, 3.0, 1.5, color);
+        canvas.text(&self.label(), center + LABEL_OFFSET, LABEL_SIZE, color);
+    }
+}
+
+// Angle between two faces, measured inside the polytope across an edge they share: the
+// angle between the directions from the edge into each face, square to the edge. Faces
+// of a 3D solid with no edge in common have the angle between their planes instead.
+fn dihedral_angle(points: &[Vec<f32>], first: &[usize], second: &[usize]) -> Option<f32> {
+    let shared: Vec<usize> = first.iter().copied().filter(|vertex| second.contains(vertex)).collect();
+    if let &[start, end, ..] = shared.as_slice() {
+        let edge = unit(&difference(&points[end], &points[start]));
+        let inward = |face: &[usize]| {
+            let mut direction = difference(&centroid_of(points, face), &points[start]);
+            let along = dot(&direction, &edge);
+            direction.iter_mut().zip(&edge).for_each(|(value, axis)| *value -= along * axis);
+            direction
+        };
+        return Some(angle_between(&inward(first), &inward(second)));
+    }
+    if points.first().map_or(0, Vec::len) != 3 {
+        return None;
+    }
+    // Outward normals of the two planes, pointing away from the solid's centroid
+    let solid_center = centroid_of(points, &(0..points.len()).collect::<Vec<_>>());
+    let normal = |face: &[usize]| {
+        let [a, b, c] = [face[0], face[1], face[2]].map(|vertex| Vec3::from_slice(&points[vertex]));
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        let outward = Vec3::from_slice(&centroid_of(points, face)) - Vec3::from_slice(&solid_center);
+        if normal.dot(outward) < 0.0 { -normal } else { normal }
+    };
+    Some(std::f32::consts::PI - normal(first).angle_between(normal(second)))
+}
+
+fn centroid_of(points: &[Vec<f32>], set: &[usize]) -> Vec<f32> {
+    let mut centroid = vec![0.0; points[set[0]].len()];
+    for &vertex in set {
+        centroid.iter_mut().zip(&points[vertex]).for_each(|(sum, value)| *sum += value / set.len() as f32);
+    }
+    centroid
+}
+
+// Screen position of the middle of a set of vertices
+fn center(set: &[usize], screen: &[Vec2]) -> Vec2 {
+    set.iter().map(|&vertex| screen[vertex]).sum::<Vec2>() / set.len() as f32
+}
+
+fn difference(a: &[f32], b: &[f32]) -> Vec<f32> {
+    a.iter().zip(b).map(|(a, b)| a - b).collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn length(vector: &[f32]) -> f32 {
+    dot(vector, vector).sqrt()
+}
+
+fn unit(vector: &[f32]) -> Vec<f32> {
+    let length = length(vector);
+    vector.iter().map(|value| value / length).collect()
+}
+
+fn angle_between(a: &[f32], b: &[f32]) -> f32 {
+    (dot(a, b) / (length(a) * length(b))).clamp(-1.0, 1.0).acos()
+}
+
+fn degrees(angle: f32) -> String {
+    format!("{:.1}°", angle.to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Vertices of the cube or hypercube with edges 2, at ±1 on every axis: vertex i is at
+    // +1 along axis k if bit k of i is set
+    fn hypercube(dimension: usize) -> Vec<Vec<f32>> {
+        (0..1 << dimension)
+            .map(|vertex| (0..dimension).map(|axis| if vertex & (1 << axis) != 0 { 1.0 } else { -1.0 }).collect())
+            .collect()
+    }
+
+    // Vertices of the hypercube where each of these axes is at the given side
+    fn side(points: &[Vec<f32>], fixed: &[(usize, f32)]) -> Vec<usize> {
+        (0..points.len()).filter(|&vertex| fixed.iter().all(|&(axis, value)| points[vertex][axis] == value)).collect()
+    }
+
+    fn measured(points: &[Vec<f32>], faces: &[Vec<usize>], vertices: &[usize], marked_faces: &[usize]) -> Option<String> {
+        let measurement = Measurement { vertices: vertices.to_vec(), faces: marked_faces.to_vec() };
+        measurement.label(points, faces)
+    }
+
+    #[test]
+    fn distances_and_angles() {
+        let tesseract = hypercube(4);
+        // An edge, a square's diagonal and the long diagonal
+        assert_eq!(measured(&tesseract, &[], &[0, 1], &[]).as_deref(), Some("2.000"));
+        assert_eq!(measured(&tesseract, &[], &[0, 3], &[]).as_deref(), Some("2.828"));
+        assert_eq!(measured(&tesseract, &[], &[0, 15], &[]).as_deref(), Some("4.000"));
+        // Two edges at a corner, and an edge and a face diagonal
+        assert_eq!(measured(&tesseract, &[], &[1, 0, 2], &[]).as_deref(), Some("90.0°"));
+        assert_eq!(measured(&tesseract, &[], &[1, 0, 3], &[]).as_deref(), Some("45.0°"));
+
+        // Two edges of an equilateral triangle
+        let triangle = vec![vec![0.0, 0.0, 0.0], vec![1.0, 0.0, 0.0], vec![0.5, 3f32.sqrt() / 2.0, 0.0]];
+        assert_eq!(measured(&triangle, &[], &[1, 0, 2], &[]).as_deref(), Some("60.0°"));
+        // One vertex measures nothing yet
+        assert_eq!(measured(&triangle, &[], &[0], &[]), None);
+    }
+
+    #[test]
+    fn dihedral_angles() {
+        let cube = hypercube(3);
+        let faces: Vec<Vec<usize>> = (0..3).flat_map(|axis| [-1.0, 1.0].map(|value| side(&cube, &[(axis, value)]))).collect();
+        // Neighboring faces meet at right angles, opposite faces are parallel
+        assert_eq!(measured(&cube, &faces, &[], &[1, 3]).as_deref(), Some("90.0°"));
+        assert_eq!(measured(&cube, &faces, &[], &[0, 1]).as_deref(), Some("0.0°"));
+
+        // The regular tetrahedron's is acos(1/3)
+        let tetrahedron = vec![vec![1.0, 1.0, 1.0], vec![1.0, -1.0, -1.0], vec![-1.0, 1.0, -1.0], vec![-1.0, -1.0, 1.0]];
+        let faces = vec![vec![0, 1, 2], vec![0, 1, 3]];
+        assert_eq!(measured(&tetrahedron, &faces, &[], &[0, 1]).as_deref(), Some("70.5°"));
+
+        // Squares of the tesseract sharing an edge, and two with only a vertex in common
+        let tesseract = hypercube(4);
+        let faces = vec![side(&tesseract, &[(0, 1.0), (1, 1.0)]), side(&tesseract, &[(0, 1.0), (2, 1.0)]), side(&tesseract, &[(2, 1.0), (3, 1.0)])];
+        assert_eq!(measured(&tesseract, &faces, &[], &[0, 1]).as_deref(), Some("90.0°"));
+        assert_eq!(measured(&tesseract, &faces, &[], &[0, 2]).as_deref(), Some("no shared edge"));
+    }
+
+    #[test]
+    fn radii_of_cube_and_tesseract() {
+        let cube = hypercube(3);
+        let faces: Vec<Vec<usize>> = (0..3).flat_map(|axis| [-1.0, 1.0].map(|value| side(&cube, &[(axis, value)]))).collect();
+        let cube_radii = radii(&cube, &faces);
+        assert!((cube_radii.circumradius - 3f32.sqrt()).abs() < 1e-5);
+        assert!((cube_radii.inradius.unwrap() - 1.0).abs() < 1e-5);
+
+        let tesseract = hypercube(4);
+        let cells: Vec<Vec<usize>> = (0..4).flat_map(|axis| [-1.0, 1.0].map(|value| side(&tesseract, &[(axis, value)]))).collect();
+        let tesseract_radii = radii(&tesseract, &cells);
+        assert_eq!(tesseract_radii.label(), "R 2.000  r 1.000");
+        assert_eq!(radii(&tesseract, &[]).inradius, None);
+    }
+}
@@ -0,0 +1,354 @@
+// Object orientations and smooth transitions between them
+//
+// An orientation is the three 3D angles plus the six 4D plane angles of the viewer.
+// Transitions follow the shortest rotation between two orientations instead of
+// blending the angles one by one: 3D rotations are slerped as quaternions, and 4D
+// rotations are written as a pair of unit quaternions (v -> left * v * right) that are
+// slerped together, which traces a geodesic on SO(4).
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::StereogramViewer;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orientation {
+    rotation_3d: [f32; 3], // X, Y, Z
+    rotation_4d: [f32; 6], // XY, XZ, YZ, XW, YW, ZW
+}
+
+impl Orientation {
+    pub fn from_viewer(viewer: &StereogramViewer) -> Self {
+        Self {
+            rotation_3d: [viewer.rotation_x, viewer.rotation_y, viewer.rotation_z],
+            rotation_4d: [
+                viewer.rotation_xy,
+                viewer.rotation_xz,
+                viewer.rotation_yz,
+                viewer.rotation_xw,
+                viewer.rotation_yw,
+                viewer.rotation_zw,
+            ],
+        }
+    }
+
+    pub fn from_angles(rotation_3d: [f32; 3], rotation_4d: [f32; 6]) -> Self {
+        Self { rotation_3d, rotation_4d }
+    }
+
+    // Radians: ([X, Y, Z], [XY, XZ, YZ, XW, YW, ZW])
+    pub fn angles(&self) -> ([f32; 3], [f32; 6]) {
+        (self.rotation_3d, self.rotation_4d)
+    }
+
+    pub fn apply(&self, viewer: &mut StereogramViewer) {
+        [viewer.rotation_x, viewer.rotation_y, viewer.rotation_z] = self.rotation_3d;
+        [
+            viewer.rotation_xy,
+            viewer.rotation_xz,
+            viewer.rotation_yz,
+            viewer.rotation_xw,
+            viewer.rotation_yw,
+            viewer.rotation_zw,
+        ] = self.rotation_4d;
+    }
+
+    // Orientation a fraction `t` of the way along the shortest rotation to `to`
+    pub fn interpolate(&self, to: &Orientation, t: f32) -> Orientation {
+        let [x, y, z] = self.rotation_3d;
+        let from_3d = Quat::from_euler(EulerRot::ZYX, z, y, x);
+        let [x, y, z] = to.rotation_3d;
+        let to_3d = Quat::from_euler(EulerRot::ZYX, z, y, x);
+        let (z, y, x) = from_3d.slerp(to_3d, t).to_euler(EulerRot::ZYX);
+
+        let (from_left, from_right) = quaternion_pair(&self.rotation_4d);
+        let (mut to_left, mut to_right) = quaternion_pair(&to.rotation_4d);
+        // (l, r) and (-l, -r) are the same rotation; take the pair that is closer
+        if from_left.dot(to_left) + from_right.dot(to_right) < 0.0 {
+            to_left = -to_left;
+            to_right = -to_right;
+        }
+        let left = slerp_unflipped(from_left, to_left, t);
+        let right = slerp_unflipped(from_right, to_right, t);
+
+        Orientation {
+            rotation_3d: [x, y, z],
+            rotation_4d: plane_angles(left, right),
+        }
+    }
+}
+
+// A 4D orientation held as its left/right quaternion pair, the state a rotation preset
+// composes its steps onto. Splitting it into plane angles after every step and building
+// it again from them before the next would lose precision wherever the angles are
+// singular (XW at ±90°), and the rotation would drift off its planes. So the angles are
+// only worked out for display, and the pair stays in charge for as long as nothing else
+// changes them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuaternionPair {
+    left: Quat,
+    right: Quat,
+    angles: [f32; 6], // The plane angles it was last split into
+}
+
+impl QuaternionPair {
+    // `kept` if these plane angles are still the ones it was split into, or else the
+    // pair the angles make
+    pub fn resume(kept: Option<Self>, angles: [f32; 6]) -> Self {
+        kept.filter(|pair| pair.holds(&angles)).unwrap_or_else(|| {
+            let (left, right) = quaternion_pair(&angles);
+            Self { left, right, angles }
+        })
+    }
+
+    // Follow the rotation by a further one, given as a left/right quaternion pair
+    pub fn rotate(&mut self, left: Quat, right: Quat) {
+        self.left = (left * self.left).normalize();
+        self.right = (self.right * right).normalize();
+        self.angles = plane_angles(self.left, self.right);
+    }
+
+    pub fn angles(&self) -> [f32; 6] {
+        self.angles
+    }
+
+    // Whether these plane angles are the ones the pair was last split into
+    pub fn holds(&self, angles: &[f32; 6]) -> bool {
+        self.angles == *angles
+    }
+
+    // Rotate a point (x, y, z, w)
+    pub fn rotate_point(&self, point: Vec4) -> Vec4 {
+        let image = self.left * Quat::from_xyzw(point.x, point.y, point.z, point.w) * self.right;
+        Vec4::new(image.x, image.y, image.z, image.w)
+    }
+}
+
+// An orientation as scene and timeline files write it, in degrees, or rotation speeds
+// in degrees per frame: one value per rotation, the three 3D axes and the six 4D planes
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Angles {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub xy: f32,
+    pub xz: f32,
+    pub yz: f32,
+    pub xw: f32,
+    pub yw: f32,
+    pub zw: f32,
+}
+
+impl Angles {
+    pub fn from_orientation(orientation: &Orientation) -> Self {
+        let ([x, y, z], [xy, xz, yz, xw, yw, zw]) = orientation.angles();
+        Self {
+            x: x.to_degrees(),
+            y: y.to_degrees(),
+            z: z.to_degrees(),
+            xy: xy.to_degrees(),
+            xz: xz.to_degrees(),
+            yz: yz.to_degrees(),
+            xw: xw.to_degrees(),
+            yw: yw.to_degrees(),
+            zw: zw.to_degrees(),
+        }
+    }
+
+    pub fn to_orientation(self) -> Orientation {
+        Orientation::from_angles(
+            [self.x, self.y, self.z].map(f32::to_radians),
+            [self.xy, self.xz, self.yz, self.xw, self.yw, self.zw].map(f32::to_radians),
+        )
+    }
+}
+
+// Left and right unit quaternions of the rotation applied by `apply_4d_rotations`.
+// A point (x, y, z, w) is the quaternion w + xi + yj + zk, and a rotation by θ in a
+// single plane is a half-angle rotation on each side.
+fn quaternion_pair(angles: &[f32; 6]) -> (Quat, Quat) {
+    // Later rotations wrap around earlier ones: l2 * (l1 * v * r1) * r2
+    angles
+        .iter()
+        .enumerate()
+        .map(|(plane, &angle)| plane_pair(plane, angle))
+        .fold((Quat::IDENTITY, Quat::IDENTITY), |(left, right), (l, r)| (l * left, right * r))
+}
+
+// Left and right quaternions of a rotation by `angle` in one plane, numbered
+// XY, XZ, YZ, XW, YW, ZW like the viewer's angles
+pub fn plane_pair(plane: usize, angle: f32) -> (Quat, Quat) {
+    let turn = |axis: Vec3, angle: f32| Quat::from_axis_angle(axis, angle);
+    match plane {
+        0 => (turn(Vec3::Z, angle), turn(Vec3::Z, -angle)),
+        1 => (turn(Vec3::Y, -angle), turn(Vec3::Y, angle)),
+        2 => (turn(Vec3::X, angle), turn(Vec3::X, -angle)),
+        3 => (turn(Vec3::X, -angle), turn(Vec3::X, -angle)),
+        4 => (turn(Vec3::Y, -angle), turn(Vec3::Y, -angle)),
+        5 => (turn(Vec3::Z, -angle), turn(Vec3::Z, -angle)),
+        _ => panic!("there is no 4D rotation plane {}", plane),
+    }
+}
+
+// Slerp that keeps the given end point, unlike `Quat::slerp` which may negate it
+fn slerp_unflipped(from: Quat, to: Quat, t: f32) -> Quat {
+    let dot = from.dot(to).clamp(-1.0, 1.0);
+    let angle = dot.acos();
+    if angle.sin().abs() < 1e-4 {
+        return from.lerp(to, t).normalize();
+    }
+    let from_weight = ((1.0 - t) * angle).sin() / angle.sin();
+    let to_weight = (t * angle).sin() / angle.sin();
+    (from * from_weight + to * to_weight).normalize()
+}
+
+// Rotation matrix of the point map v -> left * v * right
+fn rotation_matrix(left: Quat, right: Quat) -> Mat4 {
+    let column = |x: f32, y: f32, z: f32, w: f32| {
+        let image = left * Quat::from_xyzw(x, y, z, w) * right;
+        Vec4::new(image.x, image.y, image.z, image.w)
+    };
+    Mat4::from_cols(
+        column(1.0, 0.0, 0.0, 0.0),
+        column(0.0, 1.0, 0.0, 0.0),
+        column(0.0, 0.0, 1.0, 0.0),
+        column(0.0, 0.0, 0.0, 1.0),
+    )
+}
+
+// Rotation by `angle` in the plane of coordinates `a` and `b`, with the same sign
+// convention as `apply_4d_rotations`
+fn plane_rotation(a: usize, b: usize, angle: f32) -> Mat4 {
+    let mut matrix = Mat4::IDENTITY;
+    let (sin, cos) = angle.sin_cos();
+    matrix.col_mut(a)[a] = cos;
+    matrix.col_mut(a)[b] = sin;
+    matrix.col_mut(b)[a] = -sin;
+    matrix.col_mut(b)[b] = cos;
+    matrix
+}
+
+// Split the rotation back into the six plane angles of the viewer, so that sliders,
+// scene files and further animation continue from the interpolated orientation
+fn plane_angles(left: Quat, right: Quat) -> [f32; 6] {
+    let matrix = rotation_matrix(left, right);
+
+    // The XW, YW and ZW rotations are applied last and alone decide where W ends up
+    let w_axis = matrix.w_axis;
+    let xw = (-w_axis.x).clamp(-1.0, 1.0).asin();
+    let yw = (-w_axis.y).atan2(w_axis.z.hypot(w_axis.w));
+    let zw = (-w_axis.z).atan2(w_axis.w);
+
+    // What remains keeps W fixed: XY, XZ and YZ are rotations about Z, -Y and X
+    let remaining = plane_rotation(0, 3, -xw) * plane_rotation(1, 3, -yw) * plane_rotation(2, 3, -zw) * matrix;
+    let rotation_3d = Quat::from_mat3(&Mat3::from_mat4(remaining));
+    let (yz, minus_xz, xy) = rotation_3d.to_euler(EulerRot::XYZ);
+
+    [xy, -minus_xz, yz, xw, yw, zw]
+}
+
+// An eased transition from one orientation to another
+#[derive(Clone, Debug)]
+pub struct Transition {
+    from: Orientation,
+    to: Orientation,
+    elapsed: f32,
+}
+
+impl Transition {
+    const DURATION: f32 = 0.8; // Seconds
+
+    pub fn new(from: Orientation, to: Orientation) -> Self {
+        Self { from, to, elapsed: 0.0 }
+    }
+
+    // Advance by `dt` seconds; returns the orientation to show and whether the target was reached
+    pub fn step(&mut self, dt: f32) -> (Orientation, bool) {
+        self.elapsed += dt;
+        let t = (self.elapsed / Self::DURATION).min(1.0);
+        if t >= 1.0 {
+            // Land exactly on the stored angles
+            return (self.to, true);
+        }
+        let eased = t * t * (3.0 - 2.0 * t); // Smoothstep: gentle start and stop
+        (self.from.interpolate(&self.to, eased), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Angles inside the ranges `plane_angles` returns, well away from XW at ±90°
+    const ANGLES: [[f32; 6]; 3] = [
+        [0.3, -0.7, 1.2, 0.4, -1.1, 2.5],
+        [-2.8, 0.2, -0.5, -1.3, 0.9, -3.0],
+        [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    ];
+
+    // Planes in the order of the viewer's angles: XY, XZ, YZ, XW, YW, ZW
+    const PLANES: [(usize, usize); 6] = [(0, 1), (0, 2), (1, 2), (0, 3), (1, 3), (2, 3)];
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn quaternion_pairs_match_the_plane_rotations() {
+        for angles in ANGLES {
+            let (left, right) = quaternion_pair(&angles);
+            let planes = PLANES
+                .iter()
+                .zip(angles)
+                .fold(Mat4::IDENTITY, |matrix, (&(a, b), angle)| plane_rotation(a, b, angle) * matrix);
+            assert_close(&rotation_matrix(left, right).to_cols_array(), &planes.to_cols_array());
+        }
+    }
+
+    #[test]
+    fn plane_angles_round_trip() {
+        for angles in ANGLES {
+            let (left, right) = quaternion_pair(&angles);
+            assert_close(&plane_angles(left, right), &angles);
+            // (-l, -r) is the same rotation
+            assert_close(&plane_angles(-left, -right), &angles);
+        }
+    }
+
+    #[test]
+    fn orientations_interpolate_from_one_end_to_the_other() {
+        let from = Orientation::from_angles([0.1, 0.2, 0.3], ANGLES[0]);
+        let to = Orientation::from_angles([-0.4, 0.5, 1.0], ANGLES[1]);
+        for (t, end) in [(0.0, from), (1.0, to)] {
+            let (rotation_3d, rotation_4d) = from.interpolate(&to, t).angles();
+            assert_close(&rotation_3d, &end.rotation_3d);
+            assert_close(&rotation_4d, &end.rotation_4d);
+        }
+    }
+
+    #[test]
+    fn quaternion_pairs_resume_only_while_they_hold_the_angles() {
+        let mut pair = QuaternionPair::resume(None, ANGLES[0]);
+        let (left, right) = plane_pair(3, 0.01);
+        pair.rotate(left, right);
+        assert_eq!(QuaternionPair::resume(Some(pair), pair.angles()), pair);
+        let rebuilt = QuaternionPair::resume(Some(pair), ANGLES[1]);
+        assert_eq!(rebuilt, QuaternionPair::resume(None, ANGLES[1]));
+    }
+
+    #[test]
+    fn quaternion_pairs_compose_steps_without_drift() {
+        // Small left isoclinic steps through XW at 90°, where the plane angles are singular
+        let step = Quat::from_axis_angle(Vec3::X, -0.01);
+        let mut pair = QuaternionPair::resume(None, [0.0; 6]);
+        for _ in 0..1000 {
+            pair.rotate(step, Quat::IDENTITY);
+        }
+        let mut whole = QuaternionPair::resume(None, [0.0; 6]);
+        whole.rotate(Quat::from_axis_angle(Vec3::X, -10.0), Quat::IDENTITY);
+        let point = Vec4::new(0.3, -0.5, 0.7, 0.2);
+        assert!(pair.rotate_point(point).distance(whole.rotate_point(point)) < 1e-4);
+        assert!((pair.rotate_point(point).length() - point.length()).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,259 @@
+// Picking vertices and edges with the mouse
+//
+// A click near a vertex or an edge in either eye view selects it; it is ringed or traced
+// over in both views, and a panel tells what it is part of. Faces are worked out from
+// the shape's own coordinates the first time something is picked on it (see
+// geometry::faces); cells are the ones the 4D cell highlighting uses.
+
+use macroquad::prelude::*;
+
+use crate::polytope_nd;
+
+// How near a click has to come to a vertex, or failing that an edge, to pick it (pixels)
+const VERTEX_PICK_RADIUS: f32 = 8.0;
+const EDGE_PICK_DISTANCE: f32 = 5.0;
+
+// Longest list of indices written out in the panel, and the panel's width
+const LISTED_INDICES: usize = 8;
+const PANEL_WIDTH: f32 = 300.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Picked {
+    Vertex(usize),
+    Edge(usize),
+}
+
+// Vertex or edge drawn nearest to `point` in any of the eye views (screen positions of
+// the vertices in each), vertices first. Edges for which `shown` is false are skipped,
+// and so are vertices with none of their edges shown.
+pub fn hit_test(views: &[&[Vec2]], edges: &[(usize, usize)], shown: impl Fn(usize) -> bool, point: Vec2) -> Option<Picked> {
+    let vertex_count = views.first().map_or(0, |view| view.len());
+    let mut vertex_shown = vec![false; vertex_count];
+    for (edge, &(start, end)) in edges.iter().enumerate() {
+        if shown(edge) {
+            vertex_shown[start] = true;
+            vertex_shown[end] = true;
+        }
+    }
+
+    let nearest = |distances: &mut dyn Iterator<Item = (f32, usize)>, within: f32| {
+        distances.filter(|&(distance, _)| distance <= within).min_by(|a, b| a.0.total_cmp(&b.0)).map(|(_, index)| index)
+    };
+    let mut vertex_distances = views
+        .iter()
+        .flat_map(|view| view.iter().enumerate().filter(|&(index, _)| vertex_shown[index]).map(|(index, &vertex)| (vertex.distance(point), index)));
+    if let Some(vertex) = nearest(&mut vertex_distances, VERTEX_PICK_RADIUS) {
+        return Some(Picked::Vertex(vertex));
+    }
+    let mut edge_distances = views.iter().flat_map(|view| {
+        edges
+            .iter()
+            .enumerate()
+            .filter(|&(edge, _)| shown(edge))
+            .map(|(edge, &(start, end))| (segment_distance(point, view[start], view[end]), edge))
+    });
+    nearest(&mut edge_distances, EDGE_PICK_DISTANCE).map(Picked::Edge)
+}
+
+// Set of vertices (a face or a cell) whose center is drawn nearest to `point`, in any
+// eye view whose outline contains the point
+pub fn nearest_center(views: &[&[Vec2]], sets: &[Vec<usize>], point: Vec2) -> Option<usize> {
+    let mut nearest: Option<(f32, usize)> = None;
+    for view in views {
+        let (min, max) = view.iter().fold((Vec2::MAX, Vec2::MIN), |(min, max), &vertex| (min.min(vertex), max.max(vertex)));
+        if point.cmplt(min).any() || point.cmpgt(max).any() {
+            continue;
+        }
+        for (index, set) in sets.iter().enumerate() {
+            let center = set.iter().map(|&vertex| view[vertex]).sum::<Vec2>() / set.len() as f32;
+            let distance = center.distance(point);
+            if nearest.is_none_or(|(closest, _)| distance < closest) {
+                nearest = Some((distance, index));
+            }
+        }
+    }
+    nearest.map(|(_, index)| index)
+}
+
+fn segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let along = end - start;
+    let t = if along.length_squared() > 0.0 { ((point - start).dot(along) / along.length_squared()).clamp(0.0, 1.0) } else { 0.0 };
+    point.distance(start + along * t)
+}
+
+// Title and text lines of the panel about a picked vertex or edge of the shape with
+// these vertices (in its own coordinates), edges, faces and, in 4D, cells
+pub fn describe(
+    picked: Picked,
+    coordinates: &[Vec<f32>],
+    edges: &[(usize, usize)],
+    faces: &[Vec<usize>],
+    cells: Option<&[Vec<usize>]>,
+) -> (String, Vec<String>) {
+    // Faces and cells containing all of `vertices`
+    let containing = |sets: &[Vec<usize>], vertices: &[usize]| -> Vec<usize> {
+        (0..sets.len()).filter(|&set| vertices.iter().all(|vertex| sets[set].contains(vertex))).collect()
+    };
+    let mut lines = Vec::new();
+    let (title, vertices) = match picked {
+        Picked::Vertex(vertex) => {
+            lines.extend(coordinate_lines(&coordinates[vertex]));
+            let incident: Vec<usize> = (0..edges.len()).filter(|&edge| edges[edge].0 == vertex || edges[edge].1 == vertex).collect();
+            let neighbors: Vec<usize> = incident
+                .iter()
+                .map(|&edge| if edges[edge].0 == vertex { edges[edge].1 } else { edges[edge].0 })
+                .collect();
+            lines.push(format!("Degree {}", incident.len()));
+            lines.push(format!("Edges: {}", index_list(&incident)));
+            lines.push(format!("Neighbors: {}", index_list(&neighbors)));
+            (format!("VERTEX {}", vertex), vec![vertex])
+        }
+        Picked::Edge(edge) => {
+            let (start, end) = edges[edge];
+            let length = coordinates[start].iter().zip(&coordinates[end]).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt();
+            lines.push(format!("Vertices {} and {}", start, end));
+            lines.push(format!("Length {:.3}", length));
+            (format!("EDGE {}", edge), vec![start, end])
+        }
+    };
+
+    let face_list = containing(faces, &vertices);
+    if !faces.is_empty() {
+        lines.push(format!("Faces: {}", polygon_counts(face_list.iter().map(|&face| faces[face].len()))));
+    }
+    if let Some(cells) = cells {
+        // Cells are numbered from 1, as when stepping through them
+        let numbers: Vec<usize> = containing(cells, &vertices).iter().map(|&cell| cell + 1).collect();
+        lines.push(format!("Cells: {}", index_list(&numbers)));
+    }
+    (title, lines)
+}
+
+// A point's coordinates, four to a line
+fn coordinate_lines(point: &[f32]) -> Vec<String> {
+    let named: Vec<String> = point
+        .iter()
+        .zip(polytope_nd::AXIS_NAMES)
+        .map(|(value, axis)| format!("{} {:+.3}", axis, value))
+        .collect();
+    named.chunks(4).map(|chunk| chunk.join("  ")).collect()
+}
+
+// The first few of a list of indices, e.g. "0, 3, 7, 9", or "0, 3, ... (20)" for a long one
+fn index_list(indices: &[usize]) -> String {
+    let listed: Vec<String> = indices.iter().take(LISTED_INDICES).map(usize::to_string).collect();
+    if indices.len() > LISTED_INDICES {
+        format!("{}, ... ({})", listed.join(", "), indices.len())
+    } else {
+        listed.join(", ")
+    }
+}
+
+// Polygons by their number of sides, e.g. "3 triangles" or "3 (1 triangle, 2 hexagons)"
+fn polygon_counts(sides: impl Iterator<Item = usize>) -> String {
+    let mut counts: Vec<(usize, usize)> = Vec::new();
+    let mut total = 0;
+    for side_count in sides {
+        total += 1;
+        match counts.iter_mut().find(|(sides, _)| *sides == side_count) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((side_count, 1)),
+        }
+    }
+    counts.sort();
+    let kinds: Vec<String> = counts
+        .iter()
+        .map(|&(sides, count)| {
+            let name = match sides {
+                3 => "triangle".to_owned(),
+                4 => "quad".to_owned(),
+                5 => "pentagon".to_owned(),
+                6 => "hexagon".to_owned(),
+                8 => "octagon".to_owned(),
+                10 => "decagon".to_owned(),
+                _ => format!("{}-gon", sides),
+            };
+            format!("{} {}{}", count, name, if count == 1 { "" } else { "s" })
+        })
+        .collect();
+    match kinds.len() {
+        0 => "none".to_owned(),
+        1 => kinds[0].clone(),
+        _ => format!("{} ({})", total, kinds.join(", ")),
+    }
+}
+
+// Area of the panel with this many lines, its bottom edge at `bottom`
+pub fn panel_rect(line_count: usize, bottom: f32) -> Rect {
+    let panel_height = 35.0 + 18.0 * line_count as f32;
+    Rect::new(10.0, bottom - panel_height, PANEL_WIDTH, panel_height)
+}
+
+// Panel at the lower left of the stereogram, its bottom edge at `bottom`
+pub fn draw_panel(title: &str, lines: &[String], bottom: f32, dark_background: bool) {
+    let Rect { x: panel_x, y: panel_y, w: panel_width, h: panel_height } = panel_rect(lines.len(), bottom);
+    draw_rectangle(
+        panel_x,
+        panel_y,
+        panel_width,
+        panel_height,
+        if dark_background { Color::new(0.1, 0.1, 0.1, 0.9) } else { Color::new(0.95, 0.95, 0.95, 0.9) },
+    );
+    let title_color = if dark_background { WHITE } else { BLACK };
+    let line_color = if dark_background { LIME } else { Color::new(0.0, 0.4, 0.0, 1.0) };
+    draw_text(title, panel_x + 10.0, panel_y + 22.0, 16.0, title_color);
+    for (line, text) in lines.iter().enumerate() {
+        draw_text(text, panel_x + 10.0, panel_y + 42.0 + 18.0 * line as f32, 14.0, line_color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 100 pixel square drawn in two eye views, the right one 200 pixels along
+    fn square() -> (Vec<Vec2>, Vec<Vec2>, Vec<(usize, usize)>) {
+        let left = vec![Vec2::new(0.0, 0.0), Vec2::new(100.0, 0.0), Vec2::new(100.0, 100.0), Vec2::new(0.0, 100.0)];
+        let right = left.iter().map(|&vertex| vertex + Vec2::new(200.0, 0.0)).collect();
+        (left, right, vec![(0, 1), (1, 2), (2, 3), (3, 0)])
+    }
+
+    #[test]
+    fn picks_the_nearest_vertex_then_edge() {
+        let (left, right, edges) = square();
+        let views = [&left[..], &right[..]];
+        let pick = |point: Vec2| hit_test(&views, &edges, |_| true, point);
+
+        assert_eq!(pick(Vec2::new(3.0, -2.0)), Some(Picked::Vertex(0)));
+        assert_eq!(pick(Vec2::new(296.0, 104.0)), Some(Picked::Vertex(2)));
+        // Within reach of an edge's end, the vertex wins
+        assert_eq!(pick(Vec2::new(100.0, 6.0)), Some(Picked::Vertex(1)));
+        assert_eq!(pick(Vec2::new(50.0, 103.0)), Some(Picked::Edge(2)));
+        assert_eq!(pick(Vec2::new(304.0, 50.0)), Some(Picked::Edge(1)));
+    }
+
+    #[test]
+    fn picks_nothing_out_of_reach() {
+        let (left, right, edges) = square();
+        let views = [&left[..], &right[..]];
+        let pick = |point: Vec2| hit_test(&views, &edges, |_| true, point);
+
+        assert_eq!(pick(Vec2::new(50.0, 50.0)), None);
+        assert_eq!(pick(Vec2::new(50.0, 100.0 + EDGE_PICK_DISTANCE + 1.0)), None);
+        assert_eq!(pick(Vec2::new(-VERTEX_PICK_RADIUS - 1.0, -1.0)), None);
+        assert_eq!(pick(Vec2::new(150.0, 0.0)), None);
+    }
+
+    #[test]
+    fn skips_hidden_edges_and_their_vertices() {
+        let (left, right, edges) = square();
+        let views = [&left[..], &right[..]];
+        // Only the edge from vertex 2 to 3 is shown
+        let pick = |point: Vec2| hit_test(&views, &edges, |edge| edge == 2, point);
+
+        assert_eq!(pick(Vec2::new(1.0, 1.0)), None);
+        assert_eq!(pick(Vec2::new(100.0, 50.0)), None);
+        assert_eq!(pick(Vec2::new(1.0, 99.0)), Some(Picked::Vertex(3)));
+        assert_eq!(pick(Vec2::new(250.0, 98.0)), Some(Picked::Edge(2)));
+    }
+}
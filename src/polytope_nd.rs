@@ -0,0 +1,242 @@
+// Regular polytopes in five to eight dimensions: n-cubes, n-simplices and n-orthoplexes
+//
+// Points are plain coordinate lists with a circumradius of 1. An n-dimensional polytope
+// turns in all n(n-1)/2 coordinate planes, one after the other in the order XY, XZ, YZ,
+// XW, YW, ZW, XV, ... which continues the order of `apply_4d_rotations`. It then reaches
+// 3D through a chain of perspective steps, nD -> (n-1)D -> ... -> 3D, each of which looks
+// along the last remaining axis and drops it.
+
+use macroquad::prelude::*;
+
+use crate::geometry::Mesh;
+use crate::Hypersolid;
+
+pub const MIN_DIMENSION: usize = 5;
+pub const MAX_DIMENSION: usize = 8;
+
+// Axis names; V, U, T and S continue X, Y, Z, W backwards through the alphabet
+pub const AXIS_NAMES: [char; MAX_DIMENSION] = ['X', 'Y', 'Z', 'W', 'V', 'U', 'T', 'S'];
+
+// Display names by family and dimension (5 to 8)
+const CUBE_NAMES: [&str; 4] = ["5-Cube", "6-Cube", "7-Cube", "8-Cube"];
+const SIMPLEX_NAMES: [&str; 4] = ["5-Simplex", "6-Simplex", "7-Simplex", "8-Simplex"];
+const ORTHOPLEX_NAMES: [&str; 4] = ["5-Orthoplex", "6-Orthoplex", "7-Orthoplex", "8-Orthoplex"];
+
+// Starting angle in every plane; at zero whole layers of vertices would line up
+pub const REST_ANGLE: f32 = 0.3;
+
+// Distance of the eye from the center at every perspective step, in circumradii
+const CAMERA_DISTANCE: f32 = 2.5;
+
+// Name of the family member in `dimension`. The regular hypersolids stand for their
+// families; the shapes that only exist in 4D fall back to the n-cube.
+pub fn name(family: Hypersolid, dimension: usize) -> &'static str {
+    let names = match family {
+        Hypersolid::FourSimplex => &SIMPLEX_NAMES,
+        Hypersolid::FourOrthoplex => &ORTHOPLEX_NAMES,
+        _ => &CUBE_NAMES,
+    };
+    names[dimension.clamp(MIN_DIMENSION, MAX_DIMENSION) - MIN_DIMENSION]
+}
+
+// Schläfli symbol of the family member in `dimension`, e.g. "{4,3,3,3}" for the 5-cube
+pub fn schlafli(family: Hypersolid, dimension: usize) -> String {
+    let mut entries = vec!["3"; dimension - 1];
+    match family {
+        Hypersolid::FourSimplex => {}
+        Hypersolid::FourOrthoplex => entries[dimension - 2] = "4",
+        _ => entries[0] = "4",
+    }
+    format!("{{{}}}", entries.join(","))
+}
+
+// Symmetry group of the family member in `dimension`, by name and order: A(n) of order
+// (n + 1)! for the simplex, and for the orthoplex the n-cube's B(n) of order 2^n n!
+pub fn symmetry(family: Hypersolid, dimension: usize) -> (String, usize) {
+    let factorial = |n: usize| (1..=n).product::<usize>();
+    let bracket = |family: Hypersolid| schlafli(family, dimension).replace('{', "[").replace('}', "]");
+    match family {
+        Hypersolid::FourSimplex => (format!("A{} {}", dimension, bracket(family)), factorial(dimension + 1)),
+        _ => (format!("B{} {}", dimension, bracket(Hypersolid::Tesseract)), (1 << dimension) * factorial(dimension)),
+    }
+}
+
+pub fn mesh(family: Hypersolid, dimension: usize) -> Mesh<Vec<f32>> {
+    match family {
+        Hypersolid::FourSimplex => simplex(dimension),
+        Hypersolid::FourOrthoplex => orthoplex(dimension),
+        _ => cube(dimension),
+    }
+}
+
+// All sign combinations of (±1, ..., ±1); edges join corners that differ in one sign
+fn cube(dimension: usize) -> Mesh<Vec<f32>> {
+    let half_edge = 1.0 / (dimension as f32).sqrt();
+    let vertices = (0..1usize << dimension)
+        .map(|corner| {
+            (0..dimension)
+                .map(|axis| if corner & (1 << axis) == 0 { -half_edge } else { half_edge })
+                .collect()
+        })
+        .collect();
+    let edges = (0..1usize << dimension)
+        .flat_map(|corner| {
+            (0..dimension)
+                .map(move |axis| (corner, corner | (1 << axis)))
+                .filter(|&(start, end)| start != end)
+        })
+        .collect();
+    Mesh { vertices, edges }
+}
+
+// The unit vectors plus one point on the diagonal at the same distance from each of
+// them, moved so that the center is at the origin; every vertex is joined to every other
+fn simplex(dimension: usize) -> Mesh<Vec<f32>> {
+    let n = dimension as f32;
+    let diagonal = (1.0 - (n + 1.0).sqrt()) / n;
+    let mut vertices: Vec<Vec<f32>> = (0..dimension)
+        .map(|axis| (0..dimension).map(|i| if i == axis { 1.0 } else { 0.0 }).collect())
+        .collect();
+    vertices.push(vec![diagonal; dimension]);
+
+    let center = (1.0 + diagonal) / (n + 1.0);
+    let radius = ((1.0 - center).powi(2) + (n - 1.0) * center * center).sqrt();
+    for vertex in &mut vertices {
+        vertex.iter_mut().for_each(|coordinate| *coordinate = (*coordinate - center) / radius);
+    }
+
+    let edges = (0..=dimension)
+        .flat_map(|start| (start + 1..=dimension).map(move |end| (start, end)))
+        .collect();
+    Mesh { vertices, edges }
+}
+
+// ±1 on each axis; every vertex is joined to all but its opposite
+fn orthoplex(dimension: usize) -> Mesh<Vec<f32>> {
+    let vertices: Vec<Vec<f32>> = (0..2 * dimension)
+        .map(|index| {
+            let sign = if index % 2 == 0 { 1.0 } else { -1.0 };
+            (0..dimension).map(|axis| if axis == index / 2 { sign } else { 0.0 }).collect()
+        })
+        .collect();
+    let edges = (0..vertices.len())
+        .flat_map(|start| (start + 1..vertices.len()).map(move |end| (start, end)))
+        .filter(|&(start, end)| start / 2 != end / 2)
+        .collect();
+    Mesh { vertices, edges }
+}
+
+// Coordinate planes (a, b) in rotation order: by their second axis, then by their first
+pub fn planes(dimension: usize) -> Vec<(usize, usize)> {
+    (1..dimension).flat_map(|b| (0..b).map(move |a| (a, b))).collect()
+}
+
+pub fn plane_name((a, b): (usize, usize)) -> String {
+    format!("{}{}", AXIS_NAMES[a], AXIS_NAMES[b])
+}
+
+// Turn a point by `angles[i]` in each plane of `planes(point.len())`, with the same sign
+// convention as `apply_4d_rotations`
+pub fn rotate(point: &[f32], angles: &[f32]) -> Vec<f32> {
+    let mut result = Vec::with_capacity(point.len());
+    rotate_into(point, angles, &mut result);
+    result
+}
+
+// `rotate` into a buffer kept from frame to frame
+pub fn rotate_into(point: &[f32], angles: &[f32], result: &mut Vec<f32>) {
+    result.clear();
+    result.extend_from_slice(point);
+    for (index, &angle) in angles.iter().enumerate().take(point.len() * (point.len() - 1) / 2) {
+        let (a, b) = plane(index);
+        let (sin, cos) = angle.sin_cos();
+        let (x, y) = (result[a], result[b]);
+        result[a] = x * cos - y * sin;
+        result[b] = x * sin + y * cos;
+    }
+}
+
+// Plane `index` of the rotation order: (0, 1), (0, 2), (1, 2), (0, 3), ...
+fn plane(index: usize) -> (usize, usize) {
+    let mut b = 1;
+    while index >= b * (b + 1) / 2 {
+        b += 1;
+    }
+    (index - b * (b - 1) / 2, b)
+}
+
+// Project a point of the unit ball to 3D, returning its W coordinate as well for
+// coloring. Each step is scaled so that the nearest possible point keeps its size,
+// which keeps every step inside the unit ball.
+pub fn project_to_3d(point: &[f32]) -> (Vec3, f32) {
+    // Every step scales what is left by its own factor, so the steps multiply up
+    let mut scale = 1.0;
+    let mut w = 0.0;
+    for &depth in point[3..].iter().rev() {
+        let depth = depth * scale;
+        w = depth;
+        scale *= (CAMERA_DISTANCE - 1.0) / (CAMERA_DISTANCE - depth);
+    }
+    (Vec3::new(point[0], point[1], point[2]) * scale, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILIES: [Hypersolid; 3] = [Hypersolid::Tesseract, Hypersolid::FourSimplex, Hypersolid::FourOrthoplex];
+
+    fn distance(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt()
+    }
+
+    #[test]
+    fn families_have_their_vertex_and_edge_counts() {
+        for n in MIN_DIMENSION..=MAX_DIMENSION {
+            let counts = |family| {
+                let mesh = mesh(family, n);
+                (mesh.vertices.len(), mesh.edges.len())
+            };
+            assert_eq!(counts(Hypersolid::Tesseract), (1 << n, n << (n - 1)));
+            assert_eq!(counts(Hypersolid::FourSimplex), (n + 1, n * (n + 1) / 2));
+            assert_eq!(counts(Hypersolid::FourOrthoplex), (2 * n, 2 * n * (n - 1)));
+        }
+    }
+
+    #[test]
+    fn families_are_regular_on_the_unit_sphere() {
+        for family in FAMILIES {
+            for n in MIN_DIMENSION..=MAX_DIMENSION {
+                let mesh = mesh(family, n);
+                let origin = vec![0.0; n];
+                assert!(mesh.vertices.iter().all(|vertex| vertex.len() == n && (distance(vertex, &origin) - 1.0).abs() < 1e-5));
+                let lengths: Vec<f32> = mesh.edges.iter().map(|&(a, b)| distance(&mesh.vertices[a], &mesh.vertices[b])).collect();
+                assert!(lengths.iter().all(|length| (length - lengths[0]).abs() < 1e-5), "{} {:?}", name(family, n), lengths);
+            }
+        }
+    }
+
+    #[test]
+    fn symmetry_orders_and_symbols() {
+        assert_eq!(symmetry(Hypersolid::FourSimplex, 5), ("A5 [3,3,3,3]".to_owned(), 720));
+        assert_eq!(symmetry(Hypersolid::FourOrthoplex, 5), ("B5 [4,3,3,3]".to_owned(), 3840));
+        assert_eq!(schlafli(Hypersolid::FourOrthoplex, 6), "{3,3,3,3,4}");
+        assert_eq!(planes(6).len(), 15);
+        assert_eq!(plane_name(planes(5)[6]), "XV");
+    }
+
+    #[test]
+    fn rotations_keep_lengths_and_continue_the_4d_planes() {
+        let point = [0.3, -0.5, 0.7, 0.2, -0.1, 0.4];
+        let angles: Vec<f32> = (0..15).map(|plane| 0.1 * plane as f32 - 0.6).collect();
+        let origin = [0.0; 6];
+        assert!((distance(&rotate(&point, &angles), &origin) - distance(&point, &origin)).abs() < 1e-5);
+
+        // A turn in the XW plane alone, the fourth
+        let mut angles = [0.0; 10];
+        angles[3] = std::f32::consts::FRAC_PI_2;
+        let turned = rotate(&[1.0, 0.0, 0.0, 0.0, 0.0], &angles);
+        assert!(distance(&turned, &[0.0, 0.0, 0.0, 1.0, 0.0]) < 1e-6);
+        assert!(planes(MAX_DIMENSION).into_iter().enumerate().all(|(index, axes)| plane(index) == axes));
+    }
+}
@@ -0,0 +1,308 @@
+// CPU software rasterizer for headless rendering
+//
+// Lines and circle outlines are drawn with analytic coverage (distance from each
+// pixel center to the shape), which gives anti-aliased edges without a GPU. Text uses
+// a small built-in bitmap font that covers what vertex labels need: digits, signs,
+// points, commas and parentheses. Solid triangles are filled pixel by pixel against a
+// depth buffer, without anti-aliasing.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use macroquad::prelude::*;
+
+use crate::canvas::{self, Channels, LineCanvas};
+use crate::tubes::SolidMesh;
+
+pub struct SoftwareCanvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    depth: Vec<f32>, // Depth of the solid triangle drawn at each pixel
+    channels: Channels,
+}
+
+impl SoftwareCanvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![WHITE; width * height],
+            depth: Vec::new(),
+            channels: Channels::All,
+        }
+    }
+
+    // Top-down RGBA bytes
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| {
+                [pixel.r, pixel.g, pixel.b, pixel.a].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            })
+            .collect()
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.to_rgba8())?;
+        Ok(())
+    }
+
+    // Blend `color` over the pixel at (x, y) with the given coverage (0 to 1)
+    fn blend(&mut self, x: usize, y: usize, color: Color, coverage: f32) {
+        let alpha = color.a * coverage;
+        let pixel = &mut self.pixels[y * self.width + x];
+        let blended = Color::new(
+            color.r * alpha + pixel.r * (1.0 - alpha),
+            color.g * alpha + pixel.g * (1.0 - alpha),
+            color.b * alpha + pixel.b * (1.0 - alpha),
+            alpha + pixel.a * (1.0 - alpha),
+        );
+        write_channels(self.channels, pixel, blended);
+    }
+
+    // Visit every pixel whose center lies within `margin` of the box around `min`..`max`,
+    // blending in the color with the coverage returned by `shade_at`
+    fn fill_box(&mut self, min: Vec2, max: Vec2, margin: f32, shade_at: impl Fn(Vec2) -> (f32, Color)) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let x0 = (min.x - margin).floor().max(0.0) as usize;
+        let y0 = (min.y - margin).floor().max(0.0) as usize;
+        let x1 = ((max.x + margin).ceil().max(0.0) as usize).min(self.width - 1);
+        let y1 = ((max.y + margin).ceil().max(0.0) as usize).min(self.height - 1);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let (coverage, color) = shade_at(Vec2::new(x as f32 + 0.5, y as f32 + 0.5));
+                if coverage > 0.0 {
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+}
+
+// Copy the channels drawn into from `color` to `pixel`
+fn write_channels(channels: Channels, pixel: &mut Color, color: Color) {
+    let (red, green, blue, alpha) = channels.mask();
+    let channels = [(red, &mut pixel.r, color.r), (green, &mut pixel.g, color.g), (blue, &mut pixel.b, color.b), (alpha, &mut pixel.a, color.a)];
+    for (_, channel, value) in channels.into_iter().filter(|(write, ..)| *write) {
+        *channel = value;
+    }
+}
+
+// Rows of a 5x7 glyph from the top, the leftmost pixel in the highest of the five bits
+fn glyph(character: char) -> [u8; 7] {
+    match character {
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        _ => [0; 7], // Anything else leaves a gap
+    }
+}
+
+// Coverage of a pixel whose center is `distance` away from the middle of a stroke
+fn stroke_coverage(distance: f32, thickness: f32) -> f32 {
+    (thickness / 2.0 + 0.5 - distance).clamp(0.0, 1.0)
+}
+
+impl LineCanvas for SoftwareCanvas {
+    fn clear(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    fn line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        if !(start.is_finite() && end.is_finite()) {
+            return;
+        }
+        let segment = end - start;
+        let length_squared = segment.length_squared();
+        self.fill_box(start.min(end), start.max(end), thickness / 2.0 + 1.0, |point| {
+            // Distance from the pixel center to the closest point on the segment
+            let t = if length_squared > 0.0 { ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0) } else { 0.0 };
+            (stroke_coverage(point.distance(start + segment * t), thickness), color)
+        });
+    }
+
+    fn gradient_line(&mut self, start: Vec2, end: Vec2, thickness: f32, start_color: Color, end_color: Color) {
+        if !(start.is_finite() && end.is_finite()) {
+            return;
+        }
+        let segment = end - start;
+        let length_squared = segment.length_squared();
+        let (from, to) = (start_color.to_vec(), end_color.to_vec());
+        self.fill_box(start.min(end), start.max(end), thickness / 2.0 + 1.0, |point| {
+            // The closest point on the segment also gives the color
+            let t = if length_squared > 0.0 { ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0) } else { 0.0 };
+            (stroke_coverage(point.distance(start + segment * t), thickness), Color::from_vec(from.lerp(to, t)))
+        });
+    }
+
+    fn circle_outline(&mut self, center: Vec2, radius: f32, thickness: f32, color: Color) {
+        let extent = Vec2::splat(radius);
+        self.fill_box(center - extent, center + extent, thickness / 2.0 + 1.0, |point| {
+            (stroke_coverage((point.distance(center) - radius).abs(), thickness), color)
+        });
+    }
+
+    // Glyph pixels are blown up to squares so the text comes out about as tall as the
+    // window font of the same size
+    fn text(&mut self, text: &str, position: Vec2, size: f32, color: Color) {
+        let scale = (size / 9.0).round().max(1.0);
+        let top = position.y - 7.0 * scale;
+        for (index, character) in text.chars().enumerate() {
+            let left = position.x + index as f32 * 6.0 * scale;
+            for (row, bits) in glyph(character).iter().enumerate() {
+                for column in (0..5).filter(|column| bits & (0b10000 >> column) != 0) {
+                    let corner = Vec2::new(left + column as f32 * scale, top + row as f32 * scale);
+                    // Pixels whose centers fall inside the square
+                    self.fill_box(corner, corner + Vec2::splat(scale), 0.0, |point| {
+                        let inside = point.cmpge(corner).all() && point.cmplt(corner + Vec2::splat(scale)).all();
+                        (if inside { 1.0 } else { 0.0 }, color)
+                    });
+                }
+            }
+        }
+    }
+
+    fn solid(&mut self, mesh: &SolidMesh, projection: &Mat4) {
+        // Each eye view starts with an empty depth buffer
+        self.depth.clear();
+        self.depth.resize(self.width * self.height, f32::INFINITY);
+        for (vertices, indices) in mesh.chunks() {
+            for triangle in indices.chunks_exact(3) {
+                let corners = [triangle[0], triangle[1], triangle[2]].map(|index| {
+                    let vertex = &vertices[index as usize];
+                    let projected = *projection * vertex.position.extend(1.0);
+                    let color = Color::from_rgba(vertex.color[0], vertex.color[1], vertex.color[2], vertex.color[3]);
+                    (projected.w, canvas::project(projection, vertex.position), projected.z / projected.w, color.to_vec())
+                });
+                if corners.iter().any(|&(w, point, ..)| w <= 0.0 || !point.is_finite()) {
+                    continue;
+                }
+                let [(_, a, a_depth, a_color), (_, b, b_depth, b_color), (_, c, c_depth, c_color)] = corners;
+                let area = (b - a).perp_dot(c - a);
+                if area.abs() < 1e-6 {
+                    continue;
+                }
+                // Pixels whose centers lie inside, kept where nothing nearer was drawn
+                let (min, max) = (a.min(b).min(c), a.max(b).max(c));
+                let x0 = min.x.floor().max(0.0) as usize;
+                let y0 = min.y.floor().max(0.0) as usize;
+                let x1 = (max.x.ceil().max(0.0) as usize).min(self.width);
+                let y1 = (max.y.ceil().max(0.0) as usize).min(self.height);
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        let point = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                        // Barycentric weights of the corners, none negative inside
+                        let weight_a = (c - b).perp_dot(point - b) / area;
+                        let weight_b = (a - c).perp_dot(point - c) / area;
+                        let weight_c = 1.0 - weight_a - weight_b;
+                        if weight_a < 0.0 || weight_b < 0.0 || weight_c < 0.0 {
+                            continue;
+                        }
+                        let depth = a_depth * weight_a + b_depth * weight_b + c_depth * weight_c;
+                        let index = y * self.width + x;
+                        if depth < self.depth[index] {
+                            self.depth[index] = depth;
+                            let color = Color::from_vec(a_color * weight_a + b_color * weight_b + c_color * weight_c);
+                            write_channels(self.channels, &mut self.pixels[index], color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Everything is drawn straight into the pixels
+    fn flush(&mut self) {}
+
+    fn set_channels(&mut self, channels: Channels) {
+        self.channels = channels;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 8;
+    const INK: Color = Color::new(1.0, 0.0, 0.0, 1.0); // Pure red, unlike macroquad's RED
+
+    // Red channel of every pixel, row by row
+    fn red_rows(canvas: &SoftwareCanvas) -> Vec<Vec<u8>> {
+        canvas.to_rgba8().chunks(4).map(|pixel| pixel[0]).collect::<Vec<u8>>().chunks(SIZE).map(<[u8]>::to_vec).collect()
+    }
+
+    fn black_canvas() -> SoftwareCanvas {
+        let mut canvas = SoftwareCanvas::new(SIZE, SIZE);
+        canvas.clear(BLACK);
+        canvas
+    }
+
+    #[test]
+    fn draws_a_horizontal_line_through_pixel_centers() {
+        let mut canvas = black_canvas();
+        canvas.line(Vec2::new(1.5, 4.5), Vec2::new(6.5, 4.5), 1.0, INK);
+        let rows = red_rows(&canvas);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &red) in row.iter().enumerate() {
+                let on_line = y == 4 && (1..=6).contains(&x);
+                assert_eq!(red, if on_line { 255 } else { 0 }, "pixel ({}, {})", x, y);
+            }
+        }
+        // Only red was drawn, fully opaque
+        assert_eq!(&canvas.to_rgba8()[(4 * SIZE + 3) * 4..][..4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn thick_lines_cover_more_rows() {
+        let mut canvas = black_canvas();
+        canvas.line(Vec2::new(0.0, 4.5), Vec2::new(8.0, 4.5), 3.0, INK);
+        let column: Vec<u8> = red_rows(&canvas).iter().map(|row| row[4]).collect();
+        assert_eq!(column, [0, 0, 0, 255, 255, 255, 0, 0]);
+    }
+
+    #[test]
+    fn diagonal_lines_are_anti_aliased() {
+        let mut canvas = black_canvas();
+        canvas.line(Vec2::new(0.5, 0.5), Vec2::new(7.5, 7.5), 1.0, INK);
+        let rows = red_rows(&canvas);
+        for (index, row) in rows.iter().enumerate() {
+            assert_eq!(row[index], 255);
+        }
+        // Neighbors half a diagonal away are partly covered, those farther out not at all
+        let partial = rows[3][4];
+        assert!(partial > 50 && partial < 100, "{}", partial);
+        assert_eq!(rows[3][5], 0);
+        assert_eq!(rows[5][1], 0);
+    }
+
+    #[test]
+    fn lines_off_the_canvas_are_clipped() {
+        let mut canvas = black_canvas();
+        canvas.line(Vec2::new(-20.0, 2.5), Vec2::new(30.0, 2.5), 1.0, INK);
+        canvas.line(Vec2::new(-20.0, -20.0), Vec2::new(-10.0, -5.0), 1.0, INK);
+        let rows = red_rows(&canvas);
+        assert!(rows[2].iter().all(|&red| red == 255));
+        assert_eq!(rows.iter().flatten().filter(|&&red| red > 0).count(), SIZE);
+    }
+}
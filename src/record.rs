@@ -0,0 +1,236 @@
+// Off-screen recording of looping animations (animated GIF, APNG or numbered PNG frames)
+//
+// Every captured frame advances a copy of the viewer by a fixed number of rotation
+// steps, so the output does not depend on the real-time frame rate of the window.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+
+use crate::canvas::{LineCanvas, ScreenCanvas};
+use crate::measure::Measurement;
+use crate::StereogramViewer;
+
+// Playback rate written into GIF and APNG files
+pub const RECORD_FPS: u16 = 30;
+
+// Frames rendered per window frame while a recording is running
+const FRAMES_PER_TICK: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecordFormat {
+    Gif = 0,
+    Apng = 1,
+    PngSequence = 2,
+}
+
+impl RecordFormat {
+    pub fn next(self) -> Self {
+        match self {
+            RecordFormat::Gif => RecordFormat::Apng,
+            RecordFormat::Apng => RecordFormat::PngSequence,
+            RecordFormat::PngSequence => RecordFormat::Gif,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordFormat::Gif => "GIF",
+            RecordFormat::Apng => "APNG",
+            RecordFormat::PngSequence => "PNG frames",
+        }
+    }
+}
+
+// Where captured frames are written as they are rendered
+enum FrameSink {
+    Gif(gif::Encoder<BufWriter<File>>),
+    Apng(png::Writer<BufWriter<File>>),
+    PngSequence(PathBuf),
+}
+
+pub struct Recording {
+    scene: StereogramViewer, // Private copy of the viewer that is stepped frame by frame
+    target: RenderTarget,
+    canvas: ScreenCanvas,
+    width: u32,
+    height: u32,
+    step: f32,
+    frame_index: usize,
+    frame_count: usize,
+    sink: FrameSink,
+    output_path: PathBuf,
+}
+
+impl Recording {
+    // Record exactly one full period of the current rotation as a seamless loop
+    pub fn full_period(viewer: &StereogramViewer, format: RecordFormat, width: u32, height: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut scene = viewer.clone();
+        let frame_count = scene.loop_period_frames();
+        scene.snap_velocities_to_loop(frame_count);
+        Self::start(scene, format, width, height, frame_count, 1.0)
+    }
+
+    // Record `frame_count` frames, advancing the rotation by `step` frames each time
+    pub fn fixed_steps(viewer: &StereogramViewer, format: RecordFormat, width: u32, height: u32, frame_count: usize, step: f32) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::start(viewer.clone(), format, width, height, frame_count.max(1), step)
+    }
+
+    // Record the keyframe timeline from start to end at its real-time pace
+    pub fn timeline(viewer: &StereogramViewer, format: RecordFormat, width: u32, height: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        if viewer.timeline.is_empty() {
+            return Err("the timeline has no keyframes".into());
+        }
+        let mut scene = viewer.clone();
+        scene.play_timeline();
+        let frame_count = (scene.timeline.duration() * RECORD_FPS as f32).round() as usize + 1; // Both end keyframes included
+        Self::start(scene, format, width, height, frame_count, 1.0)
+    }
+
+    fn start(mut scene: StereogramViewer, format: RecordFormat, width: u32, height: u32, frame_count: usize, step: f32) -> Result<Self, Box<dyn std::error::Error>> {
+        // Keep the marks of a picked vertex or edge, and the measurements, out of the recording
+        scene.picked = None;
+        scene.measurement = Measurement::default();
+        scene.show_radii = false;
+
+        let base_name = format!(
+            "stero3d_{}_{}",
+            file_name_part(&scene.shape_name()),
+            file_name_part(scene.stereo_layout.name())
+        );
+
+        let (sink, output_path) = match format {
+            RecordFormat::Gif => {
+                let path = crate::scene::next_numbered_file(&base_name, "gif");
+                let file = BufWriter::new(File::create(&path)?);
+                let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                (FrameSink::Gif(encoder), path)
+            }
+            RecordFormat::Apng => {
+                let path = crate::scene::next_numbered_file(&base_name, "png");
+                let file = BufWriter::new(File::create(&path)?);
+                let mut encoder = png::Encoder::new(file, width, height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(frame_count as u32, 0)?;
+                encoder.set_frame_delay(1, RECORD_FPS)?;
+                (FrameSink::Apng(encoder.write_header()?), path)
+            }
+            RecordFormat::PngSequence => {
+                let path = crate::scene::next_numbered_dir(&format!("{}_frames", base_name));
+                fs::create_dir_all(&path)?;
+                (FrameSink::PngSequence(path.clone()), path)
+            }
+        };
+
+        // With a depth attachment for the ball-and-stick style
+        let target = render_target_ex(width, height, RenderTargetParams { sample_count: 1, depth: true });
+        target.texture.set_filter(FilterMode::Nearest);
+
+        Ok(Self {
+            scene,
+            target,
+            canvas: ScreenCanvas::new(),
+            width,
+            height,
+            step,
+            frame_index: 0,
+            frame_count,
+            sink,
+            output_path,
+        })
+    }
+
+    pub fn progress(&self) -> (usize, usize) {
+        (self.frame_index, self.frame_count)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame_index >= self.frame_count
+    }
+
+    // Render and write the next few frames (called once per window frame)
+    pub fn capture_frames(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        for _ in 0..FRAMES_PER_TICK {
+            if self.is_finished() {
+                break;
+            }
+            let rgba = self.render_frame();
+            self.write_frame(rgba)?;
+            self.scene.advance(self.step);
+            self.scene.advance_timeline(1.0 / RECORD_FPS as f32);
+            self.scene.wiggle_clock += 1.0 / RECORD_FPS as f32;
+            self.frame_index += 1;
+        }
+        Ok(())
+    }
+
+    // Finish the output file and return where it was written
+    pub fn finish(self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match self.sink {
+            FrameSink::Gif(encoder) => {
+                encoder.into_inner()?;
+            }
+            FrameSink::Apng(writer) => writer.finish()?,
+            FrameSink::PngSequence(_) => {}
+        }
+        Ok(self.output_path)
+    }
+
+    // Draw the current scene into the render target and read it back as top-down RGBA rows
+    fn render_frame(&mut self) -> Vec<u8> {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, width, height));
+        camera.render_target = Some(self.target.clone());
+        set_camera(&camera);
+
+        self.canvas.clear(self.scene.background_color());
+        self.scene.draw_stereo_views(&mut self.canvas, width, height);
+
+        // Switching cameras flushes the queued draw calls into the render target
+        set_default_camera();
+
+        // Render target rows come back bottom-up, flip them for image files
+        let image = self.target.texture.get_texture_data();
+        let row_bytes = self.width as usize * 4;
+        image
+            .bytes
+            .chunks_exact(row_bytes)
+            .rev()
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    fn write_frame(&mut self, mut rgba: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+        match &mut self.sink {
+            FrameSink::Gif(encoder) => {
+                let mut frame = gif::Frame::from_rgba_speed(self.width as u16, self.height as u16, &mut rgba, 10);
+                frame.delay = 100 / RECORD_FPS; // Centiseconds per frame
+                encoder.write_frame(&frame)?;
+            }
+            FrameSink::Apng(writer) => {
+                writer.write_image_data(&rgba)?;
+            }
+            FrameSink::PngSequence(directory) => {
+                let path = directory.join(format!("frame_{:04}.png", self.frame_index));
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = png::Encoder::new(file, self.width, self.height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.write_header()?.write_image_data(&rgba)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Lowercase a display name and replace anything but letters and digits with dashes
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
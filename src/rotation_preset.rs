@@ -0,0 +1,136 @@
+// 4D rotation presets: simple, double and isoclinic (Clifford) rotations
+//
+// Six independent plane speeds do not add up to a true double rotation, because the
+// plane rotations in `apply_4d_rotations` do not commute. A preset instead describes
+// one constant 4D rotation as a left/right quaternion pair and composes it onto the
+// current orientation every frame. In a double rotation two orthogonal planes turn at
+// their own speeds; when the speeds are equal the rotation is isoclinic and every
+// point moves along a great circle at the same speed. Left isoclinic rotations are a
+// pure left quaternion multiplication, right isoclinic ones a pure right multiplication.
+
+use macroquad::prelude::*;
+
+use crate::orientation::plane_pair;
+
+// Planes in the viewer's order: XY, XZ, YZ, XW, YW, ZW
+const PLANE_NAMES: [&str; 6] = ["XY", "XZ", "YZ", "XW", "YW", "ZW"];
+
+// Orthogonal plane pairs (indices into PLANE_NAMES) and the quaternion axis they share
+const PLANE_PAIRS: [(usize, usize, Vec3); 3] = [(0, 5, Vec3::Z), (1, 4, Vec3::Y), (3, 2, Vec3::X)];
+
+// Speed change per key press and largest speed, in radians per frame like the plane speed sliders
+const SPEED_STEP: f32 = 0.0025;
+const MAX_SPEED: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PresetKind {
+    Off, // The six plane speeds drive the rotation
+    Simple,
+    Double,
+    LeftIsoclinic,
+    RightIsoclinic,
+}
+
+impl PresetKind {
+    pub fn next(self) -> Self {
+        match self {
+            PresetKind::Off => PresetKind::Simple,
+            PresetKind::Simple => PresetKind::Double,
+            PresetKind::Double => PresetKind::LeftIsoclinic,
+            PresetKind::LeftIsoclinic => PresetKind::RightIsoclinic,
+            PresetKind::RightIsoclinic => PresetKind::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PresetKind::Off => "Off (plane speeds)",
+            PresetKind::Simple => "Simple",
+            PresetKind::Double => "Double",
+            PresetKind::LeftIsoclinic => "Left isoclinic",
+            PresetKind::RightIsoclinic => "Right isoclinic",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RotationPreset {
+    pub kind: PresetKind,
+    plane: usize, // Plane for simple rotations
+    pair: usize, // Plane pair for double and isoclinic rotations
+    speeds: [f32; 2], // Radians per frame; the second is only used by double rotations
+}
+
+impl Default for RotationPreset {
+    fn default() -> Self {
+        Self {
+            kind: PresetKind::Off,
+            plane: 3, // XW, the plane that shows off the fourth dimension best
+            pair: 0,
+            speeds: [0.01, 0.005],
+        }
+    }
+}
+
+impl RotationPreset {
+    pub fn is_active(&self) -> bool {
+        self.kind != PresetKind::Off
+    }
+
+    // Next plane for simple rotations, next plane pair for the others
+    pub fn next_planes(&mut self) {
+        if self.kind == PresetKind::Simple {
+            self.plane = (self.plane + 1) % PLANE_NAMES.len();
+        } else {
+            self.pair = (self.pair + 1) % PLANE_PAIRS.len();
+        }
+    }
+
+    pub fn planes_name(&self) -> String {
+        if self.kind == PresetKind::Simple {
+            PLANE_NAMES[self.plane].to_owned()
+        } else {
+            let (first, second, _) = PLANE_PAIRS[self.pair];
+            format!("{}+{}", PLANE_NAMES[first], PLANE_NAMES[second])
+        }
+    }
+
+    pub fn speed(&self, index: usize) -> f32 {
+        self.speeds[index]
+    }
+
+    pub fn adjust_speed(&mut self, index: usize, steps: f32) {
+        let speed = (self.speeds[index] + steps * SPEED_STEP).clamp(-MAX_SPEED, MAX_SPEED);
+        self.speeds[index] = (speed / SPEED_STEP).round() * SPEED_STEP;
+    }
+
+    // Speeds that take part in the current preset
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn speeds_mut(&mut self) -> Vec<&mut f32> {
+        let [first, second] = &mut self.speeds;
+        match self.kind {
+            PresetKind::Off => Vec::new(),
+            PresetKind::Double => vec![first, second],
+            _ => vec![first],
+        }
+    }
+
+    // Left and right quaternions of the rotation made over `steps` frames
+    pub fn step_pair(&self, steps: f32) -> (Quat, Quat) {
+        let [first, second] = self.speeds.map(|speed| speed * steps);
+        let (first_plane, second_plane, axis) = PLANE_PAIRS[self.pair];
+        match self.kind {
+            PresetKind::Off => (Quat::IDENTITY, Quat::IDENTITY),
+            PresetKind::Simple => plane_pair(self.plane, first),
+            PresetKind::Double => {
+                // Orthogonal planes commute, so the order does not matter
+                let (left_a, right_a) = plane_pair(first_plane, first);
+                let (left_b, right_b) = plane_pair(second_plane, second);
+                (left_b * left_a, right_a * right_b)
+            }
+            // A unit quaternion exp(u·a) turns both planes of its pair by `a`
+            PresetKind::LeftIsoclinic => (Quat::from_axis_angle(axis, 2.0 * first), Quat::IDENTITY),
+            PresetKind::RightIsoclinic => (Quat::IDENTITY, Quat::from_axis_angle(axis, 2.0 * first)),
+        }
+    }
+}
@@ -0,0 +1,350 @@
+// Scene files: a complete, human-editable snapshot of the viewer (native only)
+//
+// A scene holds the shape (with its dimension, dual morph and highlighted cell), every
+// rotation angle and velocity, and all viewing settings, so loading it restores exactly
+// what was on screen. Scenes are TOML,
+// which makes it easy to keep a library of teaching scenes under version control.
+// Angles are in degrees and velocities in degrees per frame, like the command line.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::{self, DepthRange, Palette};
+use crate::depth_cue::Fog;
+use crate::tubes::RenderStyle;
+use crate::orientation::Angles;
+use crate::wythoff::WythoffShape;
+use crate::{polytope_nd, Hypersolid, PlatonicSolid, StereoLayout, StereogramViewer, VertexMarks};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    mode: String, // "3d" or "4d"
+    solid: String,
+    hypersolid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uniform: Option<String>, // Wythoff shape shown instead, e.g. "b4-1001"
+    #[serde(default = "default_dimension")]
+    dimension: usize, // Of the polytope in 4D mode, 5 to 8 for the higher ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nd_rotation: Vec<f32>, // Plane angles above 4D, in the order the 4D controls list them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nd_velocity: Vec<f32>,
+    #[serde(default)]
+    morph: f32, // Of the way to the dual, 0 to 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cell: Option<usize>, // Highlighted cell, counted from 1 like --cell
+    #[serde(default)]
+    isolate: bool, // Only the highlighted cell and its neighbors shown
+    paused: bool,
+    rotation: Angles,
+    velocity: Angles,
+    view: View,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct View {
+    eye_separation: f32,
+    perspective_distance: f32,
+    orthographic: bool,
+    dark_background: bool,
+    coloring: String, // "none", "z" or "w"
+    #[serde(default = "default_palette")]
+    palette: String, // Colormap of the depth coloring, e.g. "viridis"
+    #[serde(default = "default_depth_range")]
+    depth_range: String, // "frame" or "fixed"
+    #[serde(default)]
+    depth_width: bool, // Near edges wider, far ones thinner
+    #[serde(default = "default_fog")]
+    fog: String, // "off", "light", "medium" or "dense"
+    #[serde(default = "default_vertex_marks")]
+    vertex_marks: String, // "off", "dots", "indices" or "coordinates"
+    #[serde(default = "default_style")]
+    style: String, // "wireframe" or "tubes"
+    guides: bool,
+    layout: String,
+    wiggle_rate: f32,
+    wiggle_in_between: usize,
+}
+
+impl Scene {
+    pub fn from_viewer(viewer: &StereogramViewer) -> Self {
+        Self {
+            mode: if viewer.is_4d_mode { "4d" } else { "3d" }.to_owned(),
+            solid: viewer.current_solid.key().to_owned(),
+            hypersolid: viewer.current_hypersolid.key().to_owned(),
+            uniform: viewer.uniform.as_ref().map(|(shape, _)| shape.key()),
+            dimension: viewer.dimension,
+            nd_rotation: viewer.nd_rotation.iter().map(|angle| angle.to_degrees()).collect(),
+            nd_velocity: viewer.nd_rotation_velocity.iter().map(|velocity| velocity.to_degrees()).collect(),
+            morph: viewer.morph,
+            cell: viewer.selected_cell.map(|cell| cell + 1),
+            isolate: viewer.isolate_cell,
+            paused: viewer.is_paused,
+            rotation: Angles {
+                x: viewer.rotation_x.to_degrees(),
+                y: viewer.rotation_y.to_degrees(),
+                z: viewer.rotation_z.to_degrees(),
+                xy: viewer.rotation_xy.to_degrees(),
+                xz: viewer.rotation_xz.to_degrees(),
+                yz: viewer.rotation_yz.to_degrees(),
+                xw: viewer.rotation_xw.to_degrees(),
+                yw: viewer.rotation_yw.to_degrees(),
+                zw: viewer.rotation_zw.to_degrees(),
+            },
+            velocity: Angles {
+                x: viewer.rotation_velocity_x.to_degrees(),
+                y: viewer.rotation_velocity_y.to_degrees(),
+                z: viewer.rotation_velocity_z.to_degrees(),
+                xy: viewer.rotation_velocity_xy.to_degrees(),
+                xz: viewer.rotation_velocity_xz.to_degrees(),
+                yz: viewer.rotation_velocity_yz.to_degrees(),
+                xw: viewer.rotation_velocity_xw.to_degrees(),
+                yw: viewer.rotation_velocity_yw.to_degrees(),
+                zw: viewer.rotation_velocity_zw.to_degrees(),
+            },
+            view: View {
+                eye_separation: viewer.eye_separation,
+                perspective_distance: viewer.perspective_distance,
+                orthographic: viewer.orthographic,
+                dark_background: viewer.dark_background,
+                coloring: colormap::coloring_key(viewer).to_owned(),
+                palette: viewer.palette.key().to_owned(),
+                depth_range: viewer.depth_range.key().to_owned(),
+                depth_width: viewer.depth_width,
+                fog: viewer.fog.key().to_owned(),
+                vertex_marks: viewer.vertex_marks.key().to_owned(),
+                style: viewer.render_style.key().to_owned(),
+                guides: viewer.show_guides,
+                layout: viewer.stereo_layout.key().to_owned(),
+                wiggle_rate: viewer.wiggle_rate,
+                wiggle_in_between: viewer.wiggle_in_between,
+            },
+        }
+    }
+
+    // Restore the viewer from the scene. Names are checked first, so a scene
+    // with a typo leaves the viewer untouched.
+    pub fn apply(&self, viewer: &mut StereogramViewer) -> Result<(), String> {
+        let is_4d_mode = match self.mode.as_str() {
+            "3d" => false,
+            "4d" => true,
+            other => return Err(format!("unknown mode '{}' (expected 3d or 4d)", other)),
+        };
+        let solid = PlatonicSolid::from_key(&self.solid).ok_or_else(|| format!("unknown solid '{}'", self.solid))?;
+        let hypersolid = Hypersolid::from_key(&self.hypersolid).ok_or_else(|| format!("unknown hypersolid '{}'", self.hypersolid))?;
+        let uniform = match &self.uniform {
+            Some(key) => Some(WythoffShape::from_key(key).ok_or_else(|| format!("unknown uniform polytope '{}'", key))?),
+            None => None,
+        };
+        if !(4..=polytope_nd::MAX_DIMENSION).contains(&self.dimension) {
+            return Err(format!("unsupported dimension {} (expected 4 to {})", self.dimension, polytope_nd::MAX_DIMENSION));
+        }
+        let planes = if self.dimension > 4 { polytope_nd::planes(self.dimension).len() } else { 0 };
+        for (name, values) in [("nd_rotation", &self.nd_rotation), ("nd_velocity", &self.nd_velocity)] {
+            if !values.is_empty() && values.len() != planes {
+                return Err(format!("{} has {} angles, {}D has {} rotation planes", name, values.len(), self.dimension, planes));
+            }
+        }
+        if self.cell == Some(0) {
+            return Err("cells are counted from 1".to_owned());
+        }
+        let layout = StereoLayout::from_key(&self.view.layout).ok_or_else(|| format!("unknown layout '{}'", self.view.layout))?;
+        let (depth_coloring, w_depth_coloring) = colormap::parse_coloring(&self.view.coloring)?;
+        let palette = Palette::from_key(&self.view.palette).ok_or_else(|| format!("unknown palette '{}'", self.view.palette))?;
+        let depth_range = DepthRange::from_key(&self.view.depth_range)
+            .ok_or_else(|| format!("unknown depth range '{}' (expected frame or fixed)", self.view.depth_range))?;
+        let fog = Fog::from_key(&self.view.fog).ok_or_else(|| format!("unknown fog '{}' (expected off, light, medium or dense)", self.view.fog))?;
+        let vertex_marks = VertexMarks::from_key(&self.view.vertex_marks)
+            .ok_or_else(|| format!("unknown vertex marks '{}' (expected off, dots, indices or coordinates)", self.view.vertex_marks))?;
+        let style = RenderStyle::from_key(&self.view.style).ok_or_else(|| format!("unknown style '{}' (expected wireframe or tubes)", self.view.style))?;
+
+        viewer.is_4d_mode = is_4d_mode;
+        viewer.current_solid = solid;
+        viewer.current_hypersolid = hypersolid;
+        match uniform {
+            Some(shape) => viewer.select_uniform(shape),
+            None => viewer.uniform = None,
+        }
+        viewer.set_dimension(self.dimension);
+        if !self.nd_rotation.is_empty() {
+            viewer.nd_rotation = self.nd_rotation.iter().map(|angle| angle.to_radians()).collect();
+        }
+        if !self.nd_velocity.is_empty() {
+            viewer.nd_rotation_velocity = self.nd_velocity.iter().map(|velocity| velocity.to_radians()).collect();
+        }
+        viewer.morph = self.morph.clamp(0.0, 1.0);
+        viewer.selected_cell = self.cell.map(|cell| cell - 1);
+        viewer.isolate_cell = self.isolate;
+        viewer.is_paused = self.paused;
+
+        let rotation = self.rotation;
+        viewer.rotation_x = rotation.x.to_radians();
+        viewer.rotation_y = rotation.y.to_radians();
+        viewer.rotation_z = rotation.z.to_radians();
+        viewer.rotation_xy = rotation.xy.to_radians();
+        viewer.rotation_xz = rotation.xz.to_radians();
+        viewer.rotation_yz = rotation.yz.to_radians();
+        viewer.rotation_xw = rotation.xw.to_radians();
+        viewer.rotation_yw = rotation.yw.to_radians();
+        viewer.rotation_zw = rotation.zw.to_radians();
+
+        let velocity = self.velocity;
+        viewer.rotation_velocity_x = velocity.x.to_radians();
+        viewer.rotation_velocity_y = velocity.y.to_radians();
+        viewer.rotation_velocity_z = velocity.z.to_radians();
+        viewer.rotation_velocity_xy = velocity.xy.to_radians();
+        viewer.rotation_velocity_xz = velocity.xz.to_radians();
+        viewer.rotation_velocity_yz = velocity.yz.to_radians();
+        viewer.rotation_velocity_xw = velocity.xw.to_radians();
+        viewer.rotation_velocity_yw = velocity.yw.to_radians();
+        viewer.rotation_velocity_zw = velocity.zw.to_radians();
+
+        let view = &self.view;
+        viewer.eye_separation = view.eye_separation.clamp(0.05, 0.3);
+        viewer.perspective_distance = view.perspective_distance.clamp(2.0, 20.0);
+        viewer.orthographic = view.orthographic;
+        viewer.dark_background = view.dark_background;
+        viewer.depth_coloring = depth_coloring;
+        viewer.w_depth_coloring = w_depth_coloring;
+        viewer.palette = palette;
+        viewer.depth_range = depth_range;
+        viewer.depth_width = view.depth_width;
+        viewer.fog = fog;
+        viewer.vertex_marks = vertex_marks;
+        viewer.render_style = style;
+        viewer.show_guides = view.guides;
+        viewer.stereo_layout = layout;
+        viewer.wiggle_rate = view.wiggle_rate.clamp(0.5, 10.0);
+        viewer.wiggle_in_between = view.wiggle_in_between.min(3);
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
+
+// Scenes saved before higher dimensions existed show the hypersolids
+fn default_dimension() -> usize {
+    4
+}
+
+// Scenes saved before the colormaps existed use the defaults
+fn default_palette() -> String {
+    Palette::default().key().to_owned()
+}
+
+fn default_depth_range() -> String {
+    DepthRange::default().key().to_owned()
+}
+
+// Scenes saved before the depth cues existed have no fog
+fn default_fog() -> String {
+    Fog::default().key().to_owned()
+}
+
+fn default_vertex_marks() -> String {
+    VertexMarks::default().key().to_owned()
+}
+
+fn default_style() -> String {
+    RenderStyle::default().key().to_owned()
+}
+
+// First unused <prefix>_NNN.toml in the current directory
+pub fn next_numbered_path(prefix: &str) -> PathBuf {
+    next_numbered_file(prefix, "toml")
+}
+
+// First unused <prefix>_NNN.<extension> in the current directory
+pub fn next_numbered_file(prefix: &str, extension: &str) -> PathBuf {
+    next_numbered(|number| format!("{}_{:03}.{}", prefix, number, extension))
+}
+
+// First unused directory name <prefix>_NNN in the current directory
+pub fn next_numbered_dir(prefix: &str) -> PathBuf {
+    next_numbered(|number| format!("{}_{:03}", prefix, number))
+}
+
+fn next_numbered(name: impl Fn(usize) -> String) -> PathBuf {
+    (1..).map(|number| PathBuf::from(name(number))).find(|path| !path.exists()).expect("ran out of file names")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A viewer with settings away from the defaults in every part of the scene
+    fn edited_viewer() -> StereogramViewer {
+        let mut viewer = StereogramViewer::new();
+        viewer.is_4d_mode = true;
+        viewer.current_hypersolid = Hypersolid::FourOrthoplex;
+        viewer.current_solid = PlatonicSolid::Dodecahedron;
+        viewer.morph = 0.25;
+        viewer.selected_cell = Some(2);
+        viewer.isolate_cell = true;
+        viewer.is_paused = true;
+        viewer.rotation_xw = 0.5;
+        viewer.rotation_velocity_y = 0.02;
+        viewer.rotation_velocity_zw = -0.01;
+        viewer.eye_separation = 0.2;
+        viewer.dark_background = true;
+        viewer.palette = Palette::Magma;
+        viewer.fog = Fog::Dense;
+        viewer.stereo_layout = StereoLayout::CrossEyed;
+        viewer
+    }
+
+    fn scene_text() -> String {
+        toml::to_string_pretty(&Scene::from_viewer(&edited_viewer())).unwrap()
+    }
+
+    #[test]
+    fn saved_scene_restores_the_viewer() {
+        let scene = Scene::from_viewer(&edited_viewer());
+        let path = std::env::temp_dir().join(format!("stero3d_scene_test_{}.toml", std::process::id()));
+        scene.save(&path).unwrap();
+        let loaded = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded, scene);
+
+        let mut restored = StereogramViewer::new();
+        loaded.apply(&mut restored).unwrap();
+        assert_eq!(Scene::from_viewer(&restored), scene);
+        assert_eq!(restored.selected_cell, Some(2));
+        assert_eq!(restored.stereo_layout, StereoLayout::CrossEyed);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let top_level = format!("colour = \"red\"\n{}", scene_text());
+        let error = toml::from_str::<Scene>(&top_level).unwrap_err().to_string();
+        assert!(error.contains("unknown field `colour`"), "{}", error);
+
+        // The view table comes last, so a key added at the end lands in it
+        let in_view = format!("{}fov = 60.0\n", scene_text());
+        let error = toml::from_str::<Scene>(&in_view).unwrap_err().to_string();
+        assert!(error.contains("unknown field `fov`"), "{}", error);
+    }
+
+    #[test]
+    fn bad_names_leave_the_viewer_untouched() {
+        let text = scene_text().replace("layout = \"cross\"", "layout = \"sideways\"");
+        let scene: Scene = toml::from_str(&text).unwrap();
+        let mut viewer = StereogramViewer::new();
+        let before = Scene::from_viewer(&viewer);
+        assert_eq!(scene.apply(&mut viewer), Err("unknown layout 'sideways'".to_owned()));
+        assert_eq!(Scene::from_viewer(&viewer), before);
+    }
+}
@@ -0,0 +1,307 @@
+// Viewer settings that persist between sessions
+//
+// Only the viewing preferences are stored (eye separation, projection, colors, layout
+// and UI visibility), not the shape or its orientation. The stored form is plain
+// `key = value` lines, which is valid TOML and easy to edit by hand. Native builds keep
+// it in the user's config directory, the web build in localStorage.
+
+use crate::colormap::{DepthRange, Palette};
+use crate::depth_cue::Fog;
+use crate::tubes::RenderStyle;
+use crate::{StereoLayout, StereogramViewer, VertexMarks};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    eye_separation: f32,
+    perspective_distance: f32,
+    orthographic: bool,
+    dark_background: bool,
+    depth_coloring: bool,
+    w_depth_coloring: bool,
+    palette: Palette,
+    depth_range: DepthRange,
+    depth_width: bool,
+    fog: Fog,
+    vertex_marks: VertexMarks,
+    render_style: RenderStyle,
+    show_guides: bool,
+    show_ui: bool,
+    stereo_layout: StereoLayout,
+    wiggle_rate: f32,
+    wiggle_in_between: usize,
+}
+
+impl Settings {
+    pub fn from_viewer(viewer: &StereogramViewer) -> Self {
+        Self {
+            eye_separation: viewer.eye_separation,
+            perspective_distance: viewer.perspective_distance,
+            orthographic: viewer.orthographic,
+            dark_background: viewer.dark_background,
+            depth_coloring: viewer.depth_coloring,
+            w_depth_coloring: viewer.w_depth_coloring,
+            palette: viewer.palette,
+            depth_range: viewer.depth_range,
+            depth_width: viewer.depth_width,
+            fog: viewer.fog,
+            vertex_marks: viewer.vertex_marks,
+            render_style: viewer.render_style,
+            show_guides: viewer.show_guides,
+            show_ui: viewer.show_ui,
+            stereo_layout: viewer.stereo_layout,
+            wiggle_rate: viewer.wiggle_rate,
+            wiggle_in_between: viewer.wiggle_in_between,
+        }
+    }
+
+    // The hard-coded defaults of a freshly started viewer
+    pub fn defaults() -> Self {
+        Self::from_viewer(&StereogramViewer::new())
+    }
+
+    pub fn apply(&self, viewer: &mut StereogramViewer) {
+        viewer.eye_separation = self.eye_separation;
+        viewer.perspective_distance = self.perspective_distance;
+        viewer.orthographic = self.orthographic;
+        viewer.dark_background = self.dark_background;
+        viewer.depth_coloring = self.depth_coloring;
+        viewer.w_depth_coloring = self.w_depth_coloring;
+        viewer.palette = self.palette;
+        viewer.depth_range = self.depth_range;
+        viewer.depth_width = self.depth_width;
+        viewer.fog = self.fog;
+        viewer.vertex_marks = self.vertex_marks;
+        viewer.render_style = self.render_style;
+        viewer.show_guides = self.show_guides;
+        viewer.show_ui = self.show_ui;
+        viewer.stereo_layout = self.stereo_layout;
+        viewer.wiggle_rate = self.wiggle_rate;
+        viewer.wiggle_in_between = self.wiggle_in_between;
+    }
+
+    fn to_text(&self) -> String {
+        format!(
+            "eye_separation = {}\n\
+             perspective_distance = {}\n\
+             orthographic = {}\n\
+             dark_background = {}\n\
+             depth_coloring = {}\n\
+             w_depth_coloring = {}\n\
+             palette = \"{}\"\n\
+             depth_range = \"{}\"\n\
+             depth_width = {}\n\
+             fog = \"{}\"\n\
+             vertex_marks = \"{}\"\n\
+             render_style = \"{}\"\n\
+             show_guides = {}\n\
+             show_ui = {}\n\
+             stereo_layout = \"{}\"\n\
+             wiggle_rate = {}\n\
+             wiggle_in_between = {}\n",
+            self.eye_separation,
+            self.perspective_distance,
+            self.orthographic,
+            self.dark_background,
+            self.depth_coloring,
+            self.w_depth_coloring,
+            self.palette.key(),
+            self.depth_range.key(),
+            self.depth_width,
+            self.fog.key(),
+            self.vertex_marks.key(),
+            self.render_style.key(),
+            self.show_guides,
+            self.show_ui,
+            self.stereo_layout.key(),
+            self.wiggle_rate,
+            self.wiggle_in_between,
+        )
+    }
+
+    // Start from the defaults and override every recognised line; anything
+    // missing or malformed keeps its default value
+    fn from_text(text: &str) -> Self {
+        let mut settings = Self::defaults();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "eye_separation" => set_parsed(&mut settings.eye_separation, value),
+                "perspective_distance" => set_parsed(&mut settings.perspective_distance, value),
+                "orthographic" => set_parsed(&mut settings.orthographic, value),
+                "dark_background" => set_parsed(&mut settings.dark_background, value),
+                "depth_coloring" => set_parsed(&mut settings.depth_coloring, value),
+                "w_depth_coloring" => set_parsed(&mut settings.w_depth_coloring, value),
+                "palette" => {
+                    if let Some(palette) = Palette::from_key(value) {
+                        settings.palette = palette;
+                    }
+                }
+                "depth_range" => {
+                    if let Some(range) = DepthRange::from_key(value) {
+                        settings.depth_range = range;
+                    }
+                }
+                "depth_width" => set_parsed(&mut settings.depth_width, value),
+                "fog" => {
+                    if let Some(fog) = Fog::from_key(value) {
+                        settings.fog = fog;
+                    }
+                }
+                "vertex_marks" => {
+                    if let Some(marks) = VertexMarks::from_key(value) {
+                        settings.vertex_marks = marks;
+                    }
+                }
+                "render_style" => {
+                    if let Some(style) = RenderStyle::from_key(value) {
+                        settings.render_style = style;
+                    }
+                }
+                "show_guides" => set_parsed(&mut settings.show_guides, value),
+                "show_ui" => set_parsed(&mut settings.show_ui, value),
+                "stereo_layout" => {
+                    if let Some(layout) = StereoLayout::from_key(value) {
+                        settings.stereo_layout = layout;
+                    }
+                }
+                "wiggle_rate" => set_parsed(&mut settings.wiggle_rate, value),
+                "wiggle_in_between" => set_parsed(&mut settings.wiggle_in_between, value),
+                _ => {}
+            }
+        }
+
+        // Keep values inside the ranges the keyboard controls allow
+        settings.eye_separation = settings.eye_separation.clamp(0.05, 0.3);
+        settings.perspective_distance = settings.perspective_distance.clamp(2.0, 20.0);
+        settings.wiggle_rate = settings.wiggle_rate.clamp(0.5, 10.0);
+        settings.wiggle_in_between = settings.wiggle_in_between.min(3);
+        if settings.w_depth_coloring {
+            settings.depth_coloring = false;
+        }
+        settings
+    }
+
+    // These settings where they differ from `before`, and `otherwise` where they do not,
+    // going line by line through the stored form
+    fn changes_over(&self, before: &Settings, otherwise: &Settings) -> Self {
+        let (now, then, other) = (self.to_text(), before.to_text(), otherwise.to_text());
+        let lines: Vec<&str> = now
+            .lines()
+            .zip(then.lines())
+            .zip(other.lines())
+            .map(|((now, then), other)| if now != then { now } else { other })
+            .collect();
+        Self::from_text(&lines.join("\n"))
+    }
+
+    // Settings saved by a previous session, if any
+    pub fn load() -> Option<Self> {
+        storage::read().map(|text| Self::from_text(&text))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::write(&self.to_text())
+    }
+}
+
+fn set_parsed<T: std::str::FromStr>(field: &mut T, value: &str) {
+    if let Ok(parsed) = value.parse() {
+        *field = parsed;
+    }
+}
+
+// Saves the settings the user changes during the session. Whatever the command line or
+// page URL set for this launch alone stays out of the stored settings until it is
+// changed by hand.
+pub struct SettingsStore {
+    launched: Settings, // As the session started, overrides included
+    saved: Settings,
+}
+
+impl SettingsStore {
+    // Start tracking from the stored settings (or the defaults if there are none) and
+    // the viewer's settings after the overrides, without saving anything
+    pub fn new(stored: Settings, viewer: &StereogramViewer) -> Self {
+        Self {
+            launched: Settings::from_viewer(viewer),
+            saved: stored,
+        }
+    }
+
+    pub fn update(&mut self, viewer: &StereogramViewer) {
+        let current = Settings::from_viewer(viewer).changes_over(&self.launched, &self.saved);
+        if current != self.saved {
+            self.save(current);
+        }
+    }
+
+    // Go back to the built-in defaults, on screen and in the stored settings
+    pub fn reset(&mut self, viewer: &mut StereogramViewer) {
+        let defaults = Settings::defaults();
+        defaults.apply(viewer);
+        self.launched = defaults.clone();
+        self.save(defaults);
+    }
+
+    fn save(&mut self, settings: Settings) {
+        if let Err(err) = settings.save() {
+            eprintln!("Could not save settings: {}", err);
+        }
+        self.saved = settings;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::fs;
+    use std::path::PathBuf;
+
+    // e.g. ~/.config/stero3d/settings.toml on Linux
+    fn settings_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("stero3d").join("settings.toml"))
+    }
+
+    pub fn read() -> Option<String> {
+        fs::read_to_string(settings_path()?).ok()
+    }
+
+    pub fn write(text: &str) -> Result<(), String> {
+        let path = settings_path().ok_or("no config directory on this system")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
+
+// localStorage access through the JavaScript plugin in index.html
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    extern "C" {
+        fn stero3d_settings_length() -> i32;
+        fn stero3d_settings_copy(buffer: *mut u8, length: u32);
+        fn stero3d_settings_store(text: *const u8, length: u32);
+    }
+
+    // Lets the JavaScript plugin check that it matches this build
+    #[no_mangle]
+    pub extern "C" fn stero3d_settings_crate_version() -> u32 {
+        1
+    }
+
+    pub fn read() -> Option<String> {
+        // A negative length means nothing has been stored yet
+        let length = u32::try_from(unsafe { stero3d_settings_length() }).ok()?;
+        let mut buffer = vec![0u8; length as usize];
+        unsafe { stero3d_settings_copy(buffer.as_mut_ptr(), length) };
+        String::from_utf8(buffer).ok()
+    }
+
+    pub fn write(text: &str) -> Result<(), String> {
+        unsafe { stero3d_settings_store(text.as_ptr(), text.len() as u32) };
+        Ok(())
+    }
+}
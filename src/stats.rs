@@ -0,0 +1,211 @@
+// Statistics of the shape shown: element counts, Euler characteristic, symmetry group
+// and Schläfli symbol
+//
+// Vertices and edges are counted from the shape's own tables, and the faces, cells and
+// higher elements are found from its coordinates (see geometry::face_levels), or taken
+// from the Wythoff construction for uniform polytopes. The alternating sum of the counts
+// is checked against the Euler characteristic every convex polytope has, 1 - (-1)^n in
+// n dimensions, so a missing or doubled edge in a table shows up as a mismatch.
+
+use macroquad::prelude::*;
+
+use crate::{geometry, polytope_nd, Hypersolid, StereogramViewer};
+
+// Names of the counts by element dimension; higher ones are "4-faces" and so on
+const COUNT_NAMES: [&str; 4] = ["Vertices", "Edges", "Faces", "Cells"];
+
+const PANEL_WIDTH: f32 = 270.0;
+
+#[derive(Clone)]
+pub struct Stats {
+    key: String, // `faces_shape_key` of the shape counted
+    shape: String,
+    dimension: usize,
+    counts: Vec<usize>, // Elements of each dimension from the vertices up, as far as they are known
+    symmetry: (String, usize), // Group name and order
+    schlafli: Option<String>,
+}
+
+impl Stats {
+    pub fn compute(viewer: &StereogramViewer) -> Self {
+        let mesh = viewer.coordinate_mesh();
+        let dimension = mesh.vertices.first().map_or(0, Vec::len);
+        let mut counts = vec![mesh.vertices.len(), mesh.edges.len()];
+        let torus = viewer.is_4d_mode && !viewer.shows_higher_dimension() && viewer.current_hypersolid == Hypersolid::CliffordTorus;
+        if let Some((_, polytope)) = viewer.uniform_shown() {
+            counts.push(polytope.faces.len());
+            if viewer.is_4d_mode {
+                counts.push(polytope.cells.len());
+            }
+        } else if !torus {
+            // Found facets first
+            counts.extend(geometry::face_levels(&mesh.vertices, &mesh.edges, 2).iter().rev().map(Vec::len));
+        }
+
+        let (symmetry, schlafli) = if let Some((shape, _)) = viewer.uniform_shown() {
+            ((format!("{} {}", shape.group.name(), shape.group.bracket()), shape.group.order()), Some(shape.schlafli()))
+        } else if viewer.shows_higher_dimension() {
+            let family = viewer.current_hypersolid;
+            (polytope_nd::symmetry(family, viewer.dimension), Some(polytope_nd::schlafli(family, viewer.dimension)))
+        } else if viewer.is_4d_mode {
+            let hypersolid = viewer.current_hypersolid;
+            let schlafli = match (hypersolid.schlafli(), hypersolid.dual().and_then(Hypersolid::schlafli)) {
+                (Some(symbol), Some(dual)) => Some(morphed_schlafli(&symbol, &dual, viewer.morph)),
+                (symbol, _) => symbol,
+            };
+            (hypersolid.symmetry(), schlafli)
+        } else {
+            let solid = viewer.current_solid;
+            let group = solid.symmetry_group();
+            let schlafli = morphed_schlafli(solid.schlafli(), solid.dual().schlafli(), viewer.morph);
+            ((format!("{} {}", group.name(), group.bracket()), group.order()), Some(schlafli))
+        };
+
+        Self { key: viewer.faces_shape_key(), shape: viewer.shape_name(), dimension, counts, symmetry, schlafli }
+    }
+
+    // Whether these are the statistics of the shape with this `faces_shape_key`
+    pub fn is_for(&self, key: &str) -> bool {
+        self.key == key
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} ({}D)", self.shape, self.dimension)];
+        for (rank, count) in self.counts.iter().enumerate() {
+            let name = COUNT_NAMES.get(rank).map_or_else(|| format!("{}-faces", rank), |name| name.to_string());
+            lines.push(format!("{:<10}{}", name, count));
+        }
+
+        // The counts of every element short of the whole polytope are needed for the sum
+        if self.counts.len() == self.dimension {
+            let euler: i64 = self
+                .counts
+                .iter()
+                .enumerate()
+                .map(|(rank, &count)| if rank.is_multiple_of(2) { count as i64 } else { -(count as i64) })
+                .sum();
+            let expected = if self.dimension.is_multiple_of(2) { 0 } else { 2 };
+            lines.push(format!("Euler characteristic {}", euler));
+            lines.push(if euler == expected {
+                format!("  as for any {}D polytope: OK", self.dimension)
+            } else {
+                format!("  MISMATCH: {}D polytopes have {}", self.dimension, expected)
+            });
+        } else {
+            lines.push("No faces: a wire surface, not a polytope".to_owned());
+        }
+
+        let (group, order) = &self.symmetry;
+        lines.push(format!("Symmetry {}, order {}", group, order));
+        lines.push(format!("Schläfli symbol {}", self.schlafli.as_deref().unwrap_or("none")));
+        lines
+    }
+
+    // Panel at the upper right of the stereogram
+    // Area of the panel at the upper right of a stereogram this wide
+    pub fn panel_rect(&self, stereogram_width: f32) -> Rect {
+        Rect::new(stereogram_width - PANEL_WIDTH - 10.0, 50.0, PANEL_WIDTH, 35.0 + 18.0 * self.lines().len() as f32)
+    }
+
+    pub fn draw_panel(&self, stereogram_width: f32, dark_background: bool) {
+        let lines = self.lines();
+        let Rect { x: panel_x, y: panel_y, w: panel_width, h: panel_height } = self.panel_rect(stereogram_width);
+        draw_rectangle(
+            panel_x,
+            panel_y,
+            panel_width,
+            panel_height,
+            if dark_background { Color::new(0.1, 0.1, 0.1, 0.9) } else { Color::new(0.95, 0.95, 0.95, 0.9) },
+        );
+        let title_color = if dark_background { WHITE } else { BLACK };
+        let line_color = if dark_background { LIME } else { Color::new(0.0, 0.4, 0.0, 1.0) };
+        let warning_color = if dark_background { ORANGE } else { RED };
+        draw_text("STATISTICS", panel_x + 10.0, panel_y + 22.0, 16.0, title_color);
+        for (line, text) in lines.iter().enumerate() {
+            let color = if text.contains("MISMATCH") { warning_color } else { line_color };
+            draw_text(text, panel_x + 10.0, panel_y + 42.0 + 18.0 * line as f32, 14.0, color);
+        }
+    }
+}
+
+// Symbol of a shape `morph` of the way to its dual: its own at the start, the dual's at
+// the end and the rectified form halfway. Before halfway it is a truncation of the shape,
+// t{p,q}, and after it a truncation of the dual, t{q,p}.
+fn morphed_schlafli(symbol: &str, dual: &str, morph: f32) -> String {
+    if morph <= 0.0 {
+        symbol.to_owned()
+    } else if morph >= 1.0 {
+        dual.to_owned()
+    } else if (morph - 0.5).abs() < 1e-3 {
+        format!("r{}", symbol)
+    } else if morph < 0.5 {
+        format!("t{}", symbol)
+    } else {
+        format!("t{}", dual)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PlatonicSolid;
+
+    fn euler_line(viewer: &StereogramViewer) -> String {
+        let lines = Stats::compute(viewer).lines();
+        lines.iter().find(|line| line.contains("MISMATCH") || line.contains(": OK")).cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn morphs_are_named_by_how_far_they_have_gone() {
+        assert_eq!(morphed_schlafli("{4,3}", "{3,4}", 0.0), "{4,3}");
+        assert_eq!(morphed_schlafli("{4,3}", "{3,4}", 0.25), "t{4,3}");
+        assert_eq!(morphed_schlafli("{4,3}", "{3,4}", 0.5), "r{4,3}");
+        assert_eq!(morphed_schlafli("{4,3}", "{3,4}", 0.5004), "r{4,3}");
+        assert_eq!(morphed_schlafli("{4,3}", "{3,4}", 0.75), "t{3,4}");
+        assert_eq!(morphed_schlafli("{4,3}", "{3,4}", 1.0), "{3,4}");
+    }
+
+    #[test]
+    fn shapes_and_their_morphs_have_the_euler_characteristic() {
+        let mut viewer = StereogramViewer::new();
+        for solid in PlatonicSolid::ALL {
+            viewer.current_solid = solid;
+            for morph in [0.0, 0.3, 0.5, 0.7] {
+                viewer.morph = morph;
+                assert!(euler_line(&viewer).ends_with("OK"), "{} at {}", solid.name(), morph);
+            }
+        }
+        viewer.is_4d_mode = true;
+        for hypersolid in [Hypersolid::Tesseract, Hypersolid::FourSimplex, Hypersolid::FourOrthoplex] {
+            viewer.current_hypersolid = hypersolid;
+            for morph in [0.0, 0.3, 0.5] {
+                viewer.morph = morph;
+                assert!(euler_line(&viewer).ends_with("OK"), "{} at {}", hypersolid.name(), morph);
+            }
+        }
+    }
+
+    #[test]
+    fn higher_dimensions_have_the_euler_characteristic() {
+        let mut viewer = StereogramViewer::new();
+        viewer.is_4d_mode = true;
+        viewer.set_dimension(5);
+        for hypersolid in [Hypersolid::Tesseract, Hypersolid::FourSimplex, Hypersolid::FourOrthoplex] {
+            viewer.current_hypersolid = hypersolid;
+            let stats = Stats::compute(&viewer);
+            assert_eq!(stats.counts.len(), 5);
+            assert!(euler_line(&viewer).ends_with("OK"), "{}", viewer.shape_name());
+        }
+    }
+
+    #[test]
+    fn stats_report_the_regular_symmetry_of_the_4_simplex() {
+        let mut viewer = StereogramViewer::new();
+        viewer.is_4d_mode = true;
+        viewer.current_hypersolid = Hypersolid::FourSimplex;
+        let stats = Stats::compute(&viewer);
+        assert_eq!(stats.counts, [5, 10, 10, 5]);
+        assert_eq!(stats.symmetry, ("A4 [3,3,3]".to_owned(), 120));
+        assert_eq!(stats.schlafli.as_deref(), Some("{3,3,3}"));
+    }
+}
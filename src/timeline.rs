@@ -0,0 +1,289 @@
+// Keyframe animation timelines
+//
+// A timeline is a list of keyframes, each holding an orientation, projection distance,
+// eye separation, dual morph, dimension and depth coloring mode at a point in time.
+// While a timeline plays it drives the viewer instead of the constant rotation speeds,
+// on the clock: orientations glide along the shortest rotation (see orientation.rs),
+// distances, morphs and the plane angles above 4D blend with the easing of the keyframe
+// being approached, and the coloring mode and dimension switch when a keyframe is
+// reached. Timelines apply to whichever shape is shown. The native build saves and
+// loads them as TOML files like scenes.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::colormap::{coloring_key, parse_coloring};
+use crate::orientation::{Angles, Orientation};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::polytope_nd;
+use crate::StereogramViewer;
+
+// Time between a newly added keyframe and the one before it
+const KEYFRAME_SPACING: f32 = 2.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn next(self) -> Self {
+        match self {
+            Easing::Linear => Easing::EaseIn,
+            Easing::EaseIn => Easing::EaseOut,
+            Easing::EaseOut => Easing::EaseInOut,
+            Easing::EaseInOut => Easing::Linear,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::EaseIn => "Ease in",
+            Easing::EaseOut => "Ease out",
+            Easing::EaseInOut => "Ease in-out",
+        }
+    }
+
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Keyframe {
+    time: f32, // Seconds from the start of the timeline
+    easing: Easing, // How the previous keyframe blends into this one
+    rotation: Angles, // Degrees
+    perspective_distance: f32,
+    eye_separation: f32,
+    coloring: String, // "none", "z" or "w"
+    #[serde(default)]
+    morph: f32, // Of the way to the dual
+    #[serde(default = "default_dimension")]
+    dimension: usize, // Of the polytope in 4D mode
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nd_rotation: Vec<f32>, // Degrees, one per plane above 4D
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Timeline {
+    #[serde(default, rename = "keyframe")]
+    keyframes: Vec<Keyframe>,
+}
+
+impl Timeline {
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    // Time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    // Append the viewer's current state a fixed spacing after the last keyframe
+    pub fn add_keyframe(&mut self, viewer: &StereogramViewer) {
+        let time = if self.is_empty() { 0.0 } else { self.duration() + KEYFRAME_SPACING };
+        self.keyframes.push(Keyframe {
+            time,
+            easing: Easing::EaseInOut,
+            rotation: Angles::from_orientation(&Orientation::from_viewer(viewer)),
+            perspective_distance: viewer.perspective_distance,
+            eye_separation: viewer.eye_separation,
+            coloring: coloring_key(viewer).to_owned(),
+            morph: viewer.morph,
+            dimension: viewer.dimension,
+            nd_rotation: viewer.nd_rotation.iter().map(|angle| angle.to_degrees()).collect(),
+        });
+    }
+
+    pub fn remove_last_keyframe(&mut self) {
+        self.keyframes.pop();
+    }
+
+    // Easing used to reach the last keyframe
+    pub fn last_easing(&self) -> Option<Easing> {
+        self.keyframes.last().map(|keyframe| keyframe.easing)
+    }
+
+    pub fn cycle_last_easing(&mut self) {
+        if let Some(keyframe) = self.keyframes.last_mut() {
+            keyframe.easing = keyframe.easing.next();
+        }
+    }
+
+    // Put the viewer into the state the timeline describes at `time` seconds
+    pub fn apply_at(&self, time: f32, viewer: &mut StereogramViewer) {
+        let Some(last) = self.keyframes.last() else {
+            return;
+        };
+        // Next keyframe still ahead; before the first and after the last one the view holds still
+        let next = self.keyframes.iter().position(|keyframe| keyframe.time > time);
+        let (from, to, t) = match next {
+            None => (last, last, 0.0),
+            Some(0) => (&self.keyframes[0], &self.keyframes[0], 0.0),
+            Some(next) => {
+                let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+                (from, to, to.easing.apply((time - from.time) / (to.time - from.time)))
+            }
+        };
+        from.apply(to, t, viewer);
+    }
+
+    // Sort keyframes by time and check the names and dimensions they use
+    #[cfg(not(target_arch = "wasm32"))]
+    fn validate(&mut self) -> Result<(), String> {
+        for keyframe in &self.keyframes {
+            parse_coloring(&keyframe.coloring)?;
+            if !(4..=polytope_nd::MAX_DIMENSION).contains(&keyframe.dimension) {
+                return Err(format!("unsupported dimension {} (expected 4 to {})", keyframe.dimension, polytope_nd::MAX_DIMENSION));
+            }
+            let planes = if keyframe.dimension > 4 { polytope_nd::planes(keyframe.dimension).len() } else { 0 };
+            if !keyframe.nd_rotation.is_empty() && keyframe.nd_rotation.len() != planes {
+                return Err(format!("nd_rotation has {} angles, {}D has {} rotation planes", keyframe.nd_rotation.len(), keyframe.dimension, planes));
+            }
+        }
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut timeline: Timeline = toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        timeline.validate().map_err(|err| format!("{}: {}", path.display(), err))?;
+        Ok(timeline)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
+
+impl Keyframe {
+    // Blend from this keyframe towards `to` by the eased fraction `t`
+    fn apply(&self, to: &Keyframe, t: f32, viewer: &mut StereogramViewer) {
+        let orientation = self.rotation.to_orientation().interpolate(&to.rotation.to_orientation(), t);
+        orientation.apply(viewer);
+        viewer.perspective_distance = self.perspective_distance + (to.perspective_distance - self.perspective_distance) * t;
+        viewer.eye_separation = self.eye_separation + (to.eye_separation - self.eye_separation) * t;
+        viewer.morph = (self.morph + (to.morph - self.morph) * t).clamp(0.0, 1.0);
+
+        // Coloring and dimension are modes, not quantities: they switch once the next
+        // keyframe is reached
+        let reached = if t >= 1.0 { to } else { self };
+        if let Ok((depth_coloring, w_depth_coloring)) = parse_coloring(&reached.coloring) {
+            viewer.depth_coloring = depth_coloring;
+            viewer.w_depth_coloring = w_depth_coloring;
+        }
+        if viewer.dimension != reached.dimension {
+            viewer.set_dimension(reached.dimension);
+        }
+
+        // Plane angles above 4D blend between keyframes of the same dimension
+        let nd_rotation: Vec<f32> = if self.nd_rotation.len() == to.nd_rotation.len() {
+            self.nd_rotation.iter().zip(&to.nd_rotation).map(|(from, to)| from + (to - from) * t).collect()
+        } else {
+            reached.nd_rotation.clone()
+        };
+        if !nd_rotation.is_empty() && nd_rotation.len() == viewer.nd_rotation.len() {
+            viewer.nd_rotation = nd_rotation.iter().map(|angle| angle.to_radians()).collect();
+        }
+    }
+}
+
+// Timelines saved before the higher dimensions existed show the hypersolids
+fn default_dimension() -> usize {
+    4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 4] = [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut];
+
+    #[test]
+    fn easings_run_from_zero_to_one() {
+        for easing in EASINGS {
+            assert_eq!(easing.apply(0.0), 0.0, "{}", easing.name());
+            assert_eq!(easing.apply(1.0), 1.0, "{}", easing.name());
+            assert_eq!(easing.next().next().next().next(), easing);
+        }
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        // Slow at the ends it eases, fast at the others
+        assert!(Easing::EaseIn.apply(0.1) < 0.1 && Easing::EaseOut.apply(0.1) > 0.1);
+        assert!(Easing::EaseInOut.apply(0.1) < 0.1 && Easing::EaseInOut.apply(0.9) > 0.9);
+    }
+
+    // Keyframes at 0 s (distance 4, no coloring), 2 s (distance 8, W coloring, reached
+    // linearly) and 4 s (distance 6, Z coloring)
+    fn three_keyframes() -> Timeline {
+        let mut viewer = StereogramViewer::new();
+        let mut timeline = Timeline::default();
+        for (index, (distance, coloring)) in [(4.0, "none"), (8.0, "w"), (6.0, "z")].into_iter().enumerate() {
+            viewer.perspective_distance = distance;
+            (viewer.depth_coloring, viewer.w_depth_coloring) = parse_coloring(coloring).unwrap();
+            timeline.add_keyframe(&viewer);
+            if index == 1 {
+                while timeline.last_easing() != Some(Easing::Linear) {
+                    timeline.cycle_last_easing();
+                }
+            }
+        }
+        timeline
+    }
+
+    fn state_at(timeline: &Timeline, time: f32) -> (f32, &'static str) {
+        let mut viewer = StereogramViewer::new();
+        timeline.apply_at(time, &mut viewer);
+        (viewer.perspective_distance, coloring_key(&viewer))
+    }
+
+    #[test]
+    fn apply_at_holds_and_switches_at_keyframes() {
+        let timeline = three_keyframes();
+        assert_eq!(timeline.duration(), 2.0 * KEYFRAME_SPACING);
+
+        // Held before the first keyframe and after the last
+        assert_eq!(state_at(&timeline, -1.0), (4.0, "none"));
+        assert_eq!(state_at(&timeline, 0.0), (4.0, "none"));
+        assert_eq!(state_at(&timeline, 4.0), (6.0, "z"));
+        assert_eq!(state_at(&timeline, 10.0), (6.0, "z"));
+
+        // Linear into the second keyframe; its coloring only once it is reached
+        assert_eq!(state_at(&timeline, 0.5), (5.0, "none"));
+        assert_eq!(state_at(&timeline, 1.5), (7.0, "none"));
+        assert_eq!(state_at(&timeline, 2.0), (8.0, "w"));
+
+        // Eased in and out of the third: halfway in distance at the halfway time, slow near the ends
+        assert_eq!(state_at(&timeline, 3.0), (7.0, "w"));
+        let (distance, _) = state_at(&timeline, 2.2);
+        assert!(distance < 8.0 && distance > 7.9, "{}", distance);
+    }
+}
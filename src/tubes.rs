@@ -0,0 +1,170 @@
+// Ball-and-stick rendering: edges as lit tubes and vertices as balls
+//
+// The wireframe draws flat lines sorted by depth, which is all thin lines need. Thick
+// tubes pass in front of and behind each other partway along, so this style builds
+// real geometry in the rotated 3D space of the view instead, and the canvas draws it
+// per eye with a depth buffer, the look of a Zome model. Shading is worked out here
+// for every vertex of the geometry from a fixed light at the upper left front, so the
+// canvases only have to blend colors across each triangle.
+
+use macroquad::prelude::*;
+
+use crate::canvas::{BATCH_INDICES, BATCH_VERTICES};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RenderStyle {
+    #[default]
+    Wireframe, // Flat lines, drawn far to near
+    Tubes, // Tubes and balls with occlusion
+}
+
+impl RenderStyle {
+    pub const ALL: [RenderStyle; 2] = [RenderStyle::Wireframe, RenderStyle::Tubes];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&style| style == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderStyle::Wireframe => "Wireframe",
+            RenderStyle::Tubes => "Ball and stick",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            RenderStyle::Wireframe => "wireframe",
+            RenderStyle::Tubes => "tubes",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.key() == key)
+    }
+}
+
+// Radius of the tubes and the balls in pixels, at the depth of the view's center
+pub const TUBE_RADIUS: f32 = 3.0;
+pub const BALL_RADIUS: f32 = 6.0;
+
+// Facets around a tube, and around and from pole to pole of a ball
+const TUBE_SIDES: usize = 10;
+const BALL_SEGMENTS: usize = 14;
+const BALL_RINGS: usize = 8;
+
+// Light falling on a surface facing it squarely, and on any surface; the glint's
+// strength and how tight it is
+const DIFFUSE: f32 = 0.65;
+const AMBIENT: f32 = 0.35;
+const SPECULAR: f32 = 0.3;
+const SHININESS: i32 = 24;
+
+// Triangles of one view's tubes and balls, split into chunks that each fit into one
+// draw call with 16-bit indices. Kept from frame to frame like the other eye buffers.
+#[derive(Clone, Default)]
+pub struct SolidMesh {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    chunk_starts: Vec<(usize, usize)>, // First vertex and index of each chunk
+}
+
+impl SolidMesh {
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.chunk_starts.clear();
+    }
+
+    // Vertices and indices of each chunk, the indices counting from the chunk's first vertex
+    pub fn chunks(&self) -> impl Iterator<Item = (&[Vertex], &[u16])> {
+        self.chunk_starts.iter().enumerate().map(|(chunk, &(first_vertex, first_index))| {
+            let (end_vertex, end_index) = self
+                .chunk_starts
+                .get(chunk + 1)
+                .copied()
+                .unwrap_or((self.vertices.len(), self.indices.len()));
+            (&self.vertices[first_vertex..end_vertex], &self.indices[first_index..end_index])
+        })
+    }
+
+    // Index of the next vertex within its chunk, starting a new chunk when the current
+    // one has no room left for a part of this size
+    fn begin_part(&mut self, vertex_count: usize, index_count: usize) -> u16 {
+        let (first_vertex, first_index) = self.chunk_starts.last().copied().unwrap_or((0, 0));
+        let full = self.vertices.len() - first_vertex + vertex_count >= BATCH_VERTICES
+            || self.indices.len() - first_index + index_count >= BATCH_INDICES;
+        if self.chunk_starts.is_empty() || full {
+            self.chunk_starts.push((self.vertices.len(), self.indices.len()));
+        }
+        (self.vertices.len() - self.chunk_starts.last().map_or(0, |start| start.0)) as u16
+    }
+
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3, color: Color) {
+        self.vertices.push(Vertex {
+            position,
+            uv: Vec2::ZERO,
+            color: shade(color, normal).into(),
+            normal: Vec4::ZERO,
+        });
+    }
+
+    // Quads between two rings of `count` vertices each, starting at `first` and `first + count`
+    fn push_band(&mut self, first: u16, count: u16) {
+        for side in 0..count {
+            let next = (side + 1) % count;
+            let (a, b, c, d) = (first + side, first + next, first + count + next, first + count + side);
+            self.indices.extend([a, b, c, a, c, d]);
+        }
+    }
+
+    // An open tube from `start` to `end`, its color blending from one end to the other
+    pub fn tube(&mut self, (start, end): (Vec3, Vec3), radius: f32, (start_color, end_color): (Color, Color)) {
+        let axis = end - start;
+        let Some(direction) = axis.try_normalize() else {
+            return;
+        };
+        let (across, up) = direction.any_orthonormal_pair();
+        let first = self.begin_part(2 * TUBE_SIDES, 6 * TUBE_SIDES);
+        for (center, color) in [(start, start_color), (end, end_color)] {
+            for side in 0..TUBE_SIDES {
+                let angle = std::f32::consts::TAU * side as f32 / TUBE_SIDES as f32;
+                let normal = across * angle.cos() + up * angle.sin();
+                self.push_vertex(center + normal * radius, normal, color);
+            }
+        }
+        self.push_band(first, TUBE_SIDES as u16);
+    }
+
+    // A ball made of rings of vertices from pole to pole
+    pub fn ball(&mut self, center: Vec3, radius: f32, color: Color) {
+        let first = self.begin_part((BALL_RINGS + 1) * BALL_SEGMENTS, 6 * BALL_RINGS * BALL_SEGMENTS);
+        for ring in 0..=BALL_RINGS {
+            let latitude = std::f32::consts::PI * (ring as f32 / BALL_RINGS as f32 - 0.5);
+            for segment in 0..BALL_SEGMENTS {
+                let longitude = std::f32::consts::TAU * segment as f32 / BALL_SEGMENTS as f32;
+                let normal = Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin());
+                self.push_vertex(center + normal * radius, normal, color);
+            }
+        }
+        for ring in 0..BALL_RINGS {
+            self.push_band(first + (ring * BALL_SEGMENTS) as u16, BALL_SEGMENTS as u16);
+        }
+    }
+}
+
+// `color` lit at a surface facing `normal`. The view's Z axis points away from the
+// viewer, so the light comes from negative Z and the glint faces straight back.
+fn shade(color: Color, normal: Vec3) -> Color {
+    let light = Vec3::new(-0.4, 0.6, -0.7).normalize();
+    let halfway = (light + Vec3::NEG_Z).normalize();
+    let diffuse = AMBIENT + DIFFUSE * normal.dot(light).max(0.0);
+    let glint = SPECULAR * normal.dot(halfway).max(0.0).powi(SHININESS);
+    Color::new(
+        color.r * diffuse + glint,
+        color.g * diffuse + glint,
+        color.b * diffuse + glint,
+        color.a,
+    )
+}
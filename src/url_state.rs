@@ -0,0 +1,310 @@
+// Viewer state in the page URL (web build only)
+//
+// The query string holds the same settings as the native command-line options, e.g.
+// `?mode=4d&shape=tesseract&wcolor=1&sep=0.08`. It is read once at startup and
+// rewritten (without adding history entries) whenever the view changes, so any view
+// can be shared as a link. Rotation angles are only written while paused, since they
+// change every frame while the animation runs.
+//
+// The JavaScript side lives in index.html as a small miniquad plugin.
+
+use macroquad::prelude::*;
+
+use crate::colormap::{DepthRange, Palette};
+use crate::depth_cue::Fog;
+use crate::tubes::RenderStyle;
+use crate::wythoff::WythoffShape;
+use crate::{Hypersolid, PlatonicSolid, StereoLayout, StereogramViewer, VertexMarks};
+
+extern "C" {
+    fn stero3d_url_query_length() -> u32;
+    fn stero3d_url_query_copy(buffer: *mut u8, length: u32);
+    fn stero3d_url_query_replace(query: *const u8, length: u32);
+}
+
+// Lets the JavaScript plugin check that it matches this build
+#[no_mangle]
+pub extern "C" fn stero3d_url_state_crate_version() -> u32 {
+    1
+}
+
+// Minimum seconds between URL rewrites (browsers throttle history updates)
+const URL_UPDATE_INTERVAL: f64 = 0.5;
+
+// The page's query string, without the leading '?'
+pub fn read_page_query() -> String {
+    let length = unsafe { stero3d_url_query_length() };
+    let mut buffer = vec![0u8; length as usize];
+    unsafe { stero3d_url_query_copy(buffer.as_mut_ptr(), length) };
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+fn replace_page_query(query: &str) {
+    unsafe { stero3d_url_query_replace(query.as_ptr(), query.len() as u32) };
+}
+
+// Copy every recognised parameter onto the viewer, ignoring unknown or malformed ones
+pub fn apply_query(viewer: &mut StereogramViewer, query: &str) {
+    // The dimension goes in once the shape is known, and its plane angles after it
+    let mut dimension = None;
+    let mut nd_rotation = None;
+    for pair in query.trim_start_matches('?').split('&') {
+        let Some((key, value)) = pair.split_once('=') else {
+            continue;
+        };
+        let value = percent_decode(value);
+        match key {
+            "mode" => viewer.is_4d_mode = value == "4d",
+            "shape" => {
+                if let Some(solid) = PlatonicSolid::from_key(&value) {
+                    viewer.current_solid = solid;
+                } else if let Some(hypersolid) = Hypersolid::from_key(&value) {
+                    viewer.current_hypersolid = hypersolid;
+                }
+            }
+            "uniform" => {
+                if let Some(shape) = WythoffShape::from_key(&value) {
+                    viewer.select_uniform(shape);
+                }
+            }
+            "layout" => {
+                if let Some(layout) = StereoLayout::from_key(&value) {
+                    viewer.stereo_layout = layout;
+                }
+            }
+            "sep" => {
+                if let Ok(sep) = value.parse::<f32>() {
+                    viewer.eye_separation = sep.clamp(0.05, 0.3);
+                }
+            }
+            "dist" => {
+                if let Ok(distance) = value.parse::<f32>() {
+                    viewer.perspective_distance = distance.clamp(2.0, 20.0);
+                }
+            }
+            "ortho" => viewer.orthographic = value == "1",
+            "dark" => viewer.dark_background = value == "1",
+            "zcolor" => viewer.depth_coloring = value == "1",
+            "wcolor" => viewer.w_depth_coloring = value == "1",
+            "palette" => {
+                if let Some(palette) = Palette::from_key(&value) {
+                    viewer.palette = palette;
+                }
+            }
+            "range" => {
+                if let Some(range) = DepthRange::from_key(&value) {
+                    viewer.depth_range = range;
+                }
+            }
+            "width" => viewer.depth_width = value == "1",
+            "fog" => {
+                if let Some(fog) = Fog::from_key(&value) {
+                    viewer.fog = fog;
+                }
+            }
+            "marks" => {
+                if let Some(marks) = VertexMarks::from_key(&value) {
+                    viewer.vertex_marks = marks;
+                }
+            }
+            "style" => {
+                if let Some(style) = RenderStyle::from_key(&value) {
+                    viewer.render_style = style;
+                }
+            }
+            "guides" => viewer.show_guides = value == "1",
+            "ui" => viewer.show_ui = value == "1",
+            "paused" => viewer.is_paused = value == "1",
+            "dim" => dimension = value.parse::<usize>().ok(),
+            "morph" => {
+                if let Ok(morph) = value.parse::<f32>() {
+                    viewer.morph = morph.clamp(0.0, 1.0);
+                }
+            }
+            "cell" => {
+                // Counted from 1, like --cell
+                if let Some(cell) = value.parse::<usize>().ok().and_then(|cell| cell.checked_sub(1)) {
+                    viewer.selected_cell = Some(cell);
+                }
+            }
+            "isolate" => viewer.isolate_cell = value == "1",
+            "rotn" => nd_rotation = Some(value),
+            "rot" => {
+                if let Some([x, y, z]) = parse_degrees::<3>(&value) {
+                    viewer.rotation_x = x;
+                    viewer.rotation_y = y;
+                    viewer.rotation_z = z;
+                }
+            }
+            "rot4" => {
+                if let Some([xy, xz, yz, xw, yw, zw]) = parse_degrees::<6>(&value) {
+                    viewer.rotation_xy = xy;
+                    viewer.rotation_xz = xz;
+                    viewer.rotation_yz = yz;
+                    viewer.rotation_xw = xw;
+                    viewer.rotation_yw = yw;
+                    viewer.rotation_zw = zw;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(dimension) = dimension {
+        viewer.set_dimension(dimension);
+    }
+    if let Some(value) = nd_rotation {
+        if let Some(angles) = parse_degree_list(&value).filter(|angles| angles.len() == viewer.nd_rotation.len()) {
+            viewer.nd_rotation = angles;
+        }
+    }
+
+    // W coloring wins if a link asks for both, matching the W key behavior
+    if viewer.w_depth_coloring {
+        viewer.depth_coloring = false;
+    }
+}
+
+// Encode the shareable parts of the viewer state as a query string
+pub fn to_query(viewer: &StereogramViewer) -> String {
+    let flag = |on: bool| if on { "1" } else { "0" };
+    let shape = if viewer.is_4d_mode {
+        viewer.current_hypersolid.key()
+    } else {
+        viewer.current_solid.key().to_owned()
+    };
+
+    let mut query = format!(
+        "mode={}&shape={}&layout={}&sep={:.2}&dist={:.1}&ortho={}&dark={}&zcolor={}&wcolor={}&palette={}&range={}&width={}&fog={}&marks={}&style={}&guides={}",
+        if viewer.is_4d_mode { "4d" } else { "3d" },
+        shape,
+        viewer.stereo_layout.key(),
+        viewer.eye_separation,
+        viewer.perspective_distance,
+        flag(viewer.orthographic),
+        flag(viewer.dark_background),
+        flag(viewer.depth_coloring),
+        flag(viewer.w_depth_coloring),
+        viewer.palette.key(),
+        viewer.depth_range.key(),
+        flag(viewer.depth_width),
+        viewer.fog.key(),
+        viewer.vertex_marks.key(),
+        viewer.render_style.key(),
+        flag(viewer.show_guides),
+    );
+    if let Some((shape, _)) = viewer.uniform_shown() {
+        query.push_str(&format!("&uniform={}", shape.key()));
+    }
+    if viewer.shows_higher_dimension() {
+        query.push_str(&format!("&dim={}", viewer.dimension));
+    }
+    if viewer.morph > 0.0 {
+        query.push_str(&format!("&morph={:.2}", viewer.morph));
+    }
+    if let Some(cell) = viewer.selected_cell.filter(|_| viewer.is_4d_mode) {
+        query.push_str(&format!("&cell={}", cell + 1));
+        if viewer.isolate_cell {
+            query.push_str("&isolate=1");
+        }
+    }
+
+    if viewer.is_paused {
+        query.push_str("&paused=1");
+        if viewer.shows_higher_dimension() {
+            query.push_str(&format!("&rotn={}", format_degrees(&viewer.nd_rotation)));
+        }
+        if viewer.is_4d_mode {
+            query.push_str(&format!(
+                "&rot4={}",
+                format_degrees(&[
+                    viewer.rotation_xy,
+                    viewer.rotation_xz,
+                    viewer.rotation_yz,
+                    viewer.rotation_xw,
+                    viewer.rotation_yw,
+                    viewer.rotation_zw,
+                ])
+            ));
+        }
+        query.push_str(&format!(
+            "&rot={}",
+            format_degrees(&[viewer.rotation_x, viewer.rotation_y, viewer.rotation_z])
+        ));
+    }
+    query
+}
+
+// Rewrites the page URL when the shareable state changes, at most every URL_UPDATE_INTERVAL
+#[derive(Default)]
+pub struct UrlSync {
+    last_query: String,
+    last_update_time: f64,
+}
+
+impl UrlSync {
+    pub fn update(&mut self, viewer: &StereogramViewer) {
+        let now = get_time();
+        if now - self.last_update_time < URL_UPDATE_INTERVAL {
+            return;
+        }
+        let query = to_query(viewer);
+        if query != self.last_query {
+            replace_page_query(&query);
+            self.last_query = query;
+            self.last_update_time = now;
+        }
+    }
+}
+
+// Parse N comma-separated angles in degrees into radians
+fn parse_degrees<const N: usize>(value: &str) -> Option<[f32; N]> {
+    let mut angles = [0.0; N];
+    let mut parts = value.split(',');
+    for angle in angles.iter_mut() {
+        *angle = parts.next()?.trim().parse::<f32>().ok()?.to_radians();
+    }
+    parts.next().is_none().then_some(angles)
+}
+
+// Parse any number of comma-separated angles in degrees into radians
+fn parse_degree_list(value: &str) -> Option<Vec<f32>> {
+    value.split(',').map(|angle| angle.trim().parse::<f32>().ok().map(f32::to_radians)).collect()
+}
+
+// Radians to comma-separated degrees in 0..360, one decimal place
+fn format_degrees(angles: &[f32]) -> String {
+    angles
+        .iter()
+        .map(|angle| format!("{:.1}", angle.to_degrees().rem_euclid(360.0)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Decode %XX escapes and '+' (space) from a query string value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%' && i + 2 < bytes.len())
+            .then(|| std::str::from_utf8(&bytes[i + 1..i + 3]).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (None, b'+') => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (None, byte) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
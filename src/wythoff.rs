@@ -0,0 +1,652 @@
+// Uniform polyhedra and polychora from the Wythoff construction
+//
+// A Coxeter group is generated by reflections in three or four mirrors whose normals
+// meet at the angles of its Coxeter diagram. Ringing some nodes of the diagram puts a
+// seed point at distance 1 from the ringed mirrors and on the others; the orbit of the
+// seed under the group is the vertex set of a uniform polytope. Its edges are the
+// orbits of the seed's mirror images in the ringed mirrors (so every edge has length 2,
+// like the tesseract table), and its faces are the orbits of the polygons that each
+// pair of mirrors traces around the seed. The cells of a polychoron come the same way
+// from the polyhedra that each three of its mirrors make of the seed.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use macroquad::prelude::*;
+
+use crate::geometry::Mesh;
+use crate::Vec4D;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoxeterGroup {
+    A3,
+    B3,
+    H3,
+    A4,
+    B4,
+    D4,
+    F4,
+    H4,
+}
+
+impl CoxeterGroup {
+    pub const ALL: [CoxeterGroup; 8] = [
+        CoxeterGroup::A3,
+        CoxeterGroup::B3,
+        CoxeterGroup::H3,
+        CoxeterGroup::A4,
+        CoxeterGroup::B4,
+        CoxeterGroup::D4,
+        CoxeterGroup::F4,
+        CoxeterGroup::H4,
+    ];
+
+    // Next group of the same rank
+    pub fn next(self) -> Self {
+        match self {
+            CoxeterGroup::A3 => CoxeterGroup::B3,
+            CoxeterGroup::B3 => CoxeterGroup::H3,
+            CoxeterGroup::H3 => CoxeterGroup::A3,
+            CoxeterGroup::A4 => CoxeterGroup::B4,
+            CoxeterGroup::B4 => CoxeterGroup::D4,
+            CoxeterGroup::D4 => CoxeterGroup::F4,
+            CoxeterGroup::F4 => CoxeterGroup::H4,
+            CoxeterGroup::H4 => CoxeterGroup::A4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CoxeterGroup::A3 => "A3",
+            CoxeterGroup::B3 => "B3",
+            CoxeterGroup::H3 => "H3",
+            CoxeterGroup::A4 => "A4",
+            CoxeterGroup::B4 => "B4",
+            CoxeterGroup::D4 => "D4",
+            CoxeterGroup::F4 => "F4",
+            CoxeterGroup::H4 => "H4",
+        }
+    }
+
+    // Coxeter's bracket notation, the branch orders of the diagram
+    pub fn bracket(self) -> &'static str {
+        match self {
+            CoxeterGroup::A3 => "[3,3]",
+            CoxeterGroup::B3 => "[4,3]",
+            CoxeterGroup::H3 => "[5,3]",
+            CoxeterGroup::A4 => "[3,3,3]",
+            CoxeterGroup::B4 => "[4,3,3]",
+            CoxeterGroup::D4 => "[3^1,1,1]",
+            CoxeterGroup::F4 => "[3,4,3]",
+            CoxeterGroup::H4 => "[5,3,3]",
+        }
+    }
+
+    // Number of elements: reflections and rotations together
+    pub fn order(self) -> usize {
+        match self {
+            CoxeterGroup::A3 => 24,
+            CoxeterGroup::B3 => 48,
+            CoxeterGroup::H3 => 120,
+            CoxeterGroup::A4 => 120,
+            CoxeterGroup::B4 => 384,
+            CoxeterGroup::D4 => 192,
+            CoxeterGroup::F4 => 1152,
+            CoxeterGroup::H4 => 14400,
+        }
+    }
+
+    // Number of mirrors, which is also the dimension of the polytopes
+    pub fn rank(self) -> usize {
+        match self {
+            CoxeterGroup::A3 | CoxeterGroup::B3 | CoxeterGroup::H3 => 3,
+            _ => 4,
+        }
+    }
+
+    // Branches of the Coxeter diagram as (node, node, m): the mirrors meet at π/m.
+    // Nodes not joined by a branch are perpendicular. D4 has its branch point at node 1.
+    fn branches(self) -> &'static [(usize, usize, usize)] {
+        match self {
+            CoxeterGroup::A3 => &[(0, 1, 3), (1, 2, 3)],
+            CoxeterGroup::B3 => &[(0, 1, 4), (1, 2, 3)],
+            CoxeterGroup::H3 => &[(0, 1, 5), (1, 2, 3)],
+            CoxeterGroup::A4 => &[(0, 1, 3), (1, 2, 3), (2, 3, 3)],
+            CoxeterGroup::B4 => &[(0, 1, 4), (1, 2, 3), (2, 3, 3)],
+            CoxeterGroup::D4 => &[(0, 1, 3), (1, 2, 3), (1, 3, 3)],
+            CoxeterGroup::F4 => &[(0, 1, 3), (1, 2, 4), (2, 3, 3)],
+            CoxeterGroup::H4 => &[(0, 1, 5), (1, 2, 3), (2, 3, 3)],
+        }
+    }
+
+    fn branch_order(self, a: usize, b: usize) -> usize {
+        self.branches()
+            .iter()
+            .find(|&&(i, j, _)| (i, j) == (a, b) || (j, i) == (a, b))
+            .map_or(2, |&(_, _, m)| m)
+    }
+
+    // The regular polytopes at the two ends of a linear diagram (node 0 ringed, last node ringed)
+    fn regular_names(self) -> (&'static str, &'static str) {
+        match self {
+            CoxeterGroup::A3 => ("tetrahedron", "tetrahedron"),
+            CoxeterGroup::B3 => ("cube", "octahedron"),
+            CoxeterGroup::H3 => ("dodecahedron", "icosahedron"),
+            CoxeterGroup::A4 => ("5-cell", "5-cell"),
+            CoxeterGroup::B4 => ("tesseract", "16-cell"),
+            CoxeterGroup::D4 => ("demitesseract", "demitesseract"),
+            CoxeterGroup::F4 => ("24-cell", "24-cell"),
+            CoxeterGroup::H4 => ("120-cell", "600-cell"),
+        }
+    }
+}
+
+// Common names of the uniform polyhedra by ring pattern (bit i rings node i)
+const A3_NAMES: [&str; 7] = [
+    "Tetrahedron", "Octahedron", "Truncated tetrahedron", "Tetrahedron",
+    "Cuboctahedron", "Truncated tetrahedron", "Truncated octahedron",
+];
+const B3_NAMES: [&str; 7] = [
+    "Cube", "Cuboctahedron", "Truncated cube", "Octahedron",
+    "Rhombicuboctahedron", "Truncated octahedron", "Truncated cuboctahedron",
+];
+const H3_NAMES: [&str; 7] = [
+    "Dodecahedron", "Icosidodecahedron", "Truncated dodecahedron", "Icosahedron",
+    "Rhombicosidodecahedron", "Truncated icosahedron", "Truncated icosidodecahedron",
+];
+
+// Operations on a linear four-node diagram by ring pattern, and whether they are
+// named after the dual (the regular polytope of the last node)
+const RANK_4_OPERATIONS: [(&str, bool); 15] = [
+    ("", false),
+    ("Rectified ", false),
+    ("Truncated ", false),
+    ("Rectified ", true),
+    ("Cantellated ", false),
+    ("Bitruncated ", false),
+    ("Cantitruncated ", false),
+    ("", true),
+    ("Runcinated ", false),
+    ("Cantellated ", true),
+    ("Runcitruncated ", false),
+    ("Truncated ", true),
+    ("Runcitruncated ", true),
+    ("Cantitruncated ", true),
+    ("Omnitruncated ", false),
+];
+
+// A group with a ring pattern: bit i set rings node i
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WythoffShape {
+    pub group: CoxeterGroup,
+    pub rings: u8,
+}
+
+impl WythoffShape {
+    pub fn new(group: CoxeterGroup, rings: u8) -> Self {
+        Self { group, rings: rings.clamp(1, (1 << group.rank()) - 1) }
+    }
+
+    // Next ring pattern of the same group, skipping the empty one
+    pub fn next_rings(self) -> Self {
+        let patterns = (1u8 << self.group.rank()) - 1;
+        Self { group: self.group, rings: self.rings % patterns + 1 }
+    }
+
+    fn is_ringed(self, node: usize) -> bool {
+        self.rings & (1 << node) != 0
+    }
+
+    pub fn name(self) -> String {
+        let index = self.rings as usize - 1;
+        match self.group {
+            CoxeterGroup::A3 => A3_NAMES[index].to_owned(),
+            CoxeterGroup::B3 => B3_NAMES[index].to_owned(),
+            CoxeterGroup::H3 => H3_NAMES[index].to_owned(),
+            CoxeterGroup::D4 => {
+                // The branched diagram has no linear naming; use Coxeter's t-notation
+                format!("Demitesseract t{}", self.ringed_nodes().join(","))
+            }
+            group => {
+                let (operation, dual) = RANK_4_OPERATIONS[index];
+                let (regular, dual_regular) = group.regular_names();
+                let base = if dual { dual_regular } else { regular };
+                if operation.is_empty() {
+                    capitalized(base)
+                } else {
+                    format!("{}{}", operation, base)
+                }
+            }
+        }
+    }
+
+    // Coxeter diagram with ringed nodes as x and the others as o, e.g. "x4o3o3x";
+    // D4 is written with its third arm after "*b", as in "x3o3o *b3x"
+    pub fn diagram(self) -> String {
+        let node = |index: usize| if self.is_ringed(index) { 'x' } else { 'o' };
+        if self.group == CoxeterGroup::D4 {
+            return format!("{}3{}3{} *b3{}", node(0), node(1), node(2), node(3));
+        }
+        let mut diagram = String::from(node(0));
+        for index in 1..self.group.rank() {
+            diagram.push_str(&self.group.branch_order(index - 1, index).to_string());
+            diagram.push(node(index));
+        }
+        diagram
+    }
+
+    // Schläfli symbol: "{4,3}" for a regular shape from either end of a linear diagram
+    // and Coxeter's t-notation for the rest, e.g. "t0,1{4,3}" for the truncated cube
+    pub fn schlafli(self) -> String {
+        let rank = self.group.rank();
+        let orders: Vec<String> = (1..rank).map(|node| self.group.branch_order(node - 1, node).to_string()).collect();
+        if self.group == CoxeterGroup::D4 {
+            return format!("t{}{{3,3^1,1}}", self.ringed_nodes().join(","));
+        }
+        if self.rings == 1 {
+            format!("{{{}}}", orders.join(","))
+        } else if self.rings == 1 << (rank - 1) {
+            let reversed: Vec<String> = orders.into_iter().rev().collect();
+            format!("{{{}}}", reversed.join(","))
+        } else {
+            format!("t{}{{{}}}", self.ringed_nodes().join(","), orders.join(","))
+        }
+    }
+
+    fn ringed_nodes(self) -> Vec<String> {
+        (0..self.group.rank()).filter(|&node| self.is_ringed(node)).map(|node| node.to_string()).collect()
+    }
+
+    // Short identifier such as "b4-1001", listing the rings from node 0
+    pub fn key(self) -> String {
+        let rings: String = (0..self.group.rank()).map(|node| if self.is_ringed(node) { '1' } else { '0' }).collect();
+        format!("{}-{}", self.group.name().to_ascii_lowercase(), rings)
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        let (group, rings) = key.split_once('-')?;
+        let group = CoxeterGroup::ALL.into_iter().find(|candidate| candidate.name().eq_ignore_ascii_case(group))?;
+        if rings.len() != group.rank() || !rings.chars().all(|c| c == '0' || c == '1') {
+            return None;
+        }
+        let rings = rings.chars().enumerate().fold(0u8, |bits, (node, c)| if c == '1' { bits | (1 << node) } else { bits });
+        (rings != 0).then_some(Self { group, rings })
+    }
+
+    // Whether every piece of the diagram left after removing `node` still has a ring. A
+    // piece without one keeps the seed fixed, so the other mirrors would only make a
+    // lower-dimensional face of it.
+    fn has_cell_without(self, node: usize) -> bool {
+        let rank = self.group.rank();
+        let mut piece: Vec<usize> = (0..rank).collect();
+        for _ in 0..rank {
+            for &(a, b, _) in self.group.branches() {
+                if a != node && b != node {
+                    let lowest = piece[a].min(piece[b]);
+                    (piece[a], piece[b]) = (lowest, lowest);
+                }
+            }
+        }
+        (0..rank)
+            .filter(|&other| other != node)
+            .all(|other| (0..rank).any(|ringed| ringed != node && piece[ringed] == piece[other] && self.is_ringed(ringed)))
+    }
+
+    // Seed point: distance 1 from every ringed mirror, on every other one
+    fn seed(self, normals: &[Vec<f64>]) -> Vec<f64> {
+        let rank = self.group.rank();
+        let targets: Vec<f64> = (0..rank).map(|node| if self.is_ringed(node) { 1.0 } else { 0.0 }).collect();
+        let mut seed = vec![0.0; rank];
+        for row in 0..rank {
+            let known: f64 = (0..row).map(|column| normals[row][column] * seed[column]).sum();
+            seed[row] = (targets[row] - known) / normals[row][row];
+        }
+        seed
+    }
+
+    // Run the construction
+    pub fn build(self) -> Polytope {
+        let rank = self.group.rank();
+        let normals = mirror_normals(self.group);
+        let seed = self.seed(&normals);
+
+        // Vertex orbit, remembering where each mirror sends each vertex
+        let mut index = PointIndex::default();
+        let mut vertices = vec![seed.clone()];
+        index.insert(&seed, 0);
+        let mut images: Vec<Vec<usize>> = vec![Vec::new(); rank];
+        let mut next = 0;
+        while next < vertices.len() {
+            for (mirror, normal) in normals.iter().enumerate() {
+                let image = reflect(&vertices[next], normal);
+                let found = index.find(&image).unwrap_or_else(|| {
+                    index.insert(&image, vertices.len());
+                    vertices.push(image);
+                    vertices.len() - 1
+                });
+                images[mirror].push(found);
+            }
+            next += 1;
+        }
+
+        // Edges: the seed joined to its images in the ringed mirrors, and everything the group makes of that
+        let edge_seeds = (0..rank).filter(|&node| self.is_ringed(node)).map(|node| vec![0, images[node][0]]);
+        let edges = orbit(edge_seeds, &images)
+            .into_iter()
+            .map(|edge| (edge[0], edge[1]))
+            .collect();
+
+        // Faces: the polygon each pair of mirrors traces around the seed
+        let mut face_seeds = Vec::new();
+        for first in 0..rank {
+            for second in first + 1..rank {
+                let polygon = trace_polygon(&images, first, second, self.group.branch_order(first, second));
+                if polygon.len() >= 3 {
+                    face_seeds.push(polygon);
+                }
+            }
+        }
+        let faces = orbit(face_seeds.into_iter(), &images);
+
+        // Cells of a polychoron: what the other three mirrors make of the seed, for each node
+        // that can be left out without cutting off a piece of the diagram with no ring
+        let cell_seeds = (0..rank)
+            .filter(|&node| rank == 4 && self.has_cell_without(node))
+            .map(|node| subgroup_orbit(&images, node));
+        let cells = orbit(cell_seeds, &images);
+
+        Polytope {
+            vertices: vertices.iter().map(|vertex| vertex.iter().map(|&x| x as f32).collect()).collect(),
+            edges,
+            faces,
+            cells,
+        }
+    }
+}
+
+// Every shape in a group's catalog, ring pattern by ring pattern
+pub fn catalog(group: CoxeterGroup) -> Vec<WythoffShape> {
+    (1..1u8 << group.rank()).map(|rings| WythoffShape { group, rings }).collect()
+}
+
+// Result of the construction; face vertices are listed in order around the face, cells
+// (polychora only) are plain vertex sets
+#[derive(Clone, Debug, Default)]
+pub struct Polytope {
+    pub vertices: Vec<Vec<f32>>,
+    pub edges: Vec<(usize, usize)>,
+    pub faces: Vec<Vec<usize>>,
+    pub cells: Vec<Vec<usize>>,
+}
+
+impl Polytope {
+    // Scaled to the circumradius of the cube, like the Platonic solids
+    pub fn mesh_3d(&self) -> Mesh<Vec3> {
+        let scale = 3f32.sqrt() / self.circumradius();
+        Mesh {
+            vertices: self.vertices.iter().map(|vertex| Vec3::new(vertex[0], vertex[1], vertex[2]) * scale).collect(),
+            edges: self.edges.clone(),
+        }
+    }
+
+    // Scaled to the circumradius of the tesseract, like the other hypersolids
+    pub fn mesh_4d(&self) -> Mesh<Vec4D> {
+        let scale = 2.0 / self.circumradius();
+        Mesh {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|vertex| Vec4D::new(vertex[0] * scale, vertex[1] * scale, vertex[2] * scale, vertex[3] * scale))
+                .collect(),
+            edges: self.edges.clone(),
+        }
+    }
+
+    // Every vertex is at the same distance from the center
+    pub fn circumradius(&self) -> f32 {
+        self.vertices.first().map_or(1.0, |vertex| vertex.iter().map(|coordinate| coordinate * coordinate).sum::<f32>().sqrt())
+    }
+}
+
+// Unit mirror normals with the diagram's angles between them: the rows of the
+// Cholesky factor of the Gram matrix, so each normal only uses the first coordinates
+fn mirror_normals(group: CoxeterGroup) -> Vec<Vec<f64>> {
+    let rank = group.rank();
+    let gram = |a: usize, b: usize| {
+        if a == b {
+            1.0
+        } else {
+            -(std::f64::consts::PI / group.branch_order(a, b) as f64).cos()
+        }
+    };
+    let mut normals = vec![vec![0.0; rank]; rank];
+    for row in 0..rank {
+        for column in 0..=row {
+            let known: f64 = (0..column).map(|k| normals[row][k] * normals[column][k]).sum();
+            normals[row][column] = if row == column {
+                (gram(row, row) - known).sqrt()
+            } else {
+                (gram(row, column) - known) / normals[column][column]
+            };
+        }
+    }
+    normals
+}
+
+fn reflect(point: &[f64], normal: &[f64]) -> Vec<f64> {
+    let along: f64 = point.iter().zip(normal).map(|(x, n)| x * n).sum();
+    point.iter().zip(normal).map(|(x, n)| x - 2.0 * along * n).collect()
+}
+
+// Vertices of the polygon around the seed (vertex 0) made by two mirrors meeting at
+// π/m, in order around it. The rotation ρ = R_first R_second turns the polygon by one
+// step, so the seed and its image in the first mirror go round as ρ^k v and ρ^k R_first v.
+// The polygon collapses to a point when neither mirror moves the seed, and to an
+// edge when the mirrors are perpendicular and only one of them does.
+fn trace_polygon(images: &[Vec<usize>], first: usize, second: usize, m: usize) -> Vec<usize> {
+    let rotate = |vertex: usize| images[first][images[second][vertex]];
+    let mut polygon = vec![0];
+    let (mut seed, mut mirrored) = (0, images[first][0]);
+    for _ in 0..m {
+        for vertex in [seed, mirrored] {
+            if polygon.last() != Some(&vertex) {
+                polygon.push(vertex);
+            }
+        }
+        (seed, mirrored) = (rotate(seed), rotate(mirrored));
+    }
+    if polygon.len() > 1 && polygon.last() == Some(&0) {
+        polygon.pop();
+    }
+    polygon
+}
+
+// Vertices the seed reaches through every mirror but one
+fn subgroup_orbit(images: &[Vec<usize>], left_out: usize) -> Vec<usize> {
+    let mut reached = vec![0];
+    let mut next = 0;
+    while next < reached.len() {
+        for (mirror, mirror_images) in images.iter().enumerate() {
+            let image = mirror_images[reached[next]];
+            if mirror != left_out && !reached.contains(&image) {
+                reached.push(image);
+            }
+        }
+        next += 1;
+    }
+    reached
+}
+
+// All images of some vertex lists under the group, each once. Lists count as the same
+// when they hold the same vertices, whatever their order.
+fn orbit(seeds: impl Iterator<Item = Vec<usize>>, images: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    let mut queue = VecDeque::new();
+    let canonical = |list: &Vec<usize>| {
+        let mut sorted = list.clone();
+        sorted.sort_unstable();
+        sorted
+    };
+    for seed in seeds {
+        if seen.insert(canonical(&seed)) {
+            queue.push_back(seed);
+        }
+    }
+    while let Some(list) = queue.pop_front() {
+        for mirror_images in images {
+            let image: Vec<usize> = list.iter().map(|&vertex| mirror_images[vertex]).collect();
+            if seen.insert(canonical(&image)) {
+                queue.push_back(image);
+            }
+        }
+        found.push(list);
+    }
+    found
+}
+
+// Finds points already generated, allowing for rounding error: points are filed in a
+// grid and looked up in the neighboring cells too
+#[derive(Default)]
+struct PointIndex {
+    cells: HashMap<Vec<i64>, Vec<(Vec<f64>, usize)>>,
+}
+
+impl PointIndex {
+    const CELL_SIZE: f64 = 0.5; // Well below the edge length of 2
+    const TOLERANCE: f64 = 1e-6;
+
+    fn cell(point: &[f64]) -> Vec<i64> {
+        point.iter().map(|x| (x / Self::CELL_SIZE).floor() as i64).collect()
+    }
+
+    fn insert(&mut self, point: &[f64], index: usize) {
+        self.cells.entry(Self::cell(point)).or_default().push((point.to_vec(), index));
+    }
+
+    fn find(&self, point: &[f64]) -> Option<usize> {
+        let home = Self::cell(point);
+        let neighbors = 3usize.pow(home.len() as u32);
+        (0..neighbors).find_map(|neighbor| {
+            let mut offset = neighbor;
+            let cell: Vec<i64> = home
+                .iter()
+                .map(|&coordinate| {
+                    let shift = (offset % 3) as i64 - 1;
+                    offset /= 3;
+                    coordinate + shift
+                })
+                .collect();
+            self.cells.get(&cell)?.iter().find_map(|(other, index)| {
+                let distance: f64 = other.iter().zip(point).map(|(a, b)| (a - b) * (a - b)).sum();
+                (distance < Self::TOLERANCE).then_some(*index)
+            })
+        })
+    }
+}
+
+fn capitalized(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every catalog shape, except that H4 only gives the 120-cell and 600-cell: its other
+    // shapes, up to 14400 vertices, take too long to build in a debug build
+    fn shapes() -> impl Iterator<Item = WythoffShape> {
+        CoxeterGroup::ALL
+            .into_iter()
+            .flat_map(catalog)
+            .filter(|shape| shape.group != CoxeterGroup::H4 || [0b0001, 0b1000].contains(&shape.rings))
+    }
+
+    // Order of the subgroup generated by some of the group's mirrors: the size of the
+    // orbit of a point on none of them, which only the identity fixes
+    fn subgroup_order(group: CoxeterGroup, mirrors: &[usize]) -> usize {
+        let normals = mirror_normals(group);
+        let generic = WythoffShape::new(group, u8::MAX).seed(&normals);
+        let mut index = PointIndex::default();
+        index.insert(&generic, 0);
+        let mut points = vec![generic];
+        let mut next = 0;
+        while next < points.len() {
+            for &mirror in mirrors {
+                let image = reflect(&points[next], &normals[mirror]);
+                if index.find(&image).is_none() {
+                    index.insert(&image, points.len());
+                    points.push(image);
+                }
+            }
+            next += 1;
+        }
+        points.len()
+    }
+
+    #[test]
+    fn vertex_orbits_are_the_group_order_over_the_stabilizer() {
+        for shape in shapes() {
+            // The seed is fixed by exactly the mirrors it lies on, the unringed ones
+            let group = shape.group;
+            let unringed: Vec<usize> = (0..group.rank()).filter(|&node| !shape.is_ringed(node)).collect();
+            let stabilizer = subgroup_order(group, &unringed);
+            assert_eq!(shape.build().vertices.len() * stabilizer, group.order(), "{}", shape.key());
+        }
+    }
+
+    #[test]
+    fn uniform_polytopes_have_the_euler_characteristic_of_their_dimension() {
+        for shape in shapes() {
+            let rank = shape.group.rank();
+            let polytope = shape.build();
+            let counts = [polytope.vertices.len(), polytope.edges.len(), polytope.faces.len(), polytope.cells.len()];
+            let euler: i64 = counts[..rank]
+                .iter()
+                .enumerate()
+                .map(|(dimension, &count)| if dimension.is_multiple_of(2) { count as i64 } else { -(count as i64) })
+                .sum();
+            assert_eq!(euler, if rank.is_multiple_of(2) { 0 } else { 2 }, "{} has counts {:?}", shape.key(), counts);
+        }
+    }
+
+    #[test]
+    fn every_edge_has_length_two() {
+        for shape in catalog(CoxeterGroup::B4).into_iter().chain(catalog(CoxeterGroup::H3)) {
+            let polytope = shape.build();
+            for &(start, end) in &polytope.edges {
+                let length: f32 = polytope.vertices[start]
+                    .iter()
+                    .zip(&polytope.vertices[end])
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f32>()
+                    .sqrt();
+                assert!((length - 2.0).abs() < 1e-4, "{} has an edge of length {}", shape.key(), length);
+            }
+        }
+    }
+
+    #[test]
+    fn regular_polytopes_have_their_known_counts() {
+        let counts = |key: &str| {
+            let polytope = WythoffShape::from_key(key).unwrap().build();
+            (polytope.vertices.len(), polytope.edges.len(), polytope.faces.len(), polytope.cells.len())
+        };
+        assert_eq!(counts("a4-1000"), (5, 10, 10, 5));
+        assert_eq!(counts("b4-1000"), (16, 32, 24, 8));
+        assert_eq!(counts("f4-1000"), (24, 96, 96, 24));
+        assert_eq!(counts("h4-0001"), (120, 720, 1200, 600));
+        assert_eq!(counts("h4-1000"), (600, 1200, 720, 120));
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        for group in CoxeterGroup::ALL {
+            for shape in catalog(group) {
+                assert_eq!(WythoffShape::from_key(&shape.key()), Some(shape));
+            }
+        }
+        assert_eq!(WythoffShape::from_key("b4-0000"), None);
+        assert_eq!(WythoffShape::from_key("b3-1000"), None);
+    }
+}